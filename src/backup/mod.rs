@@ -0,0 +1,587 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::info;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::server::{lockfile::Lockfile, world};
+
+mod remote;
+
+const BACKUP_DIR: &str = ".mup/backups";
+const INDEX_FILE: &str = "index.json";
+const DAY: u64 = 24 * 60 * 60;
+const CONFIG_EXTENSIONS: [&str; 6] = ["properties", "json", "yml", "yaml", "toml", "txt"];
+
+#[derive(Debug, Subcommand)]
+pub enum Backup {
+    /// Archive the worlds, configs and plugins of the server
+    Create {
+        /// Only archive the worlds
+        #[arg(long, action)]
+        worlds_only: bool,
+    },
+
+    /// Replace the server files with the contents of a backup
+    Restore {
+        /// ID of the backup, see `mup backup list`
+        id: String,
+
+        /// Do not ask for confirmation
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+
+    /// List the backups of this server
+    List,
+
+    /// Upload a backup to a remote configured under [backup.remotes]
+    Push {
+        /// ID of the backup, defaults to the most recent one
+        id: Option<String>,
+
+        /// Name of the remote, required when several are configured
+        #[arg(short, long)]
+        remote: Option<String>,
+    },
+
+    /// Download a backup from a remote so it can be restored
+    Pull {
+        /// ID of the backup
+        id: String,
+
+        /// Name of the remote, required when several are configured
+        #[arg(short, long)]
+        remote: Option<String>,
+    },
+
+    /// Delete the backups that fall outside of the retention policy
+    Prune {
+        /// Keep this many of the most recent backups, defaults to the configured retention
+        #[arg(long)]
+        keep_last: Option<usize>,
+
+        /// Keep the newest backup of each of this many days
+        #[arg(long)]
+        keep_daily: Option<usize>,
+
+        /// Keep the newest backup of each of this many weeks
+        #[arg(long)]
+        keep_weekly: Option<usize>,
+
+        /// Only print which backups would be deleted
+        #[arg(long, action)]
+        dry_run: bool,
+    },
+}
+
+struct Policy {
+    last: usize,
+    daily: usize,
+    weekly: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    id: String,
+    created: u64,
+    file: String,
+    worlds_only: bool,
+    paths: Vec<String>,
+    size: u64,
+    sha256: String,
+}
+
+pub fn action(backup: &Backup) -> Result<()> {
+    match backup {
+        Backup::Create { worlds_only } => create(*worlds_only),
+        Backup::Restore { id, yes } => restore(id, *yes),
+        Backup::List => list(),
+        Backup::Push { id, remote } => push(id.as_deref(), remote.as_deref()),
+        Backup::Pull { id, remote } => pull(id, remote.as_deref()),
+        Backup::Prune {
+            keep_last,
+            keep_daily,
+            keep_weekly,
+            dry_run,
+        } => {
+            let retention = &mup::config::get().backup;
+
+            prune(
+                &Policy {
+                    last: keep_last.unwrap_or(retention.keep_last),
+                    daily: keep_daily.unwrap_or(retention.keep_daily),
+                    weekly: keep_weekly.unwrap_or(retention.keep_weekly),
+                },
+                *dry_run,
+            )
+        }
+    }
+}
+
+fn create(worlds_only: bool) -> Result<()> {
+    let mut paths = world::active_dirs()?;
+    if paths.is_empty() {
+        return Err(anyhow!(
+            "no worlds to back up, has the server been started?"
+        ));
+    }
+
+    if !worlds_only {
+        paths.extend(config_paths()?);
+    }
+
+    let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut index = load_index()?;
+
+    // Backups taken within the same second get a suffix instead of overwriting each other
+    let mut id = created.to_string();
+    let mut n = 1;
+    while index.iter().any(|e| e.id == id) {
+        id = format!("{created}-{n}");
+        n += 1;
+    }
+
+    let file = format!("{id}.tar.gz");
+    let archive = Path::new(BACKUP_DIR).join(&file);
+    fs::create_dir_all(BACKUP_DIR)?;
+
+    info!("archiving {} to {}", paths.join(", "), archive.display());
+
+    let relative: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let encoder = GzEncoder::new(
+        BufWriter::new(File::create(&archive)?),
+        Compression::default(),
+    );
+    mup::tar::write(encoder, Path::new("."), &relative)?.finish()?;
+
+    let entry = Entry {
+        id,
+        created,
+        file,
+        worlds_only,
+        paths,
+        size: fs::metadata(&archive)?.len(),
+        sha256: mup::hash_file::<Sha256>(&archive)?,
+    };
+
+    println!(
+        "created backup {} ({})",
+        entry.id,
+        mup::format_size(entry.size)
+    );
+
+    index.push(entry);
+    save_index(&index)
+}
+
+// Configuration files at the top of the server directory, the `config` folder used by mods
+// and every folder the lockfile installs projects to, which also hold plugin configs
+fn config_paths() -> Result<Vec<String>> {
+    let mut paths = vec![];
+
+    for entry in fs::read_dir(".")? {
+        let path = entry?.path();
+        let is_config = path.is_file()
+            && path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| CONFIG_EXTENSIONS.iter().any(|c| e.eq_ignore_ascii_case(c)));
+
+        if is_config {
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                paths.push(name.to_string());
+            }
+        }
+    }
+
+    paths.sort();
+
+    if Path::new("config").is_dir() {
+        paths.push(String::from("config"));
+    }
+
+    if let Some(lockfile) = Lockfile::existing()? {
+        for location in lockfile.loader.mod_locations() {
+            if Path::new(location).is_dir() && !paths.iter().any(|p| p == location) {
+                paths.push(location.to_string());
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+fn restore(id: &str, yes: bool) -> Result<()> {
+    let index = load_index()?;
+    let entry = index
+        .iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| anyhow!("no backup with id {id}, see `mup backup list`"))?;
+
+    let archive = Path::new(BACKUP_DIR).join(&entry.file);
+    if !archive.exists() {
+        return Err(anyhow!("archive {} is missing", archive.display()));
+    }
+
+    info!("verifying {}", archive.display());
+
+    if mup::hash_file::<Sha256>(&archive)? != entry.sha256 {
+        return Err(anyhow!(
+            "archive {} does not match its checksum, refusing to restore it",
+            archive.display()
+        ));
+    }
+
+    // Everything is extracted before the server is touched, so a damaged archive leaves it as is
+    let staging = Path::new(BACKUP_DIR).join(format!(".restore-{id}"));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    let extracted = extract(&archive, &staging, &entry.paths);
+    if let Err(e) = extracted {
+        fs::remove_dir_all(&staging)?;
+        return Err(e);
+    }
+
+    println!(
+        "this will replace {} with the contents of backup {id}",
+        entry.paths.join(", ")
+    );
+
+    if !yes && !mup::confirm("Restore backup?")? {
+        fs::remove_dir_all(&staging)?;
+        return Ok(());
+    }
+
+    for path in &entry.paths {
+        let current = Path::new(path);
+
+        if current.is_dir() {
+            fs::remove_dir_all(current)?;
+        } else if current.exists() {
+            fs::remove_file(current)?;
+        }
+
+        info!("restoring {path}");
+
+        fs::rename(staging.join(path), current)?;
+    }
+
+    fs::remove_dir_all(&staging)?;
+
+    println!("restored backup {id}");
+
+    Ok(())
+}
+
+fn extract(archive: &Path, staging: &Path, paths: &[String]) -> Result<()> {
+    let mut decoder = GzDecoder::new(BufReader::new(File::open(archive)?));
+    mup::tar::extract(&mut decoder, staging)?;
+
+    // Reading to the end makes the decoder check the gzip trailer
+    io::copy(&mut decoder, &mut io::sink())?;
+
+    if let Some(missing) = paths.iter().find(|p| !staging.join(p).exists()) {
+        return Err(anyhow!("archive is incomplete, {missing} is missing"));
+    }
+
+    Ok(())
+}
+
+fn list() -> Result<()> {
+    let index = load_index()?;
+
+    if index.is_empty() {
+        println!("no backups");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = index
+        .iter()
+        .map(|e| {
+            vec![
+                e.id.clone(),
+                String::from(if e.worlds_only { "worlds" } else { "full" }),
+                mup::format_size(e.size),
+                e.paths.join(", "),
+            ]
+        })
+        .collect();
+
+    println!(
+        "{}",
+        mup::format_table(&["ID", "TYPE", "SIZE", "PATHS"], &rows)
+    );
+
+    Ok(())
+}
+
+// The index entry is uploaded next to the archive so that pulling it back needs nothing else
+fn push(id: Option<&str>, remote: Option<&str>) -> Result<()> {
+    let (name, remote) = remote::find(remote)?;
+    let index = load_index()?;
+
+    let entry = id
+        .map_or_else(
+            || index.iter().max_by_key(|e| e.created),
+            |id| index.iter().find(|e| e.id == id),
+        )
+        .ok_or_else(|| anyhow!("no backup to push, see `mup backup list`"))?;
+
+    let archive = Path::new(BACKUP_DIR).join(&entry.file);
+    let metadata = Path::new(BACKUP_DIR).join(format!("{}.json", entry.id));
+    serde_json::to_writer_pretty(BufWriter::new(File::create(&metadata)?), entry)?;
+
+    let pushed = remote::upload(remote, &archive, &entry.file)
+        .and_then(|()| remote::upload(remote, &metadata, &format!("{}.json", entry.id)));
+    fs::remove_file(&metadata)?;
+    pushed?;
+
+    println!("pushed backup {} to {name}", entry.id);
+
+    Ok(())
+}
+
+fn pull(id: &str, remote: Option<&str>) -> Result<()> {
+    if !is_plain_relative(id) {
+        return Err(anyhow!("invalid backup id {id}"));
+    }
+
+    let (name, remote) = remote::find(remote)?;
+    let mut index = load_index()?;
+
+    if index.iter().any(|e| e.id == id) {
+        return Err(anyhow!("backup {id} already exists locally"));
+    }
+
+    fs::create_dir_all(BACKUP_DIR)?;
+
+    let metadata = Path::new(BACKUP_DIR).join(format!("{id}.json"));
+    remote::download(remote, &format!("{id}.json"), &metadata)?;
+    let entry: Result<Entry> = File::open(&metadata)
+        .map_err(Into::into)
+        .and_then(|f| Ok(serde_json::from_reader(BufReader::new(f))?));
+    fs::remove_file(&metadata)?;
+    let entry = entry?;
+    check_pulled(id, &entry)?;
+
+    let archive = Path::new(BACKUP_DIR).join(&entry.file);
+    remote::download(remote, &entry.file, &archive)?;
+
+    if mup::hash_file::<Sha256>(&archive)? != entry.sha256 {
+        fs::remove_file(&archive)?;
+        return Err(anyhow!(
+            "downloaded archive does not match its checksum, the copy on {name} may be damaged"
+        ));
+    }
+
+    println!("pulled backup {id} from {name}");
+
+    index.push(entry);
+    save_index(&index)
+}
+
+// The index entry comes from the remote, and restoring deletes every path it lists, so anything
+// pointing outside the server directory is refused before it is used
+fn check_pulled(id: &str, entry: &Entry) -> Result<()> {
+    if entry.id != id || entry.file != format!("{id}.tar.gz") {
+        return Err(anyhow!(
+            "the index entry of backup {id} names another backup"
+        ));
+    }
+
+    if let Some(path) = entry.paths.iter().find(|p| !is_plain_relative(p)) {
+        return Err(anyhow!(
+            "backup {id} lists {path}, which is outside of the server directory"
+        ));
+    }
+
+    Ok(())
+}
+
+// Relative paths that stay inside the directory they are joined to, like archive entries
+fn is_plain_relative(name: &str) -> bool {
+    let path = Path::new(name);
+
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        && path.components().any(|c| matches!(c, Component::Normal(_)))
+}
+
+fn prune(policy: &Policy, dry_run: bool) -> Result<()> {
+    if policy.last == 0 && policy.daily == 0 && policy.weekly == 0 {
+        return Err(anyhow!("the retention policy would delete every backup"));
+    }
+
+    let index = load_index()?;
+    let keep: HashSet<String> = retained(&index, policy)
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+    let (kept, expired): (Vec<Entry>, Vec<Entry>) =
+        index.into_iter().partition(|e| keep.contains(&e.id));
+
+    let mut freed = 0;
+    for entry in &expired {
+        if dry_run {
+            println!("would delete backup {}", entry.id);
+            continue;
+        }
+
+        let archive = Path::new(BACKUP_DIR).join(&entry.file);
+        if archive.exists() {
+            info!("deleting {}", archive.display());
+            fs::remove_file(&archive)?;
+        }
+
+        freed += entry.size;
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    save_index(&kept)?;
+
+    println!(
+        "deleted {} backups, freed {}",
+        expired.len(),
+        mup::format_size(freed)
+    );
+
+    Ok(())
+}
+
+// Returns the IDs of the backups kept by the policy: the most recent `last` backups, plus the
+// newest backup of each of the most recent `daily` days and `weekly` weeks that have one
+fn retained<'a>(index: &'a [Entry], policy: &Policy) -> HashSet<&'a str> {
+    let mut newest_first: Vec<&Entry> = index.iter().collect();
+    newest_first.sort_by_key(|e| std::cmp::Reverse(e.created));
+
+    let mut keep: HashSet<&str> = newest_first
+        .iter()
+        .take(policy.last)
+        .map(|e| e.id.as_str())
+        .collect();
+
+    // The epoch fell on a Thursday, shifting by three days makes weeks start on Monday
+    for (count, period) in [(policy.daily, DAY), (policy.weekly, 7 * DAY)] {
+        let mut seen = HashSet::new();
+
+        for entry in &newest_first {
+            if seen.len() == count {
+                break;
+            }
+
+            if seen.insert((entry.created + 3 * DAY) / period) {
+                keep.insert(&entry.id);
+            }
+        }
+    }
+
+    keep
+}
+
+fn load_index() -> Result<Vec<Entry>> {
+    let path = Path::new(BACKUP_DIR).join(INDEX_FILE);
+
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+fn save_index(index: &[Entry]) -> Result<()> {
+    let path = Path::new(BACKUP_DIR).join(INDEX_FILE);
+
+    serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), index)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, created: u64) -> Entry {
+        Entry {
+            id: id.to_string(),
+            created,
+            file: format!("{id}.tar.gz"),
+            worlds_only: false,
+            paths: vec![],
+            size: 0,
+            sha256: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_retained() {
+        // Monday 2024-01-01 00:00 UTC
+        let monday = 1_704_067_200;
+        let index = vec![
+            entry("old", monday - 30 * DAY),
+            entry("last-week", monday - DAY),
+            entry("mon-am", monday + 3600),
+            entry("mon-pm", monday + 12 * 3600),
+            entry("tue", monday + DAY),
+            entry("wed", monday + 2 * DAY),
+        ];
+
+        let keep = |last, daily, weekly| {
+            let mut ids: Vec<&str> = retained(
+                &index,
+                &Policy {
+                    last,
+                    daily,
+                    weekly,
+                },
+            )
+            .into_iter()
+            .collect();
+            ids.sort_unstable();
+            ids
+        };
+
+        assert_eq!(keep(2, 0, 0), ["tue", "wed"]);
+        assert_eq!(keep(0, 3, 0), ["mon-pm", "tue", "wed"]);
+        assert_eq!(keep(0, 0, 2), ["last-week", "wed"]);
+        assert_eq!(keep(1, 0, 10), ["last-week", "old", "wed"]);
+    }
+
+    #[test]
+    fn test_check_pulled() {
+        let pulled = |file: &str, paths: &[&str]| Entry {
+            file: file.to_string(),
+            paths: paths.iter().map(ToString::to_string).collect(),
+            ..entry("1700000000", 0)
+        };
+
+        assert!(check_pulled("1700000000", &pulled("1700000000.tar.gz", &["world"])).is_ok());
+        assert!(check_pulled("1700000000", &pulled("../../.bashrc", &["world"])).is_err());
+        assert!(check_pulled("1700000000", &pulled("other.tar.gz", &["world"])).is_err());
+
+        for path in ["/etc", "../world", "world/../..", ".", ""] {
+            assert!(
+                check_pulled("1700000000", &pulled("1700000000.tar.gz", &[path])).is_err(),
+                "{path} was accepted"
+            );
+        }
+
+        assert!(!is_plain_relative("../1700000000"));
+        assert!(!is_plain_relative("/tmp/1700000000"));
+    }
+}
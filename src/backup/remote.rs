@@ -0,0 +1,160 @@
+use std::{
+    env,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use mup::{config::Remote, credentials, s3::Bucket};
+
+// Picks the remote named on the command line, or the only one configured
+pub fn find(name: Option<&str>) -> Result<(&'static str, &'static Remote)> {
+    let remotes = &mup::config::get().backup.remotes;
+
+    match name {
+        Some(name) => remotes
+            .get_key_value(name)
+            .map(|(k, v)| (k.as_str(), v))
+            .ok_or_else(|| anyhow!("no backup remote named {name} in the config")),
+        None if remotes.len() == 1 => remotes
+            .iter()
+            .next()
+            .map(|(k, v)| (k.as_str(), v))
+            .ok_or_else(|| anyhow!("no backup remotes configured")),
+        None if remotes.is_empty() => Err(anyhow!(
+            "no backup remotes configured, add one under [backup.remotes] in the config"
+        )),
+        None => Err(anyhow!(
+            "several backup remotes are configured, pick one with --remote"
+        )),
+    }
+}
+
+pub fn upload(remote: &Remote, local: &Path, name: &str) -> Result<()> {
+    match remote {
+        Remote::S3 { prefix, .. } => with_bucket(remote, |b| b.put(&join(prefix, name), local)),
+        Remote::Sftp { path, .. } => sftp(
+            remote,
+            &format!("put {} {}", quote(local), quote(join(path, name))),
+        ),
+        Remote::Rclone { destination } => {
+            rclone(&local.to_string_lossy(), &join(destination, name))
+        }
+    }
+}
+
+pub fn download(remote: &Remote, name: &str, local: &Path) -> Result<()> {
+    match remote {
+        Remote::S3 { prefix, .. } => with_bucket(remote, |b| b.get(&join(prefix, name), local)),
+        Remote::Sftp { path, .. } => sftp(
+            remote,
+            &format!("get {} {}", quote(join(path, name)), quote(local)),
+        ),
+        Remote::Rclone { destination } => {
+            rclone(&join(destination, name), &local.to_string_lossy())
+        }
+    }
+}
+
+fn with_bucket(remote: &Remote, f: impl FnOnce(&Bucket) -> Result<()>) -> Result<()> {
+    let Remote::S3 {
+        endpoint,
+        bucket,
+        region,
+        access_key_id,
+        ..
+    } = remote
+    else {
+        return Err(anyhow!("not an S3 remote"));
+    };
+
+    let access_key_id = access_key_id
+        .clone()
+        .or_else(|| env::var("AWS_ACCESS_KEY_ID").ok())
+        .ok_or_else(|| anyhow!("set access_key_id for the remote or AWS_ACCESS_KEY_ID"))?;
+    let secret_access_key = credentials::get("s3").ok_or_else(|| {
+        anyhow!(
+            "no S3 secret key, run `mup config credentials set s3` or set AWS_SECRET_ACCESS_KEY"
+        )
+    })?;
+
+    f(&Bucket {
+        endpoint,
+        name: bucket,
+        region,
+        access_key_id: &access_key_id,
+        secret_access_key: &secret_access_key,
+    })
+}
+
+// Runs a single command through sftp in batch mode, which fails on the first error
+fn sftp(remote: &Remote, batch: &str) -> Result<()> {
+    let Remote::Sftp {
+        host, user, port, ..
+    } = remote
+    else {
+        return Err(anyhow!("not an SFTP remote"));
+    };
+
+    let target = user
+        .as_ref()
+        .map_or_else(|| host.clone(), |user| format!("{user}@{host}"));
+
+    info!("sftp {target}: {batch}");
+
+    let mut command = Command::new("sftp");
+    command.args(["-b", "-"]);
+    if let Some(port) = port {
+        command.args(["-P", &port.to_string()]);
+    }
+
+    let mut child = command
+        .arg(&target)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("sftp is required for SFTP remotes: {e}"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{batch}")?;
+    }
+
+    if !child.wait()?.success() {
+        return Err(anyhow!("sftp to {target} failed"));
+    }
+
+    Ok(())
+}
+
+fn rclone(from: &str, to: &str) -> Result<()> {
+    info!("rclone copyto {from} {to}");
+
+    let status = Command::new("rclone")
+        .args(["copyto", from, to])
+        .status()
+        .map_err(|e| anyhow!("rclone is required for rclone remotes: {e}"))?;
+
+    if !status.success() {
+        return Err(anyhow!("rclone failed to copy {from} to {to}"));
+    }
+
+    Ok(())
+}
+
+// Joins a remote directory and a file name, rclone destinations may end in `remote:`
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() || dir.ends_with(':') {
+        format!("{dir}{name}")
+    } else {
+        format!("{}/{name}", dir.trim_end_matches('/'))
+    }
+}
+
+fn quote(path: impl AsRef<Path>) -> String {
+    format!(
+        "\"{}\"",
+        path.as_ref().to_string_lossy().replace('"', "\\\"")
+    )
+}
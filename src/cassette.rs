@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::Response;
+
+// A cassette is a directory holding an index of interactions and one file per response body
+const INDEX: &str = "index.json";
+
+static STATE: Mutex<Option<Cassette>> = Mutex::new(None);
+
+// The cassette is shared by the whole process, so tests replaying one take turns
+static REPLAYING: Mutex<()> = Mutex::new(());
+
+#[derive(PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+struct Cassette {
+    mode: Mode,
+    dir: PathBuf,
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Interaction {
+    method: String,
+    url: String,
+    status: u16,
+    content_length: Option<u64>,
+    body: String,
+    #[serde(skip)]
+    used: bool,
+}
+
+pub fn start_recording(dir: &Path) -> Result<()> {
+    info!("recording http interactions to {}", dir.display());
+
+    fs::create_dir_all(dir)?;
+
+    // Kept absolute so the cassette is written to the same place after --dir changes directory
+    set(Cassette {
+        mode: Mode::Record,
+        dir: std::path::absolute(dir)?,
+        interactions: vec![],
+    })
+}
+
+pub fn start_replaying(dir: &Path) -> Result<()> {
+    info!("replaying http interactions from {}", dir.display());
+
+    let index = fs::read_to_string(dir.join(INDEX))
+        .map_err(|e| anyhow!("failed to read cassette {}: {e}", dir.display()))?;
+
+    set(Cassette {
+        mode: Mode::Replay,
+        dir: std::path::absolute(dir)?,
+        interactions: serde_json::from_str(&index)?,
+    })
+}
+
+// Replays a cassette only while `f` runs, so tests of providers and loaders run without the
+// network
+pub fn replaying<T>(dir: &Path, f: impl FnOnce() -> T) -> Result<T> {
+    let _turn = REPLAYING.lock().unwrap_or_else(PoisonError::into_inner);
+
+    start_replaying(dir)?;
+    let result = f();
+    *lock()? = None;
+
+    Ok(result)
+}
+
+fn lock() -> Result<MutexGuard<'static, Option<Cassette>>> {
+    STATE.lock().map_err(|_| anyhow!("cassette lock poisoned"))
+}
+
+fn set(cassette: Cassette) -> Result<()> {
+    *lock()? = Some(cassette);
+
+    Ok(())
+}
+
+// Writes the index of a recording cassette, should be called once the command finishes
+pub fn finish() -> Result<()> {
+    let state = lock()?;
+
+    if let Some(cassette) = state.as_ref().filter(|c| c.mode == Mode::Record) {
+        let output = fs::File::create(cassette.dir.join(INDEX))?;
+        serde_json::to_writer_pretty(output, &cassette.interactions)?;
+    }
+
+    Ok(())
+}
+
+// Returns the next unused recorded response for this request when replaying
+pub(crate) fn replay(method: &str, url: &str) -> Result<Option<Response>> {
+    let mut state = lock()?;
+
+    let Some(cassette) = state.as_mut().filter(|c| c.mode == Mode::Replay) else {
+        return Ok(None);
+    };
+
+    let interaction = cassette
+        .interactions
+        .iter_mut()
+        .find(|i| !i.used && i.method == method && i.url == url)
+        .ok_or_else(|| anyhow!("no recorded response for {method} {url}"))?;
+
+    interaction.used = true;
+
+    let body = fs::read(cassette.dir.join(&interaction.body))?;
+
+    Ok(Some(Response::new(
+        url,
+        interaction.status,
+        interaction.content_length,
+        Box::new(Cursor::new(body)),
+    )))
+}
+
+// Buffers the response body into the cassette when recording, otherwise passes it through
+pub(crate) fn record(method: &str, mut resp: Response) -> Result<Response> {
+    let mut state = lock()?;
+
+    let Some(cassette) = state.as_mut().filter(|c| c.mode == Mode::Record) else {
+        return Ok(resp);
+    };
+
+    let mut body = vec![];
+    resp.reader().read_to_end(&mut body)?;
+
+    let filename = format!("{}.body", cassette.interactions.len());
+    fs::write(cassette.dir.join(&filename), &body)?;
+
+    cassette.interactions.push(Interaction {
+        method: method.to_string(),
+        url: resp.url.clone(),
+        status: resp.status,
+        content_length: resp.content_length,
+        body: filename,
+        used: false,
+    });
+
+    Ok(Response::new(
+        &resp.url,
+        resp.status,
+        resp.content_length,
+        Box::new(Cursor::new(body)),
+    ))
+}
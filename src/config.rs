@@ -0,0 +1,187 @@
+use std::{collections::BTreeMap, env, fs, path::PathBuf, sync::OnceLock};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::Deserialize;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Worker threads used for parallel work such as verification
+    pub jobs: Option<usize>,
+    pub user_agent: UserAgent,
+    pub compatibility: Compatibility,
+    pub backup: Backup,
+    pub logs: Logs,
+    pub modrinth: Modrinth,
+    pub run: Run,
+    /// Paper forks usable as loaders, keyed by loader name
+    pub forks: BTreeMap<String, Fork>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct UserAgent {
+    /// Contact information of whoever operates this server, such as an email address
+    pub contact: Option<String>,
+    /// A URL identifying the server or network
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Compatibility {
+    /// Require exact Minecraft version matches for Paper-family plugins
+    pub strict: bool,
+    /// How many minor versions older a Paper-family plugin may target and still be accepted
+    pub paper_window: u32,
+}
+
+impl Default for Compatibility {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            paper_window: 1,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Backup {
+    /// Number of most recent backups kept when pruning
+    pub keep_last: usize,
+    /// Number of days for which the newest backup of the day is kept
+    pub keep_daily: usize,
+    /// Number of weeks for which the newest backup of the week is kept
+    pub keep_weekly: usize,
+    /// Off-site destinations for `mup backup push`, keyed by name
+    pub remotes: BTreeMap<String, Remote>,
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self {
+            keep_last: 5,
+            keep_daily: 7,
+            keep_weekly: 4,
+            remotes: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Remote {
+    /// An S3-compatible bucket. The secret key is read from the credentials file or
+    /// `AWS_SECRET_ACCESS_KEY`
+    S3 {
+        endpoint: String,
+        bucket: String,
+        #[serde(default = "default_region")]
+        region: String,
+        /// Falls back to `AWS_ACCESS_KEY_ID`
+        access_key_id: Option<String>,
+        /// Directory inside the bucket to store backups in
+        #[serde(default)]
+        prefix: String,
+    },
+    /// A directory on an SFTP server, authenticated with the keys used by `ssh`
+    Sftp {
+        host: String,
+        user: Option<String>,
+        port: Option<u16>,
+        #[serde(default)]
+        path: String,
+    },
+    /// Any rclone destination such as `b2:bucket/backups`
+    Rclone { destination: String },
+}
+
+fn default_region() -> String {
+    String::from("us-east-1")
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Logs {
+    /// Logs and crash reports older than this are deleted when pruning
+    pub keep_days: u64,
+    /// Gzip the logs that are kept when pruning
+    pub compress: bool,
+}
+
+impl Default for Logs {
+    fn default() -> Self {
+        Self {
+            keep_days: 14,
+            compress: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Modrinth {
+    /// Personal access token, raises rate limits and gives access to unlisted and draft projects
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Run {
+    /// Java executable servers are launched with, `java` from the PATH by default
+    pub java: Option<String>,
+    /// Flags passed to the JVM before the server jar, such as `-Xmx4G`
+    pub jvm_args: Vec<String>,
+}
+
+/// Where to download a Paper fork from. URLs may contain `{minecraft_version}`,
+/// `{minecraft_minor}` (e.g. `1.21`) and, for direct downloads, `{build}`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Fork {
+    /// URL of a Jenkins job that publishes the server jar
+    pub jenkins: Option<String>,
+    /// Glob pattern selecting the server jar among the Jenkins build artifacts
+    pub artifact: Option<String>,
+    /// Direct download URL of the server jar
+    pub url: Option<String>,
+}
+
+// $MUP_CONFIG takes precedence over the platform config directory
+pub fn path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("MUP_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    dirs::config_dir().map(|dir| dir.join("mup").join("config.toml"))
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| match load() {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("failed to load config, using defaults: {e}");
+            Config::default()
+        }
+    })
+}
+
+fn load() -> Result<Config> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    info!("loading config from {}", path.to_string_lossy());
+
+    let contents = fs::read_to_string(&path)?;
+
+    toml::from_str(&contents).map_err(|e| anyhow!("invalid config {}: {e}", path.display()))
+}
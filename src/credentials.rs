@@ -0,0 +1,118 @@
+use std::{collections::BTreeMap, env, fs, io::Write, path::PathBuf, sync::OnceLock};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+
+use crate::config;
+
+/// Providers that accept credentials, along with the environment variable that overrides them.
+pub const PROVIDERS: [(&str, &str); 3] = [
+    ("github", "GITHUB_TOKEN"),
+    ("modrinth", "MODRINTH_TOKEN"),
+    ("s3", "AWS_SECRET_ACCESS_KEY"),
+];
+
+static CREDENTIALS: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+// Kept out of config.toml so the config can be shared without leaking secrets
+pub fn path() -> Option<PathBuf> {
+    config::path().map(|p| p.with_file_name("credentials.toml"))
+}
+
+pub fn env_var(provider: &str) -> Result<&'static str> {
+    PROVIDERS
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .map(|(_, var)| *var)
+        .ok_or_else(|| anyhow!("unknown provider {provider}"))
+}
+
+// The environment takes precedence over the credentials file
+pub fn get(provider: &str) -> Option<String> {
+    let from_env = env_var(provider)
+        .ok()
+        .and_then(|var| env::var(var).ok())
+        .filter(|v| !v.is_empty());
+
+    from_env.or_else(|| {
+        CREDENTIALS
+            .get_or_init(|| match load() {
+                Ok(credentials) => credentials,
+                Err(e) => {
+                    warn!("failed to load credentials: {e}");
+                    BTreeMap::new()
+                }
+            })
+            .get(provider)
+            .cloned()
+    })
+}
+
+pub fn load() -> Result<BTreeMap<String, String>> {
+    let Some(path) = path().filter(|p| p.exists()) else {
+        return Ok(BTreeMap::new());
+    };
+
+    info!("loading credentials from {}", path.display());
+
+    let contents = fs::read_to_string(&path)?;
+
+    toml::from_str(&contents).map_err(|e| anyhow!("invalid credentials {}: {e}", path.display()))
+}
+
+pub fn save(credentials: &BTreeMap<String, String>) -> Result<()> {
+    let path = path().ok_or_else(|| anyhow!("could not find a config directory"))?;
+
+    if let Some(prefix) = path.parent() {
+        fs::create_dir_all(prefix)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    // Nobody but the owner has any business reading API keys, not even for the moment between
+    // creating the file and writing to it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&path)?;
+
+    // The mode only applies to new files, an existing one is locked down before anything is
+    // written to it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(toml::to_string(credentials)?.as_bytes())?;
+
+    Ok(())
+}
+
+// Shows just enough of a secret to tell which one is stored
+pub fn mask(secret: &str) -> String {
+    let shown: String = secret.chars().take(4).collect();
+
+    if secret.chars().count() <= 8 {
+        "*".repeat(secret.chars().count())
+    } else {
+        format!("{shown}...")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask("mrp_abcdefghijkl"), "mrp_...");
+        assert_eq!(mask("short"), "*****");
+    }
+}
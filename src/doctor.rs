@@ -0,0 +1,202 @@
+use std::{net::TcpListener, path::Path};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    java,
+    loader::Loader,
+    plugin::{gc, verify},
+    server::{eula, lockfile::Lockfile, properties::Properties, run},
+};
+
+const DEFAULT_PORT: u16 = 25565;
+// Enough for a few world saves and backups
+const MIN_FREE_SPACE: u64 = 2 * 1024 * 1024 * 1024;
+
+enum Check {
+    Ok(String),
+    Warn(String, String),
+    Fail(String, String),
+}
+
+pub fn doctor() -> Result<()> {
+    let lockfile = Lockfile::existing()?;
+
+    let mut checks = vec![];
+    match &lockfile {
+        Some(lockfile) if lockfile.loader.is_bedrock() => {}
+        Some(lockfile) => checks.push(check_java(&lockfile.loader)),
+        None => checks.push(Check::Warn(
+            String::from("no lockfile in this directory"),
+            String::from("run `mup server init` to set up a server here"),
+        )),
+    }
+    checks.push(check_eula());
+    checks.push(check_port());
+    if let Some(lockfile) = &lockfile {
+        checks.extend(check_drift(lockfile)?);
+    }
+    checks.push(check_disk_space());
+
+    let mut failed = 0;
+    let mut lines = vec![];
+    for check in checks {
+        match check {
+            Check::Ok(message) => lines.push(format!("ok    {message}")),
+            Check::Warn(message, fix) => {
+                lines.push(format!("warn  {message}\n      {fix}"));
+            }
+            Check::Fail(message, fix) => {
+                failed += 1;
+                lines.push(format!("fail  {message}\n      {fix}"));
+            }
+        }
+    }
+
+    println!("{}", lines.join("\n"));
+
+    if failed > 0 {
+        return Err(anyhow!(
+            "{failed} check{} failed",
+            if failed == 1 { "" } else { "s" }
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_java(loader: &Loader) -> Check {
+    let minecraft_version = &loader.minecraft_version;
+    let executable = java::executable(None);
+
+    let major = match java::detect(&executable) {
+        Ok(major) => major,
+        Err(e) => {
+            return Check::Fail(
+                format!("java is not available: {e}"),
+                String::from("run `mup java install`, or set `java` under [run] in the config"),
+            )
+        }
+    };
+
+    match loader.required_java() {
+        Some(required) if major < required => Check::Fail(
+            format!("Java {major} is too old for Minecraft {minecraft_version}"),
+            String::from("run `mup java install`"),
+        ),
+        Some(required) => Check::Ok(format!(
+            "Java {major} (Minecraft {minecraft_version} needs {required})"
+        )),
+        None => Check::Ok(format!("Java {major}")),
+    }
+}
+
+fn check_eula() -> Check {
+    if eula::is_signed() {
+        Check::Ok(String::from("eula is signed"))
+    } else {
+        Check::Fail(
+            String::from("eula is not signed, the server will refuse to start"),
+            String::from("run `mup server sign`"),
+        )
+    }
+}
+
+fn check_port() -> Check {
+    let port = Properties::load()
+        .ok()
+        .and_then(|p| p.get("server-port").and_then(|p| p.parse().ok()))
+        .unwrap_or(DEFAULT_PORT);
+
+    if TcpListener::bind(("0.0.0.0", port)).is_ok() {
+        return Check::Ok(format!("port {port} is free"));
+    }
+
+    if Path::new(run::PID_FILE).exists() {
+        Check::Ok(format!("port {port} is in use by the running server"))
+    } else {
+        Check::Fail(
+            format!("port {port} is in use by another program"),
+            String::from("stop it, or pick another port with `mup server config set server-port`"),
+        )
+    }
+}
+
+fn check_drift(lockfile: &Lockfile) -> Result<Vec<Check>> {
+    let mut checks = vec![];
+
+    let (mut missing, mut modified) = (vec![], vec![]);
+    for (info, status) in verify::verify_all(lockfile, mup::jobs()) {
+        match status? {
+            verify::Status::Missing => missing.push(info.name.as_str()),
+            verify::Status::Mismatch { .. } => modified.push(info.name.as_str()),
+            verify::Status::Ok | verify::Status::NoChecksum => {}
+        }
+    }
+
+    if !missing.is_empty() {
+        checks.push(Check::Fail(
+            format!("missing from disk: {}", missing.join(", ")),
+            String::from("run `mup server install` to download them"),
+        ));
+    }
+
+    if !modified.is_empty() {
+        checks.push(Check::Warn(
+            format!("changed since they were installed: {}", modified.join(", ")),
+            String::from("run `mup plugin verify` for details, then reinstall them"),
+        ));
+    }
+
+    let unmanaged = gc::find_unmanaged(lockfile)?;
+    if !unmanaged.is_empty() {
+        let names: Vec<String> = unmanaged.iter().map(|p| p.display().to_string()).collect();
+
+        checks.push(Check::Warn(
+            format!("not in the lockfile: {}", names.join(", ")),
+            String::from("track them with `mup plugin add --provider local`, or remove them with `mup plugin gc`"),
+        ));
+    }
+
+    if checks.is_empty() {
+        checks.push(Check::Ok(String::from(
+            "installed projects match the lockfile",
+        )));
+    }
+
+    Ok(checks)
+}
+
+fn check_disk_space() -> Check {
+    let Some(free) = free_space() else {
+        return Check::Ok(String::from("free disk space is unknown on this platform"));
+    };
+
+    if free < MIN_FREE_SPACE {
+        Check::Warn(
+            format!("only {} of disk space left", mup::format_size(free)),
+            String::from("free up space, e.g. with `mup backup prune` or `mup server logs prune`"),
+        )
+    } else {
+        Check::Ok(format!("{} of disk space free", mup::format_size(free)))
+    }
+}
+
+#[cfg(unix)]
+fn free_space() -> Option<u64> {
+    // SAFETY: statvfs only writes to the struct it is given, the path is NUL-terminated
+    unsafe {
+        let mut stats: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(c".".as_ptr(), &raw mut stats) != 0 {
+            return None;
+        }
+
+        #[allow(clippy::useless_conversion)]
+        Some(u64::from(stats.f_bavail) * u64::from(stats.f_frsize))
+    }
+}
+
+#[cfg(not(unix))]
+const fn free_space() -> Option<u64> {
+    None
+}
@@ -0,0 +1,259 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    plugin::{self, verify},
+    server::lockfile::{self, Lockfile},
+};
+
+const HISTORY_DIR: &str = ".mup/history";
+
+// A command that changed the lockfile, along with what it looked like before
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    time: u64,
+    command: String,
+    changes: Vec<Change>,
+    /// File name and contents of the lockfile before the command ran
+    lockfile: (String, String),
+}
+
+#[derive(Deserialize, Serialize)]
+struct Change {
+    name: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+// Runs a command that may change the lockfile and records what it changed, even when it fails
+// partway so whatever it managed to do can still be undone
+pub fn record<T>(change: impl FnOnce() -> Result<T>) -> Result<T> {
+    let path = lockfile::path();
+    let before = fs::read_to_string(path).ok();
+    let before_lockfile = Lockfile::existing()?;
+
+    let result = change();
+
+    let after = fs::read_to_string(lockfile::path()).ok();
+    if let (Some(before), Some(before_lockfile)) = (before, before_lockfile) {
+        if after.as_ref() != Some(&before) {
+            let changes = Lockfile::existing()?
+                .map(|after| diff(&before_lockfile, &after))
+                .unwrap_or_default();
+
+            save(&Entry {
+                time: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+                command: env::args().skip(1).collect::<Vec<_>>().join(" "),
+                changes,
+                lockfile: (path.to_string(), before),
+            })?;
+        }
+    }
+
+    result
+}
+
+fn diff(before: &Lockfile, after: &Lockfile) -> Vec<Change> {
+    let server = |lockfile: &Lockfile| {
+        let loader = &lockfile.loader;
+        let version = loader
+            .installed
+            .as_ref()
+            .map_or(&loader.version, |i| &i.version);
+
+        Some(format!(
+            "{} {} {version}",
+            loader.name, loader.minecraft_version
+        ))
+    };
+
+    let version = |lockfile: &Lockfile, name: &str| {
+        lockfile
+            .mods
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.version.clone())
+    };
+
+    let mut names: Vec<&str> = before
+        .mods
+        .iter()
+        .chain(&after.mods)
+        .map(|p| p.name.as_str())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+
+    std::iter::once(Change {
+        name: String::from("server"),
+        before: server(before),
+        after: server(after),
+    })
+    .chain(names.into_iter().map(|name| Change {
+        name: name.to_string(),
+        before: version(before, name),
+        after: version(after, name),
+    }))
+    .filter(|c| c.before != c.after)
+    .collect()
+}
+
+// Entries are named after the millisecond they were saved in, like config snapshots
+fn save(entry: &Entry) -> Result<()> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+
+    fs::create_dir_all(HISTORY_DIR)?;
+    fs::write(
+        Path::new(HISTORY_DIR).join(format!("{timestamp}.json")),
+        serde_json::to_string_pretty(entry)?,
+    )?;
+
+    Ok(())
+}
+
+// Oldest first
+fn entries() -> Result<Vec<(PathBuf, Entry)>> {
+    if !Path::new(HISTORY_DIR).exists() {
+        return Ok(vec![]);
+    }
+
+    let mut files: Vec<(u128, PathBuf)> = fs::read_dir(HISTORY_DIR)?
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let path = e.path();
+            let timestamp = path.file_stem()?.to_str()?.parse().ok()?;
+            Some((timestamp, path))
+        })
+        .collect();
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|(_, path)| {
+            let entry = serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| anyhow!("invalid history entry {}: {e}", path.display()))?;
+
+            Ok((path, entry))
+        })
+        .collect()
+}
+
+fn describe(changes: &[Change]) -> String {
+    let described: Vec<String> = changes
+        .iter()
+        .map(|c| match (&c.before, &c.after) {
+            (None, Some(after)) => format!("+{} {after}", c.name),
+            (Some(before), None) => format!("-{} {before}", c.name),
+            (Some(before), Some(after)) => format!("~{} {before} -> {after}", c.name),
+            (None, None) => c.name.clone(),
+        })
+        .collect();
+
+    if described.is_empty() {
+        String::from("no version changes")
+    } else {
+        described.join(", ")
+    }
+}
+
+pub fn history() -> Result<()> {
+    let entries = entries()?;
+    if entries.is_empty() {
+        println!("no changes recorded");
+        return Ok(());
+    }
+
+    for (_, entry) in entries.iter().rev() {
+        println!(
+            "{}  mup {}\n    {}",
+            mup::format_timestamp(entry.time),
+            entry.command,
+            describe(&entry.changes)
+        );
+    }
+
+    Ok(())
+}
+
+// Puts back the lockfile from before the most recent change, then removes the jars it no longer
+// lists and downloads the ones it lists again. The lockfile is written in the format the server
+// uses now, even if it was in the other one when the change was recorded
+pub fn undo(yes: bool) -> Result<()> {
+    let (entry_path, entry) = entries()?
+        .pop()
+        .ok_or_else(|| anyhow!("no changes to undo"))?;
+
+    println!(
+        "undoing `mup {}`: {}",
+        entry.command,
+        describe(&entry.changes)
+    );
+
+    if !yes && !mup::confirm("Proceed?")? {
+        return Ok(());
+    }
+
+    let current = Lockfile::existing()?;
+
+    let (name, contents) = &entry.lockfile;
+    let restored = Lockfile::parse(name, contents)
+        .map_err(|e| anyhow!("the recorded lockfile is not valid: {e}"))?;
+
+    let path = lockfile::path();
+    if !Path::new(path).exists() {
+        fs::File::create(path)?;
+    }
+    restored.save()?;
+
+    if let Some(current) = current {
+        let kept: Vec<PathBuf> = restored
+            .mods
+            .iter()
+            .map(|p| p.get_file_path(&restored.loader))
+            .collect();
+
+        for project in &current.mods {
+            let jar = project.get_file_path(&current.loader);
+            if !kept.contains(&jar) && jar.exists() {
+                info!("removing {}", jar.display());
+                fs::remove_file(jar)?;
+            }
+        }
+    }
+
+    for project in &restored.mods {
+        match verify::verify_entry(&restored, project)? {
+            verify::Status::Missing | verify::Status::Mismatch { .. } => {
+                println!("downloading {} {}", project.name, project.version);
+                plugin::download_plugin(&restored, project)?;
+            }
+            verify::Status::Ok | verify::Status::NoChecksum => {}
+        }
+    }
+
+    if restored.loader.installed.is_some() && !restored.loader.is_installed()? {
+        println!("downloading the {} server jar", restored.loader.name);
+        restored.loader.fetch_installed()?;
+    }
+
+    if let Err(e) = fs::remove_file(&entry_path) {
+        warn!(
+            "failed to remove history entry {}: {e}",
+            entry_path.display()
+        );
+    }
+
+    println!(
+        "restored the lockfile from {}",
+        mup::format_timestamp(entry.time)
+    );
+
+    Ok(())
+}
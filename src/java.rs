@@ -0,0 +1,262 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use flate2::read::GzDecoder;
+use log::{info, warn};
+use mup::version::MinecraftVersion;
+use serde::Deserialize;
+use sha2::Sha256;
+
+use crate::{loader::Loader, server::lockfile::Lockfile};
+
+const ADOPTIUM_API: &str = "https://api.adoptium.net/v3/assets/latest";
+
+#[derive(Debug, Subcommand)]
+pub enum Java {
+    /// Download a Temurin JRE for this server and use it to run the server
+    Install {
+        /// Major Java version to install, defaults to what the server's Minecraft version needs
+        #[arg(long)]
+        version: Option<u32>,
+    },
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    binary: Binary,
+    release_name: String,
+}
+
+#[derive(Deserialize)]
+struct Binary {
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    link: String,
+    checksum: String,
+}
+
+pub fn action(java: &Java) -> Result<()> {
+    match java {
+        Java::Install { version } => install(*version),
+    }
+}
+
+// The --java flag wins over the runtime installed for the server, then the config, then
+// whatever `java` is on the PATH
+pub fn executable(flag: Option<&str>) -> String {
+    let installed = || {
+        Lockfile::existing()
+            .ok()
+            .flatten()
+            .and_then(|l| l.java)
+            .filter(|java| {
+                let exists = Path::new(java).exists();
+                if !exists {
+                    warn!("{java} from the lockfile does not exist, run `mup java install`");
+                }
+                exists
+            })
+    };
+
+    flag.map(String::from)
+        .or_else(installed)
+        .or_else(|| mup::config::get().run.java.clone())
+        .unwrap_or_else(|| String::from("java"))
+}
+
+fn install(version: Option<u32>) -> Result<()> {
+    let mut lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+
+    let version = match version {
+        Some(version) => version,
+        None => lockfile.loader.required_java().ok_or_else(|| {
+            anyhow!("could not tell which Java version this server needs, pass --version")
+        })?,
+    };
+
+    let (os, arch) = platform()?;
+    let url = mup::with_query(
+        &format!("{ADOPTIUM_API}/{version}/hotspot"),
+        &[
+            ("architecture", arch),
+            ("image_type", "jre"),
+            ("os", os),
+            ("vendor", "eclipse"),
+        ],
+    );
+
+    let assets: Vec<Asset> = mup::get_json(&url)?;
+    let asset = assets
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("no Java {version} runtime for {os} on {arch}"))?;
+
+    // Runtimes are shared between servers, each release is only downloaded once
+    let dir = runtime_dir()?;
+    let home = dir.join(&asset.release_name);
+
+    if home.is_dir() {
+        info!("{} is already installed", asset.release_name);
+    } else {
+        let archive = dir.join(&asset.binary.package.name);
+        mup::download_with_checksum::<Sha256>(
+            &asset.binary.package.link,
+            &archive,
+            &asset.binary.package.checksum,
+        )?;
+
+        info!("extracting {}", archive.display());
+
+        let extracted = if archive
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+        {
+            mup::zip::extract(&fs::read(&archive)?, &dir, |_| false)
+        } else {
+            mup::tar::extract(GzDecoder::new(BufReader::new(File::open(&archive)?)), &dir)
+                .map(|_| ())
+        };
+        fs::remove_file(&archive)?;
+        extracted?;
+    }
+
+    let java = executable_in(&home);
+    let major = detect(&java.to_string_lossy())?;
+
+    lockfile.java = Some(java.to_string_lossy().into_owned());
+    lockfile.save()?;
+
+    println!("installed Java {major} to {}", home.display());
+
+    Ok(())
+}
+
+// Names as used by the Adoptium API
+fn platform() -> Result<(&'static str, &'static str)> {
+    let os = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "mac",
+        "windows" => "windows",
+        os => return Err(anyhow!("no Java runtimes are published for {os}")),
+    };
+
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "aarch64",
+        "arm" => "arm",
+        "x86" => "x86",
+        "powerpc64" => "ppc64le",
+        arch => return Err(anyhow!("no Java runtimes are published for {arch}")),
+    };
+
+    Ok((os, arch))
+}
+
+fn runtime_dir() -> Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| anyhow!("failed to find a data directory"))?
+        .join("mup")
+        .join("java");
+
+    fs::create_dir_all(&dir)?;
+
+    Ok(dir)
+}
+
+// macOS runtimes are app bundles
+fn executable_in(home: &Path) -> PathBuf {
+    let home = if cfg!(target_os = "macos") {
+        home.join("Contents").join("Home")
+    } else {
+        home.to_path_buf()
+    };
+
+    home.join("bin")
+        .join(if cfg!(windows) { "java.exe" } else { "java" })
+}
+
+/// Returns the major version of a Java installation, e.g. 8 or 21.
+pub fn detect(java: &str) -> Result<u32> {
+    let output = Command::new(java)
+        .arg("-version")
+        .output()
+        .map_err(|e| anyhow!("failed to run {java}: {e}"))?;
+
+    // The version is printed to stderr
+    let text = String::from_utf8_lossy(&output.stderr);
+
+    parse_version(&text).ok_or_else(|| anyhow!("failed to read the version of {java}"))
+}
+
+// Reads `openjdk version "21.0.2"` or, before Java 9, `java version "1.8.0_392"`
+fn parse_version(output: &str) -> Option<u32> {
+    let quoted = output.split('"').nth(1)?;
+    let mut parts = quoted.split(['.', '_', '-', '+']);
+
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+/// Fails when the Java installation is older than what the server needs.
+pub fn check(java: &str, loader: &Loader) -> Result<()> {
+    let Some(required) = loader.required_java() else {
+        return Ok(());
+    };
+
+    let major = detect(java)?;
+    if major < required {
+        return Err(anyhow!(
+            "Minecraft {} needs Java {required} or newer, but {java} is Java {major}",
+            loader.minecraft_version
+        ));
+    }
+
+    Ok(())
+}
+
+// Used when the version manifest cannot be reached, unknown for snapshots
+pub fn fallback(minecraft_version: &str) -> Option<u32> {
+    match MinecraftVersion::parse(minecraft_version)? {
+        MinecraftVersion::Release { minor, patch, .. }
+        | MinecraftVersion::PreRelease { minor, patch, .. } => Some(match (minor, patch) {
+            (..=16, _) => 8,
+            (17, _) => 16,
+            (18..=19, _) | (20, None | Some(..=4)) => 17,
+            _ => 21,
+        }),
+        MinecraftVersion::Snapshot { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions() {
+        assert_eq!(
+            parse_version("openjdk version \"21.0.2\" 2024-01-16\nOpenJDK Runtime"),
+            Some(21)
+        );
+        assert_eq!(parse_version("java version \"1.8.0_392\""), Some(8));
+        assert_eq!(parse_version("openjdk version \"17-ea\""), Some(17));
+        assert_eq!(parse_version("command not found"), None);
+
+        assert_eq!(fallback("1.16.5"), Some(8));
+        assert_eq!(fallback("1.20.4"), Some(17));
+        assert_eq!(fallback("1.20.5"), Some(21));
+        assert_eq!(fallback("24w14a"), None);
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+use crate::timings;
+
+/// A build of a Jenkins job, with only the fields mup reads.
+#[derive(Deserialize)]
+pub struct Build {
+    pub number: u32,
+    pub url: String,
+    pub artifacts: Vec<Artifact>,
+}
+
+#[derive(Deserialize)]
+pub struct Artifact {
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    #[serde(rename = "relativePath")]
+    pub relative_path: String,
+}
+
+impl Build {
+    pub fn artifact_url(&self, artifact: &Artifact) -> String {
+        format!("{}artifact/{}", self.url, artifact.relative_path)
+    }
+}
+
+// `job_url` is the full URL of the job, e.g. https://ci.ender.zone/job/EssentialsX/, and `build`
+// is a build number or latest for the last successful build
+pub fn build(job_url: &str, build: &str) -> Result<Build> {
+    let job_url = job_url.trim_end_matches('/');
+    let build = if build == "latest" {
+        "lastSuccessfulBuild"
+    } else {
+        build
+    };
+
+    info!("fetching build {build} of {job_url}");
+
+    let formatted_url =
+        format!("{job_url}/{build}/api/json?tree=number,url,artifacts[fileName,relativePath]");
+
+    timings::time("resolve", &formatted_url, || {
+        let resp = crate::get(&formatted_url)?;
+
+        if resp.status == 404 {
+            return Err(anyhow!("build {build} of {job_url} does not exist"));
+        }
+
+        resp.json()
+    })
+}
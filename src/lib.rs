@@ -1,19 +1,195 @@
 use std::{
     fs::File,
-    io::{self, Read, Write},
+    io::{self, BufRead, IsTerminal, Read, Write},
     path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        LazyLock, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
-use log::info;
-use sha2::Digest;
-use ureq::{typestate::WithoutBody, RequestBuilder};
+use log::{info, warn};
+use sha2::{Digest, Sha256, Sha512};
+use ureq::Agent;
 
-pub const USER_AGENT: &str = concat!(
-    "dkbednarczyk/mup/",
-    env!("CARGO_PKG_VERSION"),
-    " (damian@bednarczyk.xyz)"
-);
+pub mod cassette;
+pub mod config;
+pub mod credentials;
+pub mod jenkins;
+pub mod nbt;
+pub mod ping;
+pub mod rcon;
+pub mod s3;
+pub mod tar;
+pub mod timings;
+pub mod version;
+pub mod zip;
+
+const PRODUCT: &str = concat!("dkbednarczyk/mup/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_CONTACT: &str = "damian@bednarczyk.xyz";
+
+pub fn user_agent() -> String {
+    let ua = &config::get().user_agent;
+
+    format_user_agent(ua.contact.as_deref(), ua.url.as_deref())
+}
+
+// The configured contact replaces the author's, which is only sent when nothing is configured
+fn format_user_agent(contact: Option<&str>, url: Option<&str>) -> String {
+    match (contact, url) {
+        (Some(contact), Some(url)) => format!("{PRODUCT} ({contact}; +{url})"),
+        (Some(contact), None) => format!("{PRODUCT} ({contact})"),
+        (None, Some(url)) => format!("{PRODUCT} (+{url})"),
+        (None, None) => format!("{PRODUCT} ({DEFAULT_CONTACT})"),
+    }
+}
+
+const MODRINTH_API: &str = "https://api.modrinth.com/";
+const GITHUB_API: &str = "https://api.github.com/";
+
+// Non-2xx responses are returned as-is so callers can handle e.g. 404s themselves
+static AGENT: LazyLock<Agent> = LazyLock::new(|| {
+    Agent::config_builder()
+        .http_status_as_error(false)
+        .build()
+        .into()
+});
+
+pub struct Response {
+    pub url: String,
+    pub status: u16,
+    pub content_length: Option<u64>,
+    body: Box<dyn Read>,
+}
+
+impl Response {
+    pub fn new(url: &str, status: u16, content_length: Option<u64>, body: Box<dyn Read>) -> Self {
+        Self {
+            url: url.to_string(),
+            status,
+            content_length,
+            body,
+        }
+    }
+
+    pub fn error_for_status(self) -> Result<Self> {
+        if (200..300).contains(&self.status) {
+            return Ok(self);
+        }
+
+        Err(anyhow!(
+            "request to {} failed with status {}",
+            self.url,
+            self.status
+        ))
+    }
+
+    pub fn reader(&mut self) -> &mut dyn Read {
+        &mut self.body
+    }
+
+    pub fn json<T: serde::de::DeserializeOwned>(self) -> Result<T> {
+        Ok(serde_json::from_reader(self.error_for_status()?.body)?)
+    }
+
+    pub fn string(self) -> Result<String> {
+        let mut body = String::new();
+        self.error_for_status()?.body.read_to_string(&mut body)?;
+
+        Ok(body)
+    }
+}
+
+// Every request goes through here so that cassettes can record and replay them
+pub fn request(method: &str, url: &str) -> Result<Response> {
+    if let Some(resp) = cassette::replay(method, url)? {
+        return Ok(resp);
+    }
+
+    let mut builder = match method {
+        "HEAD" => AGENT.head(url),
+        "GET" => AGENT.get(url),
+        _ => return Err(anyhow!("unsupported method {method}")),
+    }
+    .header("User-Agent", user_agent());
+
+    // Only ever send tokens to the API they belong to, never to CDNs or other providers
+    if url.starts_with(MODRINTH_API) {
+        if let Some(token) = modrinth_token() {
+            builder = builder.header("Authorization", token);
+        }
+    } else if url.starts_with(GITHUB_API) {
+        if let Some(token) = credentials::get("github") {
+            builder = builder.header("Authorization", format!("Bearer {token}"));
+        }
+    }
+
+    let resp = builder.call()?;
+
+    let status = resp.status().as_u16();
+    let content_length = resp
+        .headers()
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+    let body = Box::new(resp.into_body().into_reader());
+
+    cassette::record(method, Response::new(url, status, content_length, body))
+}
+
+// Stored credentials win over the token in config.toml
+fn modrinth_token() -> Option<String> {
+    credentials::get("modrinth").or_else(|| config::get().modrinth.token.clone())
+}
+
+pub fn get(url: &str) -> Result<Response> {
+    request("GET", url)
+}
+
+// Notifications are side effects rather than lookups, so they skip cassettes entirely
+pub fn post_json<T: serde::Serialize>(url: &str, body: &T) -> Result<()> {
+    info!("posting json to {url}");
+
+    let status = AGENT
+        .post(url)
+        .header("User-Agent", user_agent())
+        .send_json(body)?
+        .status()
+        .as_u16();
+
+    if !(200..300).contains(&status) {
+        return Err(anyhow!("request to {url} failed with status {status}"));
+    }
+
+    Ok(())
+}
+
+// Appends percent-encoded query parameters to a URL
+pub fn with_query(url: &str, params: &[(&str, &str)]) -> String {
+    let encode = |s: &str| {
+        s.bytes().fold(String::new(), |mut acc, b| {
+            if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+                acc.push(b as char);
+            } else {
+                acc += &format!("%{b:02X}");
+            }
+            acc
+        })
+    };
+
+    let query = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", encode(k), encode(v)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let separator = if url.contains('?') { '&' } else { '?' };
+
+    format!("{url}{separator}{query}")
+}
 
 pub fn download(url: &str, path: &Path) -> Result<()> {
     info!(
@@ -25,12 +201,14 @@ pub fn download(url: &str, path: &Path) -> Result<()> {
         std::fs::create_dir_all(prefix)?;
     }
 
-    let mut resp = get(url).call()?;
+    timings::time("download", url, || {
+        let mut resp = get(url)?.error_for_status()?;
 
-    let mut file = File::create(path)?;
-    io::copy(&mut resp.body_mut().as_reader(), &mut file)?;
+        let mut file = File::create(path)?;
+        io::copy(resp.reader(), &mut file)?;
 
-    Ok(())
+        Ok(())
+    })
 }
 
 fn hash_and_write<R: Read, W: Write, D: Digest + Write>(
@@ -39,6 +217,7 @@ fn hash_and_write<R: Read, W: Write, D: Digest + Write>(
 ) -> Result<String> {
     let mut hasher = D::new();
     let mut buf = [0; 1024];
+    let mut hashing = Duration::ZERO;
 
     loop {
         let count = reader.read(&mut buf)?;
@@ -47,9 +226,15 @@ fn hash_and_write<R: Read, W: Write, D: Digest + Write>(
         }
 
         writer.write_all(&buf[..count])?;
+
+        let start = Instant::now();
         hasher.write_all(&buf[..count])?;
+        hashing += start.elapsed();
     }
 
+    let label = format!("{}-bit digest", <D as Digest>::output_size() * 8);
+    timings::record("hash", &label, hashing);
+
     let digest = hasher.finalize();
     let hash = digest
         .as_slice()
@@ -69,15 +254,16 @@ pub fn download_with_checksum<T: Digest + Write>(
         path.to_str().ok_or_else(|| anyhow!("invalid path"))?
     );
 
-    let mut resp = get(url).call()?;
-    let body = resp.body_mut().as_reader();
-
     if let Some(prefix) = path.parent() {
         std::fs::create_dir_all(prefix)?;
     }
 
-    let output = File::create(path)?;
-    let hash = hash_and_write::<_, _, T>(body, output)?;
+    let hash = timings::time("download", url, || {
+        let mut resp = get(url)?.error_for_status()?;
+
+        let output = File::create(path)?;
+        hash_and_write::<_, _, T>(resp.reader(), output)
+    })?;
 
     if hash != wanted_hash {
         return Err(anyhow!(
@@ -88,20 +274,263 @@ pub fn download_with_checksum<T: Digest + Write>(
     Ok(())
 }
 
-pub fn get(url: &str) -> RequestBuilder<WithoutBody> {
-    ureq::get(url).header("User-Agent", USER_AGENT)
+// Maven repositories publish checksums next to each artifact as `<artifact>.sha512` etc.
+pub fn download_with_sidecar(url: &str, path: &Path) -> Result<()> {
+    for method in ["sha512", "sha256"] {
+        let resp = get(&format!("{url}.{method}"))?;
+        if resp.status != 200 {
+            continue;
+        }
+
+        let body = resp.string()?;
+        let hash = body
+            .split_whitespace()
+            .next()
+            .ok_or_else(|| anyhow!("empty {method} checksum for {url}"))?
+            .to_lowercase();
+
+        return match method {
+            "sha512" => download_with_checksum::<Sha512>(url, path, &hash),
+            _ => download_with_checksum::<Sha256>(url, path, &hash),
+        };
+    }
+
+    warn!("no checksum published for {url}, downloading without verification");
+
+    download(url, path)
 }
 
-pub fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, ureq::Error> {
+pub fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T> {
     info!("fetching json from {url}");
 
-    get(url).call()?.body_mut().read_json::<T>()
+    timings::time("resolve", url, || get(url)?.json())
+}
+
+pub fn content_length(url: &str) -> Result<Option<u64>> {
+    info!("fetching content length of {url}");
+
+    Ok(request("HEAD", url)?.error_for_status()?.content_length)
 }
 
-pub fn get_string(url: &str) -> Result<String, ureq::Error> {
+pub fn get_string(url: &str) -> Result<String> {
     info!("fetching string from {url}");
 
-    get(url).call()?.body_mut().read_to_string()
+    timings::time("resolve", url, || get(url)?.string())
+}
+
+pub fn hash_file<D: Digest + Write>(path: &Path) -> Result<String> {
+    hash_and_write::<_, _, D>(File::open(path)?, io::sink())
+}
+
+// Number of worker threads for parallel work, from the config or the available parallelism
+pub fn jobs() -> usize {
+    config::get()
+        .jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .max(1)
+}
+
+// Runs `f` over every item on a pool of `jobs` threads sharing a work queue, keeping input order
+pub fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(idx) else {
+                    break;
+                };
+
+                let result = f(item);
+                results.lock().unwrap().push((idx, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _)| *idx);
+
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+// Without a terminal there is nobody to answer, so this errors instead of reading EOF as a no
+pub fn confirm(prompt: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "cannot ask for confirmation without a terminal, pass --yes to proceed"
+        ));
+    }
+
+    print!("{prompt} [y/N] ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input)?;
+
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Matches `text` against a shell-style pattern supporting `*` and `?`
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+// Parses durations such as `90s`, `10m` or `1h30m`, a bare number is taken as seconds
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let invalid = || anyhow!("invalid duration {text}, expected e.g. 30s, 10m or 1h30m");
+
+    if let Ok(secs) = text.parse() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+
+        total += number.parse::<u64>().map_err(|_| invalid())? * unit;
+        number.clear();
+    }
+
+    if !number.is_empty() || text.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+// Serializes a list of strings in sorted order, for files kept in version control
+pub fn serialize_sorted<S: serde::Serializer>(
+    items: &[String],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let mut sorted: Vec<&String> = items.iter().collect();
+    sorted.sort();
+
+    serde::Serialize::serialize(&sorted, serializer)
+}
+
+// Formats seconds since the epoch as an ISO 8601 UTC time, e.g. `2024-02-29T12:34:56Z`
+pub fn format_timestamp(secs: u64) -> String {
+    let days = secs / 86400;
+    let rest = secs % 86400;
+
+    // Converts days to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+// Lays out rows in left-aligned columns under the given headers, the last column is not padded
+pub fn format_table<S: AsRef<str>>(headers: &[&str], rows: &[Vec<S>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.as_ref().chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        let last = cells.len().saturating_sub(1);
+        let line = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                if i == last {
+                    (*cell).to_string()
+                } else {
+                    format!("{cell:<width$}", width = widths[i])
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        line.trim_end().to_string()
+    };
+
+    let mut lines = vec![format_row(headers.to_vec())];
+    lines.extend(
+        rows.iter()
+            .map(|row| format_row(row.iter().map(AsRef::as_ref).collect())),
+    );
+
+    lines.join("\n")
 }
 
 #[cfg(test)]
@@ -122,4 +551,93 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_format_user_agent() {
+        let version = env!("CARGO_PKG_VERSION");
+
+        assert_eq!(
+            format_user_agent(Some("ops@example.com"), Some("https://example.com")),
+            format!("dkbednarczyk/mup/{version} (ops@example.com; +https://example.com)")
+        );
+        assert_eq!(
+            format_user_agent(Some("ops@example.com"), None),
+            format!("dkbednarczyk/mup/{version} (ops@example.com)")
+        );
+        assert_eq!(
+            format_user_agent(None, Some("https://example.com")),
+            format!("dkbednarczyk/mup/{version} (+https://example.com)")
+        );
+        assert_eq!(
+            format_user_agent(None, None),
+            format!("dkbednarczyk/mup/{version} (damian@bednarczyk.xyz)")
+        );
+    }
+
+    #[test]
+    fn test_parallel_map() {
+        let items: Vec<u64> = (0..100).collect();
+
+        let squared = parallel_map(&items, 8, |n| n * n);
+
+        assert_eq!(squared, items.iter().map(|n| n * n).collect::<Vec<_>>());
+        assert!(parallel_map(&Vec::<u64>::new(), 4, |n| *n).is_empty());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_mins(10));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_mins(90));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10").is_ok());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("1h5").is_err());
+    }
+
+    #[test]
+    fn test_with_query() {
+        assert_eq!(
+            with_query("https://example.com/v2", &[("loaders", "[\"paper\"]")]),
+            "https://example.com/v2?loaders=%5B%22paper%22%5D"
+        );
+        assert_eq!(
+            with_query("https://example.com/v2?limit=5", &[("q", "a b")]),
+            "https://example.com/v2?limit=5&q=a%20b"
+        );
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("essentialsx*", "essentialsx"));
+        assert!(glob_match("essentialsx*", "essentialsx-chat"));
+        assert!(glob_match("*edit", "worldedit"));
+        assert!(glob_match("world?uard", "worldguard"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("essentialsx*", "luckperms"));
+        assert!(!glob_match("world?", "worldedit"));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![
+            vec!["luckperms", "1000", "permissions"],
+            vec!["via", "5", ""],
+        ];
+
+        assert_eq!(
+            format_table(&["SLUG", "DOWNLOADS", "DESCRIPTION"], &rows),
+            "SLUG       DOWNLOADS  DESCRIPTION\n\
+             luckperms  1000       permissions\n\
+             via        5"
+        );
+    }
 }
@@ -0,0 +1,63 @@
+#![allow(clippy::case_sensitive_file_extension_comparisons)]
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+use super::Jar;
+
+const BASE_URL: &str = "https://api.github.com/repos/IzzelAliz/Arclight/releases";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+// Arclight is only published as GitHub releases, the Forge flavour is used since it is the
+// one that covers every supported Minecraft version
+pub fn fetch(minecraft_version: &str, version: &str) -> Result<Jar> {
+    if minecraft_version == "latest" {
+        return Err(anyhow!("arclight requires an explicit Minecraft version"));
+    }
+
+    info!("fetching arclight releases");
+
+    let releases: Vec<Release> = mup::get_json(&format!("{BASE_URL}?per_page=100"))?;
+    let prefix = format!("arclight-forge-{minecraft_version}-");
+
+    let (release, asset) = releases
+        .iter()
+        .filter(|r| version == "latest" || r.tag_name == version)
+        .find_map(|r| {
+            r.assets
+                .iter()
+                .find(|a| a.name.starts_with(&prefix) && a.name.ends_with(".jar"))
+                .map(|a| (r, a))
+        })
+        .ok_or_else(|| {
+            anyhow!("could not find arclight {version} for Minecraft {minecraft_version}")
+        })?;
+
+    info!(
+        "downloading {} from release {}",
+        asset.name, release.tag_name
+    );
+
+    let path = PathBuf::from(&asset.name);
+    mup::download(&asset.browser_download_url, &path)?;
+
+    Ok(Jar {
+        path,
+        version: release.tag_name.clone(),
+        url: Some(asset.browser_download_url.clone()),
+    })
+}
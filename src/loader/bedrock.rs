@@ -0,0 +1,93 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+const LINKS_URL: &str = "https://net-secondary.web.minecraft-services.net/api/v1.0/download/links";
+const DOWNLOAD_URL: &str = "https://www.minecraft.net/bedrockdedicatedserver";
+
+// Files the operator edits or the server generates, which an upgrade must not overwrite
+const PRESERVED: [&str; 3] = ["server.properties", "permissions.json", "allowlist.json"];
+
+#[derive(Deserialize)]
+struct Links {
+    result: LinksResult,
+}
+
+#[derive(Deserialize)]
+struct LinksResult {
+    links: Vec<Link>,
+}
+
+#[derive(Deserialize)]
+struct Link {
+    #[serde(rename = "downloadType")]
+    download_type: String,
+    #[serde(rename = "downloadUrl")]
+    download_url: String,
+}
+
+const fn platform() -> (&'static str, &'static str) {
+    if cfg!(windows) {
+        ("serverBedrockWindows", "bin-win")
+    } else {
+        ("serverBedrockLinux", "bin-linux")
+    }
+}
+
+// Bedrock versions have four parts, e.g. 1.21.44.01
+pub fn is_valid_version(version: &str) -> bool {
+    version == "latest"
+        || version
+            .split('.')
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+pub fn latest_version() -> Result<String> {
+    info!("fetching latest Bedrock Dedicated Server version");
+
+    let links: Links = mup::get_json(LINKS_URL)?;
+    let (download_type, _) = platform();
+
+    let url = links
+        .result
+        .links
+        .into_iter()
+        .find(|l| l.download_type == download_type)
+        .ok_or_else(|| anyhow!("no Bedrock Dedicated Server download for this platform"))?
+        .download_url;
+
+    url.rsplit_once("bedrock-server-")
+        .and_then(|(_, file)| file.strip_suffix(".zip"))
+        .map(String::from)
+        .ok_or_else(|| anyhow!("could not read the version from {url}"))
+}
+
+pub fn fetch(version: &str, dest: &Path) -> Result<()> {
+    let version = if version == "latest" {
+        latest_version()?
+    } else {
+        version.to_string()
+    };
+
+    let (_, dir) = platform();
+    let url = format!("{DOWNLOAD_URL}/{dir}/bedrock-server-{version}.zip");
+    let archive = Path::new(".mup").join(format!("bedrock-server-{version}.zip"));
+
+    mup::download(&url, &archive)?;
+
+    info!("unpacking {}", archive.display());
+
+    // Keep the configuration and worlds of an existing install when upgrading
+    mup::zip::extract(&fs::read(&archive)?, dest, |path| {
+        let preserved =
+            PRESERVED.iter().any(|p| path == Path::new(p)) || path.starts_with("worlds");
+
+        preserved && dest.join(path).exists()
+    })?;
+
+    fs::remove_file(&archive)?;
+
+    Ok(())
+}
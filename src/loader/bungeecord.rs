@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use mup::jenkins;
+
+use super::Jar;
+
+const BASE_URL: &str = "https://ci.md-5.net/job/BungeeCord";
+
+// BungeeCord supports every Minecraft version from a single jar, so only the build can be pinned.
+// Jenkins does not publish checksums for its artifacts.
+pub fn fetch(build: &str) -> Result<Jar> {
+    let build = jenkins::build(BASE_URL, build)?;
+
+    let artifact = build
+        .artifacts
+        .iter()
+        .find(|a| a.file_name == "BungeeCord.jar")
+        .ok_or_else(|| anyhow!("build {} has no BungeeCord.jar", build.number))?;
+
+    let formatted_url = build.artifact_url(artifact);
+    let filename = format!("bungeecord-{}.jar", build.number);
+
+    info!("downloading jarfile");
+
+    let path = PathBuf::from(filename);
+    mup::download(&formatted_url, &path)?;
+
+    Ok(Jar {
+        path,
+        version: build.number.to_string(),
+        url: Some(formatted_url),
+    })
+}
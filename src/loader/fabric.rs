@@ -1,41 +1,50 @@
-use std::path::Path;
+use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
 use log::info;
 use serde::Deserialize;
 
+use super::Jar;
+
 const BASE_URL: &str = "https://meta.fabricmc.net/v2/versions";
 
 #[derive(Deserialize)]
 struct FabricVersion {
     version: String,
+    stable: bool,
 }
 
-pub fn fetch(minecraft_version: &str, loader_version: &str) -> Result<()> {
-    let game = get_version("game", minecraft_version)?;
-    let loader = get_version("loader", loader_version)?;
-
-    info!("fetching latest installer");
-
-    let formatted_url = format!("{BASE_URL}/installer");
-    let resp: Vec<FabricVersion> = mup::get_json(&formatted_url)?;
-
-    let installer = &resp
-        .first()
-        .ok_or_else(|| anyhow!("failed to retrieve latest installer"))?
-        .version;
+pub fn fetch(
+    minecraft_version: &str,
+    loader_version: &str,
+    installer_version: Option<&str>,
+    snapshot: bool,
+) -> Result<Jar> {
+    let game = get_version("game", minecraft_version, snapshot)?;
+    let loader = get_version("loader", loader_version, false)?;
+    let installer = get_version("installer", installer_version.unwrap_or("latest"), false)?;
 
     let formatted_url = format!("{BASE_URL}/loader/{game}/{loader}/{installer}/server/jar");
     let filename = format!("fabric-{game}-{loader}.jar");
 
     info!("downloading jarfile to {filename} from {formatted_url}");
 
-    mup::download(&formatted_url, Path::new(&filename))?;
+    let path = PathBuf::from(filename);
+    mup::download(&formatted_url, &path)?;
 
-    Ok(())
+    Ok(Jar {
+        path,
+        version: loader,
+        url: Some(formatted_url),
+    })
 }
 
-fn get_version(path: &str, version: &str) -> Result<String> {
+pub fn latest_loader() -> Result<String> {
+    get_version("loader", "latest", false)
+}
+
+// Versions are listed newest first, "latest" only considers unstable ones when asked to
+fn get_version(path: &str, version: &str, unstable: bool) -> Result<String> {
     info!("fetching information for {path} version {version}");
 
     let formatted_url = format!("{BASE_URL}/{path}");
@@ -44,16 +53,20 @@ fn get_version(path: &str, version: &str) -> Result<String> {
     if version == "latest" {
         let latest = versions
             .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("failed to fetch requested minecraft version"))?;
+            .find(|v| v.stable || unstable)
+            .ok_or_else(|| anyhow!("failed to fetch latest {path} version"))?;
 
         return Ok(latest.version);
     }
 
-    let version = versions
+    let found = versions
         .into_iter()
         .find(|p| p.version == version)
         .ok_or_else(|| anyhow!("{path} version {version} does not exist"))?;
 
-    Ok(version.version)
+    if path == "game" && !found.stable && !unstable {
+        return Err(anyhow!("--snapshot flag is required for snapshot versions"));
+    }
+
+    Ok(found.version)
 }
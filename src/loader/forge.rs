@@ -1,21 +1,28 @@
-use std::{collections::HashMap, path::Path, sync::LazyLock};
+use std::{collections::HashMap, fs, path::PathBuf, sync::LazyLock};
 
 use anyhow::{anyhow, Result};
 use log::{info, warn};
+use mup::version::MinecraftVersion;
 use serde::Deserialize;
 use versions::Versioning;
 
+use super::Jar;
+
 const PROMOS_URL: &str =
     "https://files.minecraftforge.net/maven/net/minecraftforge/forge/promotions_slim.json";
 const BASE_MAVEN_URL: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge";
 
-// Forge does not provide installer jarfiles before Minecraft version 1.5.2
-static LOWER_MINECRAFT_CUTOFF: LazyLock<Versioning> =
-    LazyLock::new(|| Versioning::new("1.5.2").unwrap());
+// Forge does not provide installer jarfiles before Minecraft version 1.5.2, only zips
+static LOWER_MINECRAFT_CUTOFF: LazyLock<MinecraftVersion> =
+    LazyLock::new(|| MinecraftVersion::parse("1.5.2").unwrap());
+
+// Before 1.3.2 the client and server had separate zips instead of a universal one
+static UNIVERSAL_CUTOFF: LazyLock<MinecraftVersion> =
+    LazyLock::new(|| MinecraftVersion::parse("1.3.2").unwrap());
 
 // See https://neoforged.net/news/theproject/
-static UPPER_MINECRAFT_CUTOFF: LazyLock<Versioning> =
-    LazyLock::new(|| Versioning::new("1.20.1").unwrap());
+static UPPER_MINECRAFT_CUTOFF: LazyLock<MinecraftVersion> =
+    LazyLock::new(|| MinecraftVersion::parse("1.20.1").unwrap());
 
 // The cutoff in 1.9 builds after which versions are formatted as 1.X-{installer}-1.X.0
 static INSTALLER_CUTOFF_TRIPLE: LazyLock<Versioning> =
@@ -30,7 +37,19 @@ struct PromosResponse {
     promos: HashMap<String, String>,
 }
 
-pub fn fetch(minecraft_version: &str, installer_version: &str) -> Result<()> {
+// `channel` is either recommended or latest, recommended builds are the ones Forge considers stable
+pub fn fetch(
+    minecraft_version: &str,
+    installer_version: &str,
+    channel: Option<&str>,
+) -> Result<Jar> {
+    let channel = channel.unwrap_or("latest");
+    if !matches!(channel, "recommended" | "latest") {
+        return Err(anyhow!(
+            "forge has no {channel} channel, use recommended or latest"
+        ));
+    }
+
     info!("fetching promos");
 
     let promos = mup::get_json::<PromosResponse>(PROMOS_URL)?.promos;
@@ -38,41 +57,108 @@ pub fn fetch(minecraft_version: &str, installer_version: &str) -> Result<()> {
     let minecraft = if minecraft_version == "latest" {
         promos
             .keys()
-            .filter_map(|p| p.split('-').next())
-            .filter_map(Versioning::new)
-            .max()
-            .unwrap()
+            .filter_map(|p| p.rsplit_once('-'))
+            .filter_map(|(v, _)| MinecraftVersion::parse(v))
+            .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .ok_or_else(|| anyhow!("failed to find latest minecraft version"))?
     } else {
-        Versioning::new(minecraft_version)
+        MinecraftVersion::parse(minecraft_version)
             .ok_or_else(|| anyhow!("invalid minecraft version {minecraft_version}"))?
     };
 
     let installer = if installer_version == "latest" {
         promos
-            .get(&format!("{minecraft}-{installer_version}"))
-            .ok_or_else(|| anyhow!("invalid or unsupported minecraft version"))?
+            .get(&format!("{minecraft}-{channel}"))
+            .ok_or_else(|| {
+                if channel == "recommended" && promos.contains_key(&format!("{minecraft}-latest")) {
+                    anyhow!(
+                        "forge has no recommended build for {minecraft} yet, use --channel latest"
+                    )
+                } else {
+                    anyhow!("invalid or unsupported minecraft version")
+                }
+            })?
     } else {
         installer_version
     };
 
+    if !minecraft.is_snapshot() && minecraft < *LOWER_MINECRAFT_CUTOFF {
+        return Ok(Jar {
+            path: fetch_legacy(&minecraft, installer)?,
+            version: installer.to_string(),
+            url: None,
+        });
+    }
+
     let version_tag = get_version_tag(&minecraft, installer)?;
     let formatted_url = format!("{BASE_MAVEN_URL}/{version_tag}/forge-{version_tag}-installer.jar");
     let filename = format!("forge-{minecraft}-{installer}.jar");
 
     info!("downloading installer jarfile");
 
-    mup::download(&formatted_url, Path::new(&filename))?;
+    let path = PathBuf::from(filename);
+    mup::download_with_sidecar(&formatted_url, &path)?;
 
     warn!("forge servers must be installed manually using the downloaded jarfile");
 
-    Ok(())
+    Ok(Jar {
+        path,
+        version: installer.to_string(),
+        url: Some(formatted_url),
+    })
 }
 
-fn get_version_tag(minecraft: &Versioning, installer: &str) -> Result<String> {
-    if minecraft < &LOWER_MINECRAFT_CUTOFF {
-        return Err(anyhow!(
-            "forge does not provide installer jarfiles before Minecraft 1.5.2"
-        ));
+// Old Forge releases are zips meant to be merged into the vanilla server jar, which mup can't do
+// for you, so they are only extracted next to it
+// Installer version of the channel's build for a Minecraft version
+pub fn resolve(minecraft_version: &str, channel: Option<&str>) -> Result<String> {
+    let channel = channel.unwrap_or("latest");
+
+    info!("fetching promos");
+
+    let mut promos = mup::get_json::<PromosResponse>(PROMOS_URL)?.promos;
+
+    promos
+        .remove(&format!("{minecraft_version}-{channel}"))
+        .ok_or_else(|| anyhow!("forge has no {channel} build for {minecraft_version}"))
+}
+
+fn fetch_legacy(minecraft: &MinecraftVersion, installer: &str) -> Result<PathBuf> {
+    let classifier = if *minecraft < *UNIVERSAL_CUTOFF {
+        "server"
+    } else {
+        "universal"
+    };
+
+    let version_tag = format!("{minecraft}-{installer}");
+    let formatted_url =
+        format!("{BASE_MAVEN_URL}/{version_tag}/forge-{version_tag}-{classifier}.zip");
+    let archive = PathBuf::from(format!("forge-{version_tag}-{classifier}.zip"));
+
+    warn!("forge only provides {classifier} zips before Minecraft 1.5.2, these are legacy builds");
+    info!("downloading {classifier} zip");
+
+    mup::download_with_sidecar(&formatted_url, &archive)?;
+
+    let dest = PathBuf::from(format!("forge-{minecraft}-{installer}"));
+
+    info!("extracting to {}", dest.display());
+
+    mup::zip::extract(&fs::read(&archive)?, &dest, |_| false)?;
+    fs::remove_file(&archive)?;
+
+    warn!(
+        "merge the contents of {} into the vanilla {minecraft} server jar, deleting its META-INF, \
+         to finish installing forge",
+        dest.display()
+    );
+
+    Ok(dest)
+}
+
+fn get_version_tag(minecraft: &MinecraftVersion, installer: &str) -> Result<String> {
+    if minecraft.is_snapshot() {
+        return Err(anyhow!("forge does not support snapshot versions"));
     }
 
     if minecraft > &UPPER_MINECRAFT_CUTOFF {
@@ -81,34 +167,38 @@ fn get_version_tag(minecraft: &Versioning, installer: &str) -> Result<String> {
 
     // Lots of edge cases here
     match minecraft {
-        Versioning::Ideal(s) => {
-            if !(7..10).contains(&s.minor) {
-                return Ok(format!("{s}-{installer}"));
+        MinecraftVersion::Release {
+            minor,
+            patch: Some(patch),
+            ..
+        } => {
+            if !(7..10).contains(minor) {
+                return Ok(format!("{minecraft}-{installer}"));
             }
 
-            if s.minor == 7 && s.patch == 2 {
+            if *minor == 7 && *patch == 2 {
                 return Ok(format!("1.7.2-{installer}-mc172"));
             }
 
-            Ok(format!("{s}-{installer}-{s}"))
+            Ok(format!("{minecraft}-{installer}-{minecraft}"))
         }
-        Versioning::General(v) => {
-            let minor: u32 = v.chunks.0[1].to_string().parse()?;
-
+        MinecraftVersion::Release {
+            minor, patch: None, ..
+        } => {
             let installer = Versioning::new(installer)
                 .ok_or_else(|| anyhow!("invalid installer version: {installer}"))?;
 
-            if (9..11).contains(&minor) && &installer >= &INSTALLER_CUTOFF_TRIPLE {
-                return Ok(format!("{v}-{installer}-{v}.0"));
+            if (9..11).contains(minor) && installer >= *INSTALLER_CUTOFF_TRIPLE {
+                return Ok(format!("{minecraft}-{installer}-{minecraft}.0"));
             }
 
-            if minor == 9 && &installer <= &INSTALLER_CUTOFF_DOUBLE {
-                return Ok(format!("{v}-{installer}-{v}"));
+            if *minor == 9 && installer <= *INSTALLER_CUTOFF_DOUBLE {
+                return Ok(format!("{minecraft}-{installer}-{minecraft}"));
             }
 
-            Ok(format!("{v}-{installer}"))
+            Ok(format!("{minecraft}-{installer}"))
         }
         // This is currently the only release that ends up down here...
-        Versioning::Complex(_) => Ok(format!("1.7.10_pre4-{installer}-prerelease")),
+        _ => Ok(format!("{minecraft}-{installer}-prerelease")),
     }
 }
@@ -0,0 +1,104 @@
+// URL templates use the same {placeholder} syntax as format strings
+#![allow(clippy::literal_string_with_formatting_args)]
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use mup::{config::Fork, jenkins, version::MinecraftVersion};
+
+use super::Jar;
+
+// Forks configured in config.toml take precedence over the built-in ones
+pub fn find(name: &str) -> Option<Fork> {
+    if let Some(fork) = mup::config::get().forks.get(name) {
+        return Some(fork.clone());
+    }
+
+    match name {
+        "pufferfish" => Some(Fork {
+            jenkins: Some(String::from(
+                "https://ci.pufferfish.host/job/Pufferfish-{minecraft_minor}/",
+            )),
+            artifact: Some(String::from("pufferfish-paperclip-*.jar")),
+            url: None,
+        }),
+        _ => None,
+    }
+}
+
+pub fn fetch(name: &str, minecraft_version: &str, build: &str) -> Result<Jar> {
+    let fork = find(name).ok_or_else(|| anyhow!("unknown paper fork {name}"))?;
+
+    if minecraft_version == "latest" {
+        return Err(anyhow!("{name} requires an explicit Minecraft version"));
+    }
+
+    match (&fork.jenkins, &fork.url) {
+        (Some(job), _) => fetch_jenkins(name, &fork, job, minecraft_version, build),
+        (None, Some(url)) => {
+            if build == "latest" && url.contains("{build}") {
+                return Err(anyhow!("{name} requires an explicit build"));
+            }
+
+            let url = expand(url, minecraft_version)?.replace("{build}", build);
+            let filename = format!("{name}-{minecraft_version}-{build}.jar");
+
+            info!("downloading jarfile");
+
+            let path = PathBuf::from(filename);
+            mup::download(&url, &path)?;
+
+            Ok(Jar {
+                path,
+                version: build.to_string(),
+                url: Some(url),
+            })
+        }
+        (None, None) => Err(anyhow!(
+            "{name} needs either a jenkins or a url to download from"
+        )),
+    }
+}
+
+fn fetch_jenkins(
+    name: &str,
+    fork: &Fork,
+    job: &str,
+    minecraft_version: &str,
+    build: &str,
+) -> Result<Jar> {
+    let build = jenkins::build(&expand(job, minecraft_version)?, build)?;
+
+    let pattern = fork.artifact.as_deref().unwrap_or("*.jar");
+    let artifact = build
+        .artifacts
+        .iter()
+        .find(|a| mup::glob_match(pattern, &a.file_name))
+        .ok_or_else(|| anyhow!("build {} has no artifact matching {pattern}", build.number))?;
+
+    let formatted_url = build.artifact_url(artifact);
+    let filename = format!("{name}-{minecraft_version}-{}.jar", build.number);
+
+    info!("downloading jarfile");
+
+    let path = PathBuf::from(filename);
+    mup::download(&formatted_url, &path)?;
+
+    Ok(Jar {
+        path,
+        version: build.number.to_string(),
+        url: Some(formatted_url),
+    })
+}
+
+fn expand(template: &str, minecraft_version: &str) -> Result<String> {
+    let minor = match MinecraftVersion::parse(minecraft_version) {
+        Some(MinecraftVersion::Release { major, minor, .. }) => format!("{major}.{minor}"),
+        _ => return Err(anyhow!("invalid minecraft version {minecraft_version}")),
+    };
+
+    Ok(template
+        .replace("{minecraft_version}", minecraft_version)
+        .replace("{minecraft_minor}", &minor))
+}
@@ -1,20 +1,172 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
 use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use log::{info, warn};
+use mup::version::MinecraftVersion;
 use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+
+use crate::{plugin::Checksum, server::lockfile::Lockfile};
+
+#[derive(Debug, clap::Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    download: Option<Download>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Check the server's loader for a newer build, e.g. a new Paper build for the same
+    /// Minecraft version, and replace the server jar with it
+    Update {
+        /// Do not ask for confirmation
+        #[arg(short, long, action)]
+        yes: bool,
+
+        /// Move a loader version pinned in the lockfile to the newest build as well
+        #[arg(short, long, action)]
+        force: bool,
+    },
+}
 
-pub fn action(
-    name: &str,
-    minecraft_version: &str,
-    version: &str,
+#[derive(Debug, clap::Args)]
+pub struct Download {
+    /// Name of the loader to download
+    #[arg(short, long, value_name = "loader", value_parser = Loader::parse_name)]
+    name: String,
+
+    /// Minecraft version to target
+    #[arg(short, long, default_value = "latest")]
+    minecraft_version: String,
+
+    /// Loader version to target
+    #[arg(short, long, default_value = "latest")]
+    version: String,
+
+    /// Allow snapshot versions for vanilla, fabric and neoforge
+    #[arg(short, long, action)]
     snapshot: bool,
-) -> Result<()> {
-    Loader::new(name, minecraft_version, version, snapshot).fetch()
+
+    /// Release channel to take the latest build from: recommended or latest for forge,
+    /// default or experimental for paper
+    #[arg(long)]
+    channel: Option<String>,
+
+    /// Installer version to build the server jar with, for fabric
+    #[arg(long)]
+    installer: Option<String>,
+
+    /// Directory or file to write the server jar to, e.g. server.jar
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+pub fn action(args: &Args) -> Result<()> {
+    match (&args.command, &args.download) {
+        (Some(Command::Update { yes, force }), _) => update(*yes, *force),
+        (None, Some(download)) => {
+            let mut loader = Loader {
+                channel: download.channel.clone(),
+                installer: download.installer.clone(),
+                output: download.output.clone(),
+                ..Loader::new(
+                    &download.name,
+                    &download.minecraft_version,
+                    &download.version,
+                    download.snapshot,
+                )
+            };
+
+            loader.fetch()
+        }
+        (None, None) => Err(anyhow!(
+            "pass --name to download a loader, or a subcommand such as update"
+        )),
+    }
+}
+
+// Replaces the server jar with the newest build of the loader the lockfile names. A loader
+// version pinned in the lockfile is only moved with --force, and stays pinned to the new build
+fn update(yes: bool, force: bool) -> Result<()> {
+    let mut lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let loader = &lockfile.loader;
+
+    if loader.version != "latest" && !force {
+        return Err(anyhow!(
+            "{} is pinned at version {} in the lockfile, pass --force to update it anyway",
+            loader.name,
+            loader.version
+        ));
+    }
+
+    let latest = loader.latest_version()?;
+    let current = loader.installed.as_ref().map(|i| i.version.as_str());
+
+    if current == Some(latest.as_str()) && loader.is_installed()? {
+        println!(
+            "{} {} is up to date at {latest}",
+            loader.name, loader.minecraft_version
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {}: {} -> {latest}",
+        loader.name,
+        loader.minecraft_version,
+        current.unwrap_or("unknown")
+    );
+
+    if !yes && !mup::confirm("Proceed?")? {
+        return Ok(());
+    }
+
+    let old = lockfile
+        .loader
+        .installed
+        .as_ref()
+        .map(|i| PathBuf::from(&i.file));
+
+    if lockfile.loader.version != "latest" {
+        lockfile.loader.version.clone_from(&latest);
+    }
+    lockfile.fetch_loader()?;
+
+    let new = lockfile
+        .loader
+        .installed
+        .as_ref()
+        .map(|i| PathBuf::from(&i.file));
+    if let Some(old) = old.filter(|old| old.is_file() && new.as_ref() != Some(old)) {
+        info!("removing {}", old.display());
+        fs::remove_file(old)?;
+    }
+
+    println!("updated {} to {latest}", lockfile.loader.name);
+
+    Ok(())
 }
 
+mod arclight;
+mod bedrock;
+mod bungeecord;
 mod fabric;
 mod forge;
+mod fork;
+mod mohist;
 mod neoforge;
 mod paper;
-mod vanilla;
+mod spigot;
+mod sponge;
+pub mod vanilla;
 
 #[derive(Deserialize, Serialize)]
 pub struct Loader {
@@ -22,6 +174,37 @@ pub struct Loader {
     pub minecraft_version: String,
     pub version: String,
     pub snapshot: bool,
+    /// Release channel to pick "latest" builds from, for loaders that have several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Installer version, for loaders that build the server jar with a separate installer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installer: Option<String>,
+    /// Where to put the server jar, either a directory or a file name such as server.jar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// The server jar from the last fetch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed: Option<Installed>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Installed {
+    /// What the loader version resolved to, e.g. the Paper build, or the Minecraft version for
+    /// loaders without versions of their own
+    pub version: String,
+    pub file: String,
+    /// Where the jar was downloaded from, for frozen installs to fetch exactly it again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub checksum: Checksum,
+}
+
+// A server jar downloaded by one of the loaders, along with its concrete version
+pub struct Jar {
+    path: PathBuf,
+    version: String,
+    url: Option<String>,
 }
 
 impl Default for Loader {
@@ -31,12 +214,32 @@ impl Default for Loader {
             minecraft_version: "latest".to_string(),
             version: "latest".to_string(),
             snapshot: false,
+            channel: None,
+            installer: None,
+            output: None,
+            installed: None,
         }
     }
 }
 
 impl Loader {
-    const VALID_LOADERS: [&str; 5] = ["paper", "fabric", "forge", "neoforge", "vanilla"];
+    pub const VALID_LOADERS: [&str; 15] = [
+        "paper",
+        "fabric",
+        "forge",
+        "neoforge",
+        "vanilla",
+        "waterfall",
+        "bungeecord",
+        "velocity",
+        "folia",
+        "spigot",
+        "spongevanilla",
+        "spongeforge",
+        "mohist",
+        "arclight",
+        "bedrock",
+    ];
 
     pub fn new(loader: &str, minecraft_version: &str, version: &str, snapshot: bool) -> Self {
         Self {
@@ -44,32 +247,320 @@ impl Loader {
             minecraft_version: minecraft_version.to_string(),
             version: version.to_string(),
             snapshot,
+            channel: None,
+            installer: None,
+            output: None,
+            installed: None,
         }
     }
 
-    pub fn fetch(&self) -> Result<()> {
-        match self.name.as_str() {
-            "paper" => paper::fetch(&self.minecraft_version, &self.version),
-            "fabric" => fabric::fetch(&self.minecraft_version, &self.version),
-            "forge" => forge::fetch(&self.minecraft_version, &self.version),
-            "neoforge" => neoforge::fetch(&self.minecraft_version),
+    // Downloads the server and records the jar in `installed`, which the lockfile has to be saved
+    // to keep
+    pub fn fetch(&mut self) -> Result<()> {
+        // Bedrock is an archive rather than a jar, so the output is where it gets unpacked
+        if self.is_bedrock() {
+            let dest = self.output.as_deref().unwrap_or(".");
+            return bedrock::fetch(&self.minecraft_version, Path::new(dest));
+        }
+
+        let jar = match self.name.as_str() {
+            "paper" => paper::fetch(
+                "paper",
+                &self.minecraft_version,
+                &self.version,
+                self.channel.as_deref(),
+            ),
+            "waterfall" => paper::fetch(
+                "waterfall",
+                &self.minecraft_version,
+                &self.version,
+                self.channel.as_deref(),
+            ),
+            // Velocity releases are not tied to a Minecraft version, the newest one is always
+            // used and the loader version picks its build
+            "velocity" => {
+                paper::fetch("velocity", "latest", &self.version, self.channel.as_deref())
+            }
+            "folia" => paper::fetch(
+                "folia",
+                &self.minecraft_version,
+                &self.version,
+                self.channel.as_deref(),
+            ),
+            "spigot" => spigot::fetch(&self.minecraft_version),
+            "spongevanilla" | "spongeforge" => {
+                sponge::fetch(&self.name, &self.minecraft_version, &self.version)
+            }
+            "bungeecord" => bungeecord::fetch(&self.version),
+            "fabric" => fabric::fetch(
+                &self.minecraft_version,
+                &self.version,
+                self.installer.as_deref(),
+                self.snapshot,
+            ),
+            "forge" => forge::fetch(
+                &self.minecraft_version,
+                &self.version,
+                self.channel.as_deref(),
+            ),
+            "neoforge" => neoforge::fetch(&self.minecraft_version, &self.version, self.snapshot),
             "vanilla" => vanilla::fetch(&self.minecraft_version, self.snapshot),
-            _ => Ok(()),
+            "mohist" => mohist::fetch(&self.minecraft_version, &self.version),
+            "arclight" => arclight::fetch(&self.minecraft_version, &self.version),
+            name if fork::find(name).is_some() => {
+                fork::fetch(name, &self.minecraft_version, &self.version)
+            }
+            name => return Err(anyhow!("{name} is not a supported loader")),
+        }?;
+
+        let path = match &self.output {
+            Some(output) => move_to_output(&jar.path, output)?,
+            None => jar.path,
+        };
+
+        // Old Forge releases are unpacked to a directory, which has no checksum to record
+        self.installed = if path.is_file() {
+            Some(Installed {
+                version: jar.version,
+                file: path.to_string_lossy().into_owned(),
+                url: jar.url,
+                checksum: Checksum {
+                    method: String::from("sha512"),
+                    hash: mup::hash_file::<Sha512>(&path)?,
+                },
+            })
+        } else {
+            None
+        };
+
+        // The server may be run on another machine, so this only warns
+        if let Err(e) = crate::java::check(&crate::java::executable(None), self) {
+            warn!("{e}");
+        }
+
+        Ok(())
+    }
+
+    // Downloads exactly the jar from the last fetch again without resolving anything, for
+    // frozen installs
+    pub fn fetch_installed(&self) -> Result<()> {
+        let installed = self
+            .installed
+            .as_ref()
+            .ok_or_else(|| anyhow!("no server jar is recorded in the lockfile"))?;
+        let url = installed.url.as_deref().ok_or_else(|| {
+            anyhow!(
+                "{} was built locally and cannot be downloaded again",
+                installed.file
+            )
+        })?;
+
+        mup::download_with_checksum::<Sha512>(
+            url,
+            Path::new(&installed.file),
+            &installed.checksum.hash,
+        )
+    }
+
+    // Whether the jar from the last fetch is still there unchanged
+    pub fn is_installed(&self) -> Result<bool> {
+        let Some(installed) = &self.installed else {
+            return Ok(false);
+        };
+
+        let path = Path::new(&installed.file);
+        if !path.is_file() {
+            return Ok(false);
+        }
+
+        Ok(mup::hash_file::<Sha512>(path)? == installed.checksum.hash)
+    }
+
+    pub fn is_proxy(&self) -> bool {
+        matches!(self.name.as_str(), "waterfall" | "bungeecord" | "velocity")
+    }
+
+    // Oldest Java release this server runs on, proxies do not depend on the Minecraft version
+    pub fn required_java(&self) -> Option<u32> {
+        if self.is_bedrock() || self.is_proxy() {
+            return None;
+        }
+
+        match vanilla::java_version(&self.minecraft_version) {
+            Ok(Some(major)) => Some(major),
+            Ok(None) => crate::java::fallback(&self.minecraft_version),
+            Err(e) => {
+                info!(
+                    "failed to look up the Java version for {}: {e}",
+                    self.minecraft_version
+                );
+                crate::java::fallback(&self.minecraft_version)
+            }
+        }
+    }
+
+    // Concrete loader version, resolving "latest" the same way fetching the server would
+    pub fn resolve_version(&self) -> Result<String> {
+        if self.version != "latest" {
+            return Ok(self.version.clone());
+        }
+
+        self.latest_version()
+    }
+
+    // What "latest" currently resolves to, whichever version the lockfile pins
+    pub fn latest_version(&self) -> Result<String> {
+        let channel = self.channel.as_deref();
+
+        match self.name.as_str() {
+            "paper" | "waterfall" | "folia" => {
+                paper::latest_build(&self.name, &self.minecraft_version, channel)
+            }
+            "velocity" => paper::latest_build("velocity", "latest", channel),
+            "fabric" => fabric::latest_loader(),
+            "forge" => forge::resolve(&self.minecraft_version, channel),
+            "neoforge" => neoforge::resolve(&self.minecraft_version, self.snapshot),
+            "spongevanilla" | "spongeforge" => sponge::resolve(&self.name, &self.minecraft_version),
+            "vanilla" | "spigot" | "bedrock" => Err(anyhow!(
+                "{} has no builds apart from Minecraft versions, use `mup server upgrade` to move \
+                 to a newer one",
+                self.name
+            )),
+            name => Err(anyhow!("cannot resolve the latest {name} version")),
+        }
+    }
+
+    // Replaces "latest" with a concrete version for loaders that only publish a recommended or
+    // current build, returning whether anything changed
+    pub fn pin(&mut self) -> Result<bool> {
+        match self.name.as_str() {
+            "spongevanilla" | "spongeforge" => {
+                self.version = sponge::resolve(&self.name, &self.minecraft_version)?;
+            }
+            "bedrock" if self.minecraft_version == "latest" => {
+                self.minecraft_version = bedrock::latest_version()?;
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    // Whether fetching the server again gives the same files, which "latest" does not promise.
+    // Vanilla, Spigot and Bedrock have no loader version apart from the Minecraft one
+    pub fn is_pinned(&self) -> bool {
+        self.minecraft_version != "latest"
+            && (matches!(self.name.as_str(), "vanilla" | "spigot" | "bedrock")
+                || self.version != "latest")
+    }
+
+    pub fn is_bedrock(&self) -> bool {
+        self.name == "bedrock"
+    }
+
+    pub fn has_valid_minecraft_version(&self) -> bool {
+        if self.is_bedrock() {
+            return bedrock::is_valid_version(&self.minecraft_version);
+        }
+
+        MinecraftVersion::parse(&self.minecraft_version).is_some()
+    }
+
+    // Bukkit-API servers, whose plugins usually keep working across Minecraft versions
+    pub fn is_paper_family(&self) -> bool {
+        matches!(self.name.as_str(), "paper" | "folia" | "spigot") || self.is_fork()
+    }
+
+    fn is_fork(&self) -> bool {
+        fork::find(&self.name).is_some()
+    }
+
+    // Name of the loader on plugin repositories, forks run Paper plugins and both Sponge
+    // flavours share their plugins
+    pub fn plugin_loader(&self) -> &str {
+        match self.name.as_str() {
+            "spongevanilla" | "spongeforge" => "sponge",
+            _ if self.is_hybrid() => "paper",
+            _ if self.is_fork() => "paper",
+            name => name,
+        }
+    }
+
+    // Forge servers that also run Bukkit plugins, with a directory for each
+    pub fn is_hybrid(&self) -> bool {
+        matches!(self.name.as_str(), "mohist" | "arclight")
+    }
+
+    // Every loader Modrinth projects may target to run on this server, servers also run
+    // projects made for the platforms they are built on
+    pub fn plugin_loaders(&self) -> Vec<&str> {
+        if self.is_hybrid() {
+            return vec!["forge", "neoforge", "paper", "spigot", "bukkit"];
+        }
+
+        match self.plugin_loader() {
+            "paper" => vec!["paper", "spigot", "bukkit"],
+            "spigot" => vec!["spigot", "bukkit"],
+            "waterfall" => vec!["waterfall", "bungeecord"],
+            loader => vec![loader],
+        }
+    }
+
+    // Every directory jars are installed to
+    pub fn mod_locations(&self) -> Vec<&str> {
+        if self.is_hybrid() {
+            vec!["mods", "plugins"]
+        } else {
+            vec![self.mod_location()]
         }
     }
 
     pub fn mod_location(&self) -> &str {
         match self.name.as_str() {
-            "paper" => "plugins",
+            "paper" | "folia" | "spigot" | "waterfall" | "bungeecord" | "velocity" => "plugins",
+            _ if self.is_fork() => "plugins",
             _ => "mods",
         }
     }
 
     pub fn parse_name(input: &str) -> Result<String> {
-        if !Self::VALID_LOADERS.contains(&input) {
+        if !Self::VALID_LOADERS.contains(&input) && fork::find(input).is_none() {
             return Err(anyhow!("try one of {:?}", Self::VALID_LOADERS));
         }
 
         Ok(input.into())
     }
 }
+
+// A trailing slash or an existing directory keeps the original file name. Returns where the jar
+// ended up
+fn move_to_output(path: &Path, output: &str) -> Result<PathBuf> {
+    let output = Path::new(output);
+
+    let dest = if output.is_dir() || output.to_string_lossy().ends_with('/') {
+        output.join(
+            path.file_name()
+                .ok_or_else(|| anyhow!("invalid path {}", path.display()))?,
+        )
+    } else {
+        output.to_path_buf()
+    };
+
+    if dest == path {
+        return Ok(dest);
+    }
+
+    if let Some(prefix) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(prefix)?;
+    }
+
+    info!("moving {} to {}", path.display(), dest.display());
+
+    // Renaming fails across filesystems, so fall back to copying
+    if fs::rename(path, &dest).is_err() {
+        fs::copy(path, &dest)?;
+        fs::remove_file(path)?;
+    }
+
+    Ok(dest)
+}
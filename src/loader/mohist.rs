@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+use super::Jar;
+
+const BASE_URL: &str = "https://mohistmc.com/api/v2/projects/mohist";
+
+#[derive(Deserialize)]
+struct Builds {
+    builds: Vec<Build>,
+}
+
+#[derive(Deserialize)]
+struct Build {
+    number: usize,
+    url: String,
+}
+
+pub fn fetch(minecraft_version: &str, build: &str) -> Result<Jar> {
+    if minecraft_version == "latest" {
+        return Err(anyhow!("mohist requires an explicit Minecraft version"));
+    }
+
+    info!("fetching mohist build {build} for {minecraft_version}");
+
+    let formatted_url = format!("{BASE_URL}/{minecraft_version}/builds");
+    let resp = mup::get(&formatted_url)?;
+
+    if resp.status == 404 {
+        return Err(anyhow!(
+            "mohist does not support Minecraft {minecraft_version}"
+        ));
+    }
+
+    let builds: Builds = resp.json()?;
+
+    let build = if build == "latest" {
+        builds.builds.into_iter().max_by_key(|b| b.number)
+    } else {
+        let number: usize = build.parse()?;
+        builds.builds.into_iter().find(|b| b.number == number)
+    }
+    .ok_or_else(|| anyhow!("could not find mohist build {build} for {minecraft_version}"))?;
+
+    let filename = format!("mohist-{minecraft_version}-{}.jar", build.number);
+
+    info!("downloading jarfile");
+
+    let path = PathBuf::from(filename);
+    mup::download(&build.url, &path)?;
+
+    Ok(Jar {
+        path,
+        version: build.number.to_string(),
+        url: Some(build.url),
+    })
+}
@@ -1,53 +1,120 @@
-use std::{path::Path, sync::LazyLock};
+use std::{fmt::Write, path::PathBuf, sync::LazyLock};
 
 use anyhow::{anyhow, Result};
 use log::{info, warn};
+use mup::version::MinecraftVersion;
 use serde::Deserialize;
-use versions::SemVer;
+
+use super::Jar;
 
 const API_URL: &str =
     "https://maven.neoforged.net/api/maven/latest/version/releases/net/neoforged/neoforge";
+const VERSIONS_URL: &str =
+    "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
 const DOWNLOAD_URL: &str = "https://maven.neoforged.net/releases/net/neoforged/neoforge";
 
-static CUTOFF: LazyLock<SemVer> = LazyLock::new(|| SemVer::new("1.20.2").unwrap());
+static CUTOFF: LazyLock<MinecraftVersion> =
+    LazyLock::new(|| MinecraftVersion::parse("1.20.2").unwrap());
 
 #[derive(Deserialize)]
 struct Installer {
     version: String,
 }
 
+#[derive(Deserialize)]
+struct Versions {
+    versions: Vec<String>,
+}
+
 // see https://github.com/neoforged/websites/blob/main/assets/js/neoforge.js
-pub fn fetch(minecraft_version: &str) -> Result<()> {
-    let mut endpoint = API_URL.to_string();
+pub fn fetch(minecraft_version: &str, version: &str, snapshot: bool) -> Result<Jar> {
+    let prefix = if minecraft_version == "latest" {
+        None
+    } else {
+        Some(version_prefix(minecraft_version, snapshot)?)
+    };
+
+    let version = if version == "latest" {
+        get_latest_version(prefix.as_deref())?
+    } else {
+        get_specific_version(prefix.as_deref(), version)?
+    };
+
+    let installer_url = format!("{DOWNLOAD_URL}/{version}/neoforge-{version}-installer.jar");
+    let filename = format!("neoforge-{minecraft_version}-{version}.jar");
+
+    info!("downloading installer jarfile");
+
+    let path = PathBuf::from(filename);
+    mup::download_with_sidecar(&installer_url, &path)?;
+
+    warn!("neoforge servers must be installed manually using the downloaded jarfile");
+
+    Ok(Jar {
+        path,
+        version,
+        url: Some(installer_url),
+    })
+}
+
+// NeoForge versions drop the leading "1." of the Minecraft version they target, e.g. 21.1.77 is
+// for 1.21.1, and builds for snapshots are betas prefixed with 0, e.g. 0.25w14a.3-beta
+pub fn resolve(minecraft_version: &str, snapshot: bool) -> Result<String> {
+    get_latest_version(Some(&version_prefix(minecraft_version, snapshot)?))
+}
 
-    if minecraft_version != "latest" {
-        let version = SemVer::new(minecraft_version)
-            .ok_or_else(|| anyhow!("invalid minecaft version {minecraft_version}"))?;
+fn version_prefix(minecraft_version: &str, snapshot: bool) -> Result<String> {
+    let version = MinecraftVersion::parse(minecraft_version)
+        .ok_or_else(|| anyhow!("invalid minecaft version {minecraft_version}"))?;
 
-        if version < *CUTOFF {
-            return Err(anyhow!("use forge for minecraft versions before 1.20.2"));
+    match version {
+        MinecraftVersion::Snapshot { .. } if snapshot => Ok(format!("0.{version}")),
+        MinecraftVersion::Snapshot { .. } => {
+            Err(anyhow!("--snapshot flag is required for snapshot versions"))
         }
+        MinecraftVersion::Release { minor, patch, .. } => {
+            if version < *CUTOFF {
+                return Err(anyhow!("use forge for minecraft versions before 1.20.2"));
+            }
 
-        let double = format!("{}.{}", version.minor, version.patch);
+            Ok(format!("{minor}.{}", patch.unwrap_or(0)))
+        }
+        MinecraftVersion::PreRelease { .. } => {
+            Err(anyhow!("neoforge does not support pre-release versions"))
+        }
+    }
+}
 
-        endpoint += &format!("?filter={double}");
+fn get_latest_version(prefix: Option<&str>) -> Result<String> {
+    let mut endpoint = API_URL.to_string();
+
+    if let Some(prefix) = prefix {
+        write!(endpoint, "?filter={prefix}")?;
     }
 
-    info!("fetching latest installer version for minecraft {minecraft_version}");
+    info!("fetching latest installer version");
 
     let installer: Installer = mup::get_json(&endpoint)?;
 
-    let installer_url = format!(
-        "{DOWNLOAD_URL}/{}/neoforge-{}-installer.jar",
-        installer.version, installer.version
-    );
-    let filename = format!("neoforge-{minecraft_version}-{}.jar", installer.version);
+    Ok(installer.version)
+}
 
-    info!("downloading installer jarfile");
+fn get_specific_version(prefix: Option<&str>, version: &str) -> Result<String> {
+    info!("fetching installer versions");
 
-    mup::download(&installer_url, Path::new(&filename))?;
+    let versions: Versions = mup::get_json(VERSIONS_URL)?;
 
-    warn!("neoforge servers must be installed manually using the downloaded jarfile");
+    if !versions.versions.iter().any(|v| v == version) {
+        return Err(anyhow!("neoforge version {version} does not exist"));
+    }
+
+    if let Some(prefix) = prefix {
+        if !version.starts_with(&format!("{prefix}.")) {
+            return Err(anyhow!(
+                "neoforge version {version} is not for this Minecraft version, expected {prefix}.*"
+            ));
+        }
+    }
 
-    Ok(())
+    Ok(version.to_string())
 }
@@ -5,7 +5,9 @@ use log::info;
 use serde::Deserialize;
 use sha2::Sha256;
 
-const BASE_URL: &str = "https://api.papermc.io/v2/projects/paper";
+use super::Jar;
+
+const BASE_URL: &str = "https://api.papermc.io/v2/projects";
 
 #[derive(Deserialize)]
 struct Versions {
@@ -19,7 +21,9 @@ struct Builds {
 
 #[derive(Deserialize)]
 struct Build {
-    build: usize,
+    #[serde(rename = "build")]
+    number: usize,
+    channel: String,
     downloads: Downloads,
 }
 
@@ -33,58 +37,104 @@ struct Application {
     sha256: String,
 }
 
-pub fn fetch(minecraft_version: &str, build: &str) -> Result<()> {
-    let minecraft = if minecraft_version == "latest" {
-        get_latest_version()?
-    } else {
-        minecraft_version.to_string()
-    };
+// `project` is any project on the PaperMC downloads API, e.g. paper or waterfall.
+// `channel` is default for stable builds only, or experimental to also accept experimental ones
+pub fn fetch(
+    project: &str,
+    minecraft_version: &str,
+    build: &str,
+    channel: Option<&str>,
+) -> Result<Jar> {
+    let channel = check_channel(project, channel)?;
+    let minecraft = resolve_minecraft(project, minecraft_version)?;
 
-    let build = get_build(&minecraft, build)?;
+    let build = get_build(project, &minecraft, build, channel)?;
 
     let formatted_url = format!(
-        "{BASE_URL}/versions/{minecraft}/builds/{}/downloads/paper-{minecraft}-{}.jar",
-        build.build, build.build,
+        "{BASE_URL}/{project}/versions/{minecraft}/builds/{}/downloads/{project}-{minecraft}-{}.jar",
+        build.number, build.number,
     );
 
-    let filename = format!("paper-{minecraft}-{}.jar", build.build);
+    let filename = format!("{project}-{minecraft}-{}.jar", build.number);
     let wanted_hash = build.downloads.application.sha256;
 
     info!("downloading jarfile");
 
-    mup::download_with_checksum::<Sha256>(&formatted_url, &PathBuf::from(filename), &wanted_hash)?;
+    let path = PathBuf::from(filename);
+    mup::download_with_checksum::<Sha256>(&formatted_url, &path, &wanted_hash)?;
+
+    Ok(Jar {
+        path,
+        version: build.number.to_string(),
+        url: Some(formatted_url),
+    })
+}
+
+// Number of the build "latest" resolves to, without downloading it
+pub fn latest_build(
+    project: &str,
+    minecraft_version: &str,
+    channel: Option<&str>,
+) -> Result<String> {
+    let channel = check_channel(project, channel)?;
+    let minecraft = resolve_minecraft(project, minecraft_version)?;
+
+    Ok(get_build(project, &minecraft, "latest", channel)?
+        .number
+        .to_string())
+}
+
+fn check_channel<'a>(project: &str, channel: Option<&'a str>) -> Result<&'a str> {
+    let channel = channel.unwrap_or("default");
+    if !matches!(channel, "default" | "experimental") {
+        return Err(anyhow!(
+            "{project} has no {channel} channel, use default or experimental"
+        ));
+    }
 
-    Ok(())
+    Ok(channel)
 }
 
-fn get_latest_version() -> Result<String> {
+fn resolve_minecraft(project: &str, minecraft_version: &str) -> Result<String> {
+    if minecraft_version == "latest" {
+        get_latest_version(project)
+    } else {
+        Ok(minecraft_version.to_string())
+    }
+}
+
+fn get_latest_version(project: &str) -> Result<String> {
     info!("fetching latest Minecraft version");
 
-    let versions: Versions = mup::get_json(BASE_URL)?;
+    let versions: Versions = mup::get_json(&format!("{BASE_URL}/{project}"))?;
 
     let latest = versions
         .versions
         .last()
         .ok_or_else(|| anyhow!("could not get latest minecraft version"))?
-        .to_string();
+        .clone();
 
     Ok(latest.replace('"', ""))
 }
 
-fn get_build(minecraft_version: &str, build: &str) -> Result<Build> {
-    let formatted_url = format!("{BASE_URL}/versions/{minecraft_version}/builds");
+fn get_build(project: &str, minecraft_version: &str, build: &str, channel: &str) -> Result<Build> {
+    let formatted_url = format!("{BASE_URL}/{project}/versions/{minecraft_version}/builds");
 
     info!("fetching build {build} for {minecraft_version}");
 
     let body: Builds = mup::get_json(&formatted_url)?;
     if build == "latest" {
-        let first = body
+        // Builds are listed oldest first
+        let latest = body
             .builds
             .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("could not get latest loader version"))?;
+            .filter(|b| channel == "experimental" || b.channel == "default")
+            .max_by_key(|b| b.number)
+            .ok_or_else(|| {
+                anyhow!("no {channel} builds for {minecraft_version}, try --channel experimental")
+            })?;
 
-        return Ok(first);
+        return Ok(latest);
     }
 
     let build_id: usize = build.parse()?;
@@ -92,8 +142,32 @@ fn get_build(minecraft_version: &str, build: &str) -> Result<Build> {
     let latest_build = body
         .builds
         .into_iter()
-        .find(|p| p.build == build_id)
+        .find(|p| p.number == build_id)
         .ok_or_else(|| anyhow!("could not get specific loader version"))?;
 
     Ok(latest_build)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_latest_build() {
+        let cassette =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cassettes/paper-latest-build");
+
+        let (stable, experimental) = mup::cassette::replaying(&cassette, || {
+            (
+                latest_build("paper", "1.21.4", None),
+                latest_build("paper", "1.21.4", Some("experimental")),
+            )
+        })
+        .unwrap();
+
+        assert_eq!(stable.unwrap(), "231");
+        assert_eq!(experimental.unwrap(), "232");
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use super::Jar;
+
+const BUILDTOOLS_URL: &str =
+    "https://hub.spigotmc.org/jenkins/job/BuildTools/lastSuccessfulBuild/artifact/target/BuildTools.jar";
+
+// BuildTools clones and patches the server sources, so it gets a directory of its own
+const WORK_DIR: &str = ".mup/buildtools";
+
+// Spigot does not distribute server jars, they have to be compiled locally with BuildTools
+pub fn fetch(minecraft_version: &str) -> Result<Jar> {
+    require("git", &["--version"])?;
+    require("java", &["-version"])?;
+
+    let work_dir = Path::new(WORK_DIR);
+    let buildtools = work_dir.join("BuildTools.jar");
+
+    mup::download(BUILDTOOLS_URL, &buildtools)?;
+
+    let output_dir = std::env::current_dir()?;
+
+    info!("running BuildTools for Minecraft {minecraft_version}, this can take a while");
+
+    let status = Command::new("java")
+        .arg("-jar")
+        .arg("BuildTools.jar")
+        .args(["--rev", minecraft_version])
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .current_dir(work_dir)
+        .status()?;
+
+    if !status.success() {
+        return Err(anyhow!(
+            "BuildTools failed with {status}, see {} for its logs",
+            work_dir.join("BuildTools.log.txt").display()
+        ));
+    }
+
+    let path = find_jar(&output_dir)?;
+    info!("built {}", path.display());
+
+    // Spigot has no versions of its own and "latest" builds are named after the version built
+    let version = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.strip_prefix("spigot-"))
+        .unwrap_or(minecraft_version)
+        .to_string();
+
+    Ok(Jar {
+        path,
+        version,
+        // Built locally, so there is nothing to download again
+        url: None,
+    })
+}
+
+fn require(program: &str, args: &[&str]) -> Result<()> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("{program} is required to build Spigot: {e}"))?;
+
+    Ok(())
+}
+
+fn find_jar(dir: &Path) -> Result<PathBuf> {
+    fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| {
+            let is_spigot = p
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("spigot-"));
+
+            is_spigot && p.extension().is_some_and(|ext| ext == "jar")
+        })
+        .max_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+        .ok_or_else(|| anyhow!("BuildTools did not produce a spigot jar"))
+}
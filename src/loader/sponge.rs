@@ -0,0 +1,94 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+use sha1::Sha1;
+
+use super::Jar;
+
+const BASE_URL: &str = "https://dl-api.spongepowered.org/v2/groups/org.spongepowered/artifacts";
+
+#[derive(Deserialize)]
+struct Versions {
+    artifacts: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    classifier: String,
+    extension: String,
+    #[serde(rename = "downloadUrl")]
+    download_url: String,
+    sha1: String,
+}
+
+// `artifact` is either spongevanilla or spongeforge
+pub fn fetch(artifact: &str, minecraft_version: &str, version: &str) -> Result<Jar> {
+    let version = if version == "latest" {
+        resolve(artifact, minecraft_version)?
+    } else {
+        version.to_string()
+    };
+
+    info!("fetching {artifact} {version}");
+
+    let formatted_url = format!("{BASE_URL}/{artifact}/versions/{version}");
+    let resp = mup::get(&formatted_url)?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("{artifact} version {version} does not exist"));
+    }
+
+    let info: Version = resp.json()?;
+
+    let asset = info
+        .assets
+        .iter()
+        .find(|a| a.classifier == "universal" && a.extension == "jar")
+        .ok_or_else(|| anyhow!("{artifact} {version} has no server jar"))?;
+
+    let filename = format!("{artifact}-{version}.jar");
+
+    info!("downloading jarfile");
+
+    let path = PathBuf::from(filename);
+    mup::download_with_checksum::<Sha1>(&asset.download_url, &path, &asset.sha1)?;
+
+    Ok(Jar {
+        path,
+        version,
+        url: Some(asset.download_url.clone()),
+    })
+}
+
+// Sponge marks known-good builds as recommended, only fall back to the newest build without one
+pub fn resolve(artifact: &str, minecraft_version: &str) -> Result<String> {
+    info!("fetching recommended {artifact} build for {minecraft_version}");
+
+    for recommended in ["true", "false"] {
+        let formatted_url = mup::with_query(
+            &format!("{BASE_URL}/{artifact}/versions"),
+            &[
+                ("tags", &format!("minecraft:{minecraft_version}")),
+                ("recommended", recommended),
+                ("limit", "1"),
+            ],
+        );
+
+        let versions: Versions = mup::get_json(&formatted_url)?;
+
+        if let Some(version) = versions.artifacts.into_keys().next() {
+            return Ok(version);
+        }
+    }
+
+    Err(anyhow!(
+        "{artifact} has no builds for Minecraft {minecraft_version}"
+    ))
+}
@@ -1,10 +1,12 @@
-use std::path::Path;
+use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
 use log::info;
 use serde::Deserialize;
 use sha1::Sha1;
 
+use super::Jar;
+
 const BASE_URL: &str = "https://launchermeta.mojang.com/mc/game/version_manifest.json";
 
 #[derive(Deserialize)]
@@ -32,6 +34,18 @@ struct VersionData {
     downloads: Downloads,
 }
 
+#[derive(Deserialize)]
+struct JavaData {
+    #[serde(rename = "javaVersion")]
+    java_version: Option<JavaVersion>,
+}
+
+#[derive(Deserialize)]
+struct JavaVersion {
+    #[serde(rename = "majorVersion")]
+    major_version: u32,
+}
+
 #[derive(Deserialize)]
 struct Downloads {
     server: DownloadInfo,
@@ -43,7 +57,7 @@ struct DownloadInfo {
     sha1: String,
 }
 
-pub fn fetch(minecraft_version: &str, snapshot: bool) -> Result<()> {
+pub fn fetch(minecraft_version: &str, snapshot: bool) -> Result<Jar> {
     let version = get_version(minecraft_version, snapshot)?;
 
     if version.version_type == "snapshot" && !snapshot {
@@ -58,13 +72,38 @@ pub fn fetch(minecraft_version: &str, snapshot: bool) -> Result<()> {
         version.id, version_data.downloads.server.url
     );
 
+    let path = PathBuf::from(filename);
     mup::download_with_checksum::<Sha1>(
         &version_data.downloads.server.url,
-        Path::new(&filename),
+        &path,
         &version_data.downloads.server.sha1,
     )?;
 
-    Ok(())
+    Ok(Jar {
+        path,
+        version: version.id,
+        url: Some(version_data.downloads.server.url),
+    })
+}
+
+// Versions older than 1.6 do not list the Java version they need
+pub fn java_version(minecraft_version: &str) -> Result<Option<u32>> {
+    let version = get_version(minecraft_version, true)?;
+    let data: JavaData = mup::get_json(&version.url)?;
+
+    Ok(data.java_version.map(|j| j.major_version))
+}
+
+// Release versions, newest first
+pub fn releases() -> Result<Vec<String>> {
+    let manifest: VersionManifest = mup::get_json(BASE_URL)?;
+
+    Ok(manifest
+        .versions
+        .into_iter()
+        .filter(|v| v.version_type == "release")
+        .map(|v| v.id)
+        .collect())
 }
 
 fn get_version(minecraft_version: &str, snapshot: bool) -> Result<VanillaVersion> {
@@ -84,7 +123,7 @@ fn get_version(minecraft_version: &str, snapshot: bool) -> Result<VanillaVersion
         .versions
         .into_iter()
         .find(|v| v.id == version_id)
-        .ok_or_else(|| anyhow!("Minecraft version {} not found", version_id))?;
+        .ok_or_else(|| anyhow!("Minecraft version {version_id} not found"))?;
 
     Ok(version)
 }
@@ -1,12 +1,22 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery)]
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::{Parser, Subcommand};
-use std::env;
+use std::{env, fs, path::PathBuf};
 
+mod backup;
+mod doctor;
+mod history;
+mod java;
 mod loader;
+mod network;
 mod plugin;
 mod server;
+mod settings;
+mod status;
+#[cfg(test)]
+mod testing;
+mod workspace;
 
 #[derive(Debug, Parser)]
 #[command(author = "Damian Bednarczyk <damian@bednarczyk.xyz>")]
@@ -19,29 +29,34 @@ struct Cli {
 
     #[arg(short, long, action)]
     verbose: bool,
+
+    /// Print how long each phase of the command took
+    #[arg(long, action, global = true)]
+    timings: bool,
+
+    /// Record every HTTP interaction into a cassette directory
+    #[arg(long, global = true, value_name = "dir", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Replay HTTP interactions from a cassette directory instead of the network
+    #[arg(long, global = true, value_name = "dir")]
+    replay: Option<PathBuf>,
+
+    /// Server directory to work in instead of the current one, also read from `MUP_DIR`.
+    /// Relative paths given to commands are taken from it too
+    #[arg(short = 'C', long, global = true, value_name = "dir")]
+    dir: Option<PathBuf>,
+
+    /// Server of the workspace.toml in the current directory to work on
+    #[arg(long, global = true, value_name = "name")]
+    server: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
-    /// Download a modloader jarfile
+    /// Download a modloader jarfile, or update the server's
     #[clap(alias = "l")]
-    Loader {
-        /// Name of the loader to download
-        #[arg(short, long, value_name = "loader", value_parser = loader::Loader::parse_name)]
-        name: String,
-
-        /// Minecraft version to target
-        #[arg(short, long, default_value = "latest")]
-        minecraft_version: String,
-
-        /// Loader version to target
-        #[arg(short, long, default_value = "latest")]
-        version: String,
-
-        /// Allow snapshot versions for vanilla
-        #[arg(short, long, action)]
-        snapshot: bool,
-    },
+    Loader(loader::Args),
 
     /// Work with plugins and mods
     #[command(subcommand)]
@@ -52,6 +67,43 @@ enum Commands {
     #[command(subcommand)]
     #[clap(alias = "s")]
     Server(server::Server),
+
+    /// Back up and restore worlds, configs and plugins
+    #[command(subcommand)]
+    Backup(backup::Backup),
+
+    /// Set up and update a proxy and its backend servers from network.toml
+    #[command(subcommand)]
+    Network(network::Network),
+
+    /// Set up several servers sharing defaults from workspace.toml
+    #[command(subcommand)]
+    Workspace(workspace::Workspace),
+
+    /// Install a Java runtime for the server
+    #[command(subcommand)]
+    Java(java::Java),
+
+    /// Check the environment for problems that would keep the server from running
+    Doctor,
+
+    /// Show which jars are missing, modified or untracked compared to the lockfile
+    Status,
+
+    /// List the changes made to the lockfile by mup, newest first. Changes made by editing the
+    /// lockfile by hand are not recorded
+    History,
+
+    /// Revert the most recent change to the lockfile, removing and downloading jars to match
+    Undo {
+        /// Do not ask for confirmation
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+
+    /// Manage mup's own configuration
+    #[command(subcommand, name = "config")]
+    Settings(settings::Settings),
 }
 
 fn main() -> Result<()> {
@@ -63,15 +115,67 @@ fn main() -> Result<()> {
 
     pretty_env_logger::init();
 
+    if cli.timings {
+        mup::timings::enable();
+    }
+
+    if let Some(dir) = &cli.record {
+        mup::cassette::start_recording(dir)?;
+    }
+
+    if let Some(dir) = &cli.replay {
+        mup::cassette::start_replaying(dir)?;
+    }
+
+    // Every command works on the current directory, so scripts and panels managing a server
+    // from elsewhere only need to move there first. Only a new server gets its directory created,
+    // a typo elsewhere should not leave empty directories behind
+    if let Some(dir) = cli
+        .dir
+        .clone()
+        .or_else(|| env::var_os("MUP_DIR").map(PathBuf::from))
+    {
+        if matches!(
+            cli.command,
+            Some(Commands::Server(server::Server::Init { .. }))
+        ) {
+            fs::create_dir_all(&dir)?;
+        } else if !dir.is_dir() {
+            return Err(anyhow!("{} is not a directory", dir.display()));
+        }
+
+        env::set_current_dir(&dir)
+            .map_err(|e| anyhow!("failed to enter {}: {e}", dir.display()))?;
+    }
+
+    if let Some(name) = &cli.server {
+        let dir = workspace::directory(name)?;
+        env::set_current_dir(&dir)
+            .map_err(|e| anyhow!("{name} is not set up yet, run `mup workspace install`: {e}"))?;
+    }
+
+    let result = run(&cli);
+
+    mup::timings::report();
+    mup::cassette::finish()?;
+
+    result
+}
+
+fn run(cli: &Cli) -> Result<()> {
     match &cli.command {
-        Some(Commands::Loader {
-            name,
-            minecraft_version,
-            version,
-            snapshot,
-        }) => loader::action(name, minecraft_version, version, *snapshot)?,
+        Some(Commands::Loader(l)) => history::record(|| loader::action(l))?,
         Some(Commands::Plugin(p)) => plugin::action(p)?,
         Some(Commands::Server(s)) => server::action(s)?,
+        Some(Commands::Backup(b)) => backup::action(b)?,
+        Some(Commands::Network(n)) => network::action(n)?,
+        Some(Commands::Workspace(w)) => workspace::action(w)?,
+        Some(Commands::Java(j)) => java::action(j)?,
+        Some(Commands::Doctor) => doctor::doctor()?,
+        Some(Commands::Status) => status::status()?,
+        Some(Commands::History) => history::history()?,
+        Some(Commands::Undo { yes }) => history::undo(*yes)?,
+        Some(Commands::Settings(s)) => settings::action(s)?,
         _ => (),
     }
 
@@ -0,0 +1,158 @@
+use std::{collections::HashMap, io::Read};
+
+use anyhow::{anyhow, Result};
+
+// Just enough of the NBT format to read values out of level.dat
+#[derive(Debug)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Compound(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    // Follows a path of compound keys, e.g. ["Data", "WorldGenSettings", "seed"]
+    pub fn path(&self, keys: &[&str]) -> Option<&Self> {
+        keys.iter().try_fold(self, |tag, key| tag.get(key))
+    }
+}
+
+pub fn read<R: Read>(mut reader: R) -> Result<Tag> {
+    let id = read_array::<1, _>(&mut reader)?[0];
+    if id != 10 {
+        return Err(anyhow!("root tag is not a compound"));
+    }
+
+    read_string(&mut reader)?;
+
+    read_payload(&mut reader, id)
+}
+
+fn read_payload<R: Read>(reader: &mut R, id: u8) -> Result<Tag> {
+    let tag = match id {
+        1 => Tag::Byte(i8::from_be_bytes(read_array(reader)?)),
+        2 => Tag::Short(i16::from_be_bytes(read_array(reader)?)),
+        3 => Tag::Int(read_i32(reader)?),
+        4 => Tag::Long(i64::from_be_bytes(read_array(reader)?)),
+        5 => Tag::Float(f32::from_be_bytes(read_array(reader)?)),
+        6 => Tag::Double(f64::from_be_bytes(read_array(reader)?)),
+        7 => {
+            let mut buf = vec![0; read_len(reader)?];
+            reader.read_exact(&mut buf)?;
+            Tag::ByteArray(buf)
+        }
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let item_id = read_array::<1, _>(reader)?[0];
+            let len = read_len(reader)?;
+
+            let items = (0..len)
+                .map(|_| read_payload(reader, item_id))
+                .collect::<Result<_>>()?;
+
+            Tag::List(items)
+        }
+        10 => {
+            let mut map = HashMap::new();
+
+            loop {
+                let child_id = read_array::<1, _>(reader)?[0];
+                if child_id == 0 {
+                    break;
+                }
+
+                let name = read_string(reader)?;
+                map.insert(name, read_payload(reader, child_id)?);
+            }
+
+            Tag::Compound(map)
+        }
+        11 => {
+            let len = read_len(reader)?;
+            Tag::IntArray((0..len).map(|_| read_i32(reader)).collect::<Result<_>>()?)
+        }
+        12 => {
+            let len = read_len(reader)?;
+            let values = (0..len)
+                .map(|_| Ok(i64::from_be_bytes(read_array(reader)?)))
+                .collect::<Result<_>>()?;
+
+            Tag::LongArray(values)
+        }
+        _ => return Err(anyhow!("unknown tag type {id}")),
+    };
+
+    Ok(tag)
+}
+
+fn read_array<const N: usize, R: Read>(reader: &mut R) -> Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> Result<i32> {
+    Ok(i32::from_be_bytes(read_array(reader)?))
+}
+
+fn read_len<R: Read>(reader: &mut R) -> Result<usize> {
+    Ok(usize::try_from(read_i32(reader)?.max(0))?)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let len = u16::from_be_bytes(read_array(reader)?);
+    let mut buf = vec![0; len as usize];
+    reader.read_exact(&mut buf)?;
+
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_nested_long() -> Result<()> {
+        let mut data = vec![10, 0, 0];
+        data.extend([10, 0, 4]);
+        data.extend(b"Data");
+        data.extend([4, 0, 10]);
+        data.extend(b"RandomSeed");
+        data.extend((-42i64).to_be_bytes());
+        data.extend([8, 0, 9]);
+        data.extend(b"LevelName");
+        data.extend([0, 5]);
+        data.extend(b"world");
+        data.extend([0, 0]);
+
+        let root = read(data.as_slice())?;
+
+        assert!(matches!(
+            root.path(&["Data", "RandomSeed"]),
+            Some(Tag::Long(-42))
+        ));
+        assert!(matches!(
+            root.path(&["Data", "LevelName"]),
+            Some(Tag::String(s)) if s == "world"
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use log::warn;
+use serde::Deserialize;
+
+use crate::{
+    plugin::{self, ResolveOptions},
+    server::{
+        eula,
+        lockfile::Lockfile,
+        properties::Properties,
+        upgrade::{self, Upgrade},
+    },
+};
+
+pub const NETWORK_PATH: &str = "network.toml";
+
+// Backends without a port are given one counting up from here
+const FIRST_BACKEND_PORT: u16 = 25566;
+
+#[derive(Debug, Subcommand)]
+pub enum Network {
+    /// Set up the proxy and every backend server in network.toml, installing their projects
+    Install {
+        /// Do not ask for confirmation before downloading
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+
+    /// Move every server to the versions in network.toml and update their projects
+    Update {
+        /// Do not ask for confirmation
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+}
+
+#[derive(Deserialize)]
+struct Config {
+    /// Minecraft version of every server, projects on the proxy are picked for it too
+    minecraft_version: String,
+    #[serde(default)]
+    proxy: Proxy,
+    /// Backend servers, keyed by the name the proxy knows them by
+    #[serde(default)]
+    servers: BTreeMap<String, Backend>,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct Proxy {
+    loader: String,
+    /// Loader version, e.g. a Velocity build
+    version: String,
+    directory: String,
+    /// Projects to install, Modrinth IDs or slugs unless prefixed with `hangar:`
+    plugins: Vec<String>,
+}
+
+impl Default for Proxy {
+    fn default() -> Self {
+        Self {
+            loader: String::from("velocity"),
+            version: String::from("latest"),
+            directory: String::from("proxy"),
+            plugins: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Backend {
+    loader: String,
+    #[serde(default = "latest")]
+    version: String,
+    /// Defaults to the name of the server
+    directory: Option<String>,
+    port: Option<u16>,
+    #[serde(default)]
+    plugins: Vec<String>,
+}
+
+fn latest() -> String {
+    String::from("latest")
+}
+
+// A server of the network along with where it lives
+struct Member<'a> {
+    name: &'a str,
+    directory: PathBuf,
+    loader: &'a str,
+    version: &'a str,
+    plugins: &'a [String],
+    /// Port the server listens on, the proxy keeps its own
+    port: Option<u16>,
+}
+
+impl Config {
+    fn load() -> Result<Self> {
+        let text = fs::read_to_string(NETWORK_PATH)
+            .map_err(|e| anyhow!("failed to read {NETWORK_PATH}: {e}"))?;
+        let config: Self = toml::from_str(&text)?;
+
+        if !crate::loader::Loader::new(&config.proxy.loader, "latest", "latest", false).is_proxy() {
+            return Err(anyhow!("{} is not a proxy", config.proxy.loader));
+        }
+
+        Ok(config)
+    }
+
+    // The proxy comes first so its jar is in place before any backend is provisioned
+    fn members(&self) -> Vec<Member<'_>> {
+        let proxy = Member {
+            name: "proxy",
+            directory: PathBuf::from(&self.proxy.directory),
+            loader: &self.proxy.loader,
+            version: &self.proxy.version,
+            plugins: &self.proxy.plugins,
+            port: None,
+        };
+
+        let backends =
+            self.servers
+                .iter()
+                .zip(FIRST_BACKEND_PORT..)
+                .map(|((name, backend), port)| Member {
+                    name,
+                    directory: PathBuf::from(backend.directory.as_deref().unwrap_or(name)),
+                    loader: &backend.loader,
+                    version: &backend.version,
+                    plugins: &backend.plugins,
+                    port: Some(backend.port.unwrap_or(port)),
+                });
+
+        std::iter::once(proxy).chain(backends).collect()
+    }
+}
+
+pub fn action(network: &Network) -> Result<()> {
+    let config = Config::load()?;
+    let root = env::current_dir()?;
+
+    plugin::share_downloads(root.join(".mup").join("cache"));
+
+    let members = config.members();
+    for member in &members {
+        println!(
+            "{}: {} in {}",
+            member.name,
+            member.loader,
+            member.directory.display()
+        );
+
+        in_directory(&member.directory, || match network {
+            Network::Install { yes } => install(&config, member, *yes),
+            Network::Update { yes } => update(&config, member, *yes),
+        })
+        .map_err(|e| anyhow!("{}: {e}", member.name))?;
+    }
+
+    report_drift(&members)?;
+    check_proxy_servers(&config, &members);
+
+    Ok(())
+}
+
+// Commands work on the current directory, so each server is entered in turn
+pub fn in_directory<T>(dir: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let root = env::current_dir()?;
+
+    fs::create_dir_all(dir)?;
+    env::set_current_dir(dir)?;
+
+    let result = f();
+    env::set_current_dir(root)?;
+
+    result
+}
+
+fn install(config: &Config, member: &Member, yes: bool) -> Result<()> {
+    let mut lockfile = if let Some(lockfile) = Lockfile::existing()? {
+        if lockfile.loader.name != member.loader
+            || lockfile.loader.minecraft_version != config.minecraft_version
+        {
+            warn!(
+                "{} runs {} {}, run `mup network update` to match {NETWORK_PATH}",
+                member.name, lockfile.loader.name, lockfile.loader.minecraft_version
+            );
+        }
+
+        lockfile
+    } else {
+        let mut lockfile = Lockfile::with_params(&config.minecraft_version, member.loader, false)?;
+        lockfile.loader.version = member.version.to_string();
+        lockfile.loader.pin()?;
+        lockfile.save()?;
+
+        lockfile
+    };
+
+    if !plugin::confirm_downloads(&lockfile.mods, yes)? {
+        return Ok(());
+    }
+
+    lockfile.fetch_loader()?;
+
+    for entry in &lockfile.mods {
+        plugin::download_plugin(&lockfile, entry)?;
+    }
+
+    add_missing(member.plugins)?;
+    configure(member)
+}
+
+fn update(config: &Config, member: &Member, yes: bool) -> Result<()> {
+    let lockfile = Lockfile::existing()?
+        .ok_or_else(|| anyhow!("not installed, run `mup network install` first"))?;
+
+    if lockfile.loader.name != member.loader {
+        return Err(anyhow!(
+            "runs {} instead of {}, switching loaders is not supported",
+            lockfile.loader.name,
+            member.loader
+        ));
+    }
+
+    if lockfile.loader.minecraft_version != config.minecraft_version {
+        upgrade::action(&Upgrade {
+            minecraft_version: config.minecraft_version.clone(),
+            loader_version: member.version.to_string(),
+            force: false,
+            yes,
+        })?;
+    }
+
+    plugin::update(
+        "all",
+        "latest",
+        yes,
+        false,
+        None,
+        &ResolveOptions::default(),
+    )?;
+
+    add_missing(member.plugins)?;
+    configure(member)
+}
+
+// Projects listed in network.toml that are not installed yet, Modrinth IDs or slugs unless
+// prefixed with `hangar:`
+pub fn add_missing(plugins: &[String]) -> Result<()> {
+    for spec in plugins {
+        let (provider, id) = match spec.split_once(':') {
+            Some((provider @ ("modrinth" | "hangar"), id)) => (provider, id),
+            _ => ("modrinth", spec.as_str()),
+        };
+
+        if Lockfile::init()?.get(id).is_ok() {
+            continue;
+        }
+
+        plugin::add(
+            provider,
+            &[id],
+            "latest",
+            false,
+            plugin::Reason::Requested,
+            &ResolveOptions::default(),
+        )?;
+    }
+
+    Ok(())
+}
+
+// Backends listen on their own port and leave authentication to the proxy
+fn configure(member: &Member) -> Result<()> {
+    let Some(port) = member.port else {
+        return Ok(());
+    };
+
+    let mut properties = Properties::load()?;
+    properties.set("server-port", &port.to_string());
+    properties.set("online-mode", "false");
+    properties.save()?;
+
+    eula::accept()
+}
+
+// Servers on the same loader should run the same release of a shared project
+fn report_drift(members: &[Member]) -> Result<()> {
+    let mut versions: BTreeMap<(String, String), BTreeMap<String, Vec<&str>>> = BTreeMap::new();
+
+    for member in members {
+        let lockfile = in_directory(&member.directory, Lockfile::existing)?;
+        let Some(lockfile) = lockfile else {
+            continue;
+        };
+
+        for project in &lockfile.mods {
+            versions
+                .entry((
+                    lockfile.loader.plugin_loader().to_string(),
+                    project.name.clone(),
+                ))
+                .or_default()
+                .entry(project.version.clone())
+                .or_default()
+                .push(member.name);
+        }
+    }
+
+    for ((_, name), by_version) in versions.iter().filter(|(_, v)| v.len() > 1) {
+        let found: Vec<String> = by_version
+            .iter()
+            .map(|(version, servers)| format!("{version} on {}", servers.join(", ")))
+            .collect();
+
+        println!("{name} differs between servers: {}", found.join("; "));
+    }
+
+    Ok(())
+}
+
+// Velocity writes velocity.toml on its first start, so servers missing from it are only listed
+fn check_proxy_servers(config: &Config, members: &[Member]) {
+    if config.proxy.loader != "velocity" {
+        return;
+    }
+
+    let path = Path::new(&config.proxy.directory).join("velocity.toml");
+    let velocity = fs::read_to_string(&path).unwrap_or_default();
+
+    let missing: Vec<String> = members
+        .iter()
+        .filter_map(|m| Some((m.name, m.port?)))
+        .map(|(name, port)| format!("{name} = \"127.0.0.1:{port}\""))
+        .filter(|line| !velocity.contains(line.as_str()))
+        .collect();
+
+    if !missing.is_empty() {
+        println!(
+            "add these to [servers] in {}:\n{}",
+            path.display(),
+            missing.join("\n")
+        );
+    }
+}
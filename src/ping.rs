@@ -0,0 +1,241 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+use serde_json::Value;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+// Servers answer status requests from any protocol version, -1 is what clients send to ask
+const PROTOCOL_VERSION: i32 = -1;
+const MAX_PACKET: usize = 2 * 1024 * 1024;
+
+/// What a server reports in the multiplayer server list.
+#[derive(Debug, Deserialize)]
+pub struct Status {
+    pub version: Version,
+    pub players: Option<Players>,
+    /// Either a plain string or a chat component
+    #[serde(default)]
+    pub description: Value,
+    /// Round trip time of the ping that followed the status request
+    #[serde(skip)]
+    pub latency: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Version {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Players {
+    pub max: i64,
+    pub online: i64,
+    #[serde(default)]
+    pub sample: Vec<Player>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Player {
+    pub name: String,
+}
+
+impl Status {
+    /// The MOTD without formatting.
+    pub fn motd(&self) -> String {
+        let mut text = String::new();
+        flatten(&self.description, &mut text);
+
+        strip_formatting(&text)
+    }
+}
+
+/// Asks a Java Edition server for its status with the Server List Ping protocol, see
+/// <https://minecraft.wiki/w/Java_Edition_protocol/Server_List_Ping>.
+pub fn ping(host: &str, port: u16) -> Result<(Status, String)> {
+    info!("pinging {host}:{port}");
+
+    let address = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("could not resolve {host}"))?;
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)
+        .map_err(|e| anyhow!("failed to connect to {host}:{port}: {e}"))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+
+    // Handshake with the next state set to status, then the status request
+    let mut handshake = vec![];
+    write_varint(&mut handshake, 0x00);
+    write_varint(&mut handshake, PROTOCOL_VERSION);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+
+    send(&mut stream, &handshake)?;
+    send(&mut stream, &[0x00])?;
+
+    let response = receive(&mut stream)?;
+    let mut body = response.as_slice();
+    if read_varint(&mut body)? != 0x00 {
+        return Err(anyhow!("unexpected response from {host}:{port}"));
+    }
+    let json = read_string(&mut body)?;
+
+    let mut status: Status = serde_json::from_str(&json)
+        .map_err(|e| anyhow!("invalid status from {host}:{port}: {e}"))?;
+
+    // Older servers close the connection instead of answering the ping
+    let started = Instant::now();
+    let mut ping = vec![];
+    write_varint(&mut ping, 0x01);
+    ping.extend_from_slice(&0i64.to_be_bytes());
+    if send(&mut stream, &ping)
+        .and_then(|()| receive(&mut stream))
+        .is_ok()
+    {
+        status.latency = started.elapsed();
+    }
+
+    Ok((status, json))
+}
+
+fn send(stream: &mut TcpStream, packet: &[u8]) -> Result<()> {
+    let mut framed = vec![];
+    write_varint(&mut framed, i32::try_from(packet.len())?);
+    framed.extend_from_slice(packet);
+
+    stream.write_all(&framed)?;
+
+    Ok(())
+}
+
+fn receive(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let length = usize::try_from(read_varint(stream)?)?;
+    if length > MAX_PACKET {
+        return Err(anyhow!("status response too large"));
+    }
+
+    let mut packet = vec![0; length];
+    stream.read_exact(&mut packet)?;
+
+    Ok(packet)
+}
+
+// Seven bits at a time, least significant group first, with the high bit marking continuation
+fn write_varint(out: &mut Vec<u8>, value: i32) {
+    let mut value = value.cast_unsigned();
+
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+
+        out.push(byte | 0x80);
+    }
+}
+
+fn read_varint<R: Read>(input: &mut R) -> Result<i32> {
+    let mut value = 0u32;
+
+    for i in 0..5 {
+        let mut byte = [0];
+        input.read_exact(&mut byte)?;
+
+        value |= u32::from(byte[0] & 0x7f) << (7 * i);
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(value.cast_signed());
+        }
+    }
+
+    Err(anyhow!("varint is too long"))
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    write_varint(out, i32::try_from(s.len()).unwrap_or(i32::MAX));
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn read_string<R: Read>(input: &mut R) -> Result<String> {
+    let length = usize::try_from(read_varint(input)?)?;
+
+    let mut buf = vec![0; length];
+    input.read_exact(&mut buf)?;
+
+    Ok(String::from_utf8(buf)?)
+}
+
+// Chat components nest text in `extra`, and some servers send a bare array of them
+fn flatten(component: &Value, out: &mut String) {
+    match component {
+        Value::String(s) => out.push_str(s),
+        Value::Array(parts) => parts.iter().for_each(|p| flatten(p, out)),
+        Value::Object(map) => {
+            if let Some(text) = map.get("text") {
+                flatten(text, out);
+            }
+            if let Some(extra) = map.get("extra") {
+                flatten(extra, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Removes legacy `§` color and style codes
+fn strip_formatting(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint() {
+        for (value, bytes) in [
+            (0, vec![0x00]),
+            (300, vec![0xac, 0x02]),
+            (-1, vec![0xff, 0xff, 0xff, 0xff, 0x0f]),
+        ] {
+            let mut out = vec![];
+            write_varint(&mut out, value);
+            assert_eq!(out, bytes);
+            assert_eq!(read_varint(&mut out.as_slice()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_motd() {
+        let status: Status = serde_json::from_str(
+            r#"{"version":{"name":"Paper 1.21.4","protocol":769},
+                "players":{"max":20,"online":1,"sample":[{"name":"Notch","id":"x"}]},
+                "description":{"text":"§aHello","extra":[{"text":" world"},"!"]}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(status.motd(), "Hello world!");
+        assert_eq!(status.players.unwrap().sample[0].name, "Notch");
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use log::warn;
+
+use super::{hangar, modrinth, Info};
+use crate::server::lockfile::Lockfile;
+
+// Long changelogs are cut off, the full text is one click away on the project page
+const MAX_LINES: usize = 8;
+
+pub struct Changelog {
+    pub version: String,
+    pub text: String,
+}
+
+// Changelogs are a courtesy, failing to fetch them never stops an update
+pub fn print(lockfile: &Lockfile, plugin: &Info, target: &Info) {
+    match fetch(lockfile, plugin, target) {
+        Ok(changelogs) if changelogs.is_empty() => {}
+        Ok(changelogs) => {
+            println!(
+                "changes in {} {} -> {}:",
+                plugin.name, plugin.version, target.version
+            );
+
+            for changelog in changelogs {
+                println!("  {}", changelog.version);
+                println!("{}", summarize(&changelog.text));
+            }
+        }
+        Err(e) => warn!("failed to fetch changelogs for {}: {e}", plugin.name),
+    }
+}
+
+fn fetch(lockfile: &Lockfile, plugin: &Info, target: &Info) -> Result<Vec<Changelog>> {
+    match plugin.source.as_str() {
+        "modrinth" => modrinth::changelogs(lockfile, &plugin.id, &plugin.version, &target.version),
+        "hangar" => hangar::changelogs(lockfile, &plugin.name, &plugin.version, &target.version),
+        _ => Ok(vec![]),
+    }
+}
+
+fn summarize(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    if lines.is_empty() {
+        return String::from("    no changelog");
+    }
+
+    let mut summary: Vec<String> = lines
+        .iter()
+        .take(MAX_LINES)
+        .map(|l| format!("    {}", l.trim_end()))
+        .collect();
+
+    if lines.len() > MAX_LINES {
+        summary.push(format!("    ... {} more lines", lines.len() - MAX_LINES));
+    }
+
+    summary.join("\n")
+}
@@ -0,0 +1,76 @@
+use anyhow::{anyhow, Result};
+
+use super::{hangar, modrinth};
+use crate::server::lockfile::Lockfile;
+
+pub struct ProjectDetails {
+    pub name: String,
+    pub description: String,
+    pub license: Option<String>,
+    pub downloads: u64,
+    pub loaders: Vec<String>,
+    pub game_versions: Vec<String>,
+    /// Newest version that runs on the server, only known inside an initialized server
+    pub latest: Option<String>,
+}
+
+// Installed projects are looked up on the provider they were installed from
+pub fn show(id: &str, provider: &str) -> Result<()> {
+    let lockfile = Lockfile::existing()?;
+    let installed = lockfile.as_ref().and_then(|lf| lf.get(id).ok());
+
+    let provider = installed.map_or(provider, |info| info.source.as_str());
+
+    let details = match provider {
+        "modrinth" => modrinth::details(lockfile.as_ref(), installed.map_or(id, |i| &i.id))?,
+        "hangar" => hangar::details(lockfile.as_ref(), installed.map_or(id, |i| &i.name))?,
+        _ => {
+            let info = installed.ok_or_else(|| anyhow!("{provider} has no project pages"))?;
+
+            println!("{} ({})", info.name, info.source);
+            println!();
+            println!("source:     {}", info.download_url);
+            println!("installed:  {}", info.version);
+
+            return Ok(());
+        }
+    };
+
+    println!("{} ({provider})", details.name);
+    if !details.description.is_empty() {
+        println!("{}", details.description);
+    }
+    println!();
+    println!(
+        "license:    {}",
+        details.license.as_deref().unwrap_or("unknown")
+    );
+    println!("downloads:  {}", details.downloads);
+    println!("loaders:    {}", details.loaders.join(", "));
+    println!("versions:   {}", summarize(&details.game_versions));
+
+    if lockfile.is_some() {
+        println!(
+            "latest:     {}",
+            details.latest.as_deref().unwrap_or("no compatible version")
+        );
+    }
+
+    println!(
+        "installed:  {}",
+        installed.map_or("no", |info| info.version.as_str())
+    );
+
+    Ok(())
+}
+
+// Long-lived projects support dozens of Minecraft versions, only the range is useful
+fn summarize(versions: &[String]) -> String {
+    match versions {
+        [] => String::from("unknown"),
+        [first, .., last] if versions.len() > 6 => {
+            format!("{first} to {last} ({} versions)", versions.len())
+        }
+        _ => versions.join(", "),
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use crate::server::lockfile::Lockfile;
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    name: &'a str,
+    version: &'a str,
+    source: &'a str,
+    url: &'a str,
+}
+
+pub fn export(format: &str, output: Option<&str>) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+
+    let entries: Vec<Entry> = lockfile
+        .mods
+        .iter()
+        .map(|info| Entry {
+            name: &info.name,
+            version: &info.version,
+            source: &info.source,
+            url: &info.download_url,
+        })
+        .collect();
+
+    let manifest = match format {
+        "json" => serde_json::to_string_pretty(&entries)? + "\n",
+        "csv" => csv(&entries),
+        _ => markdown(&entries),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, manifest)?;
+            println!("exported {} projects to {path}", entries.len());
+        }
+        None => print!("{manifest}"),
+    }
+
+    Ok(())
+}
+
+fn csv(entries: &[Entry]) -> String {
+    // Fields are only quoted when they would otherwise break the row
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut out = String::from("name,version,source,url\n");
+    for e in entries {
+        let row = [e.name, e.version, e.source, e.url].map(field).join(",");
+        out.push_str(&row);
+        out.push('\n');
+    }
+
+    out
+}
+
+fn markdown(entries: &[Entry]) -> String {
+    let cell = |value: &str| value.replace('|', "\\|");
+
+    let mut lines = vec![
+        String::from("| Name | Version | Source | URL |"),
+        String::from("| --- | --- | --- | --- |"),
+    ];
+    for e in entries {
+        let row = [e.name, e.version, e.source, e.url].map(cell).join(" | ");
+        lines.push(format!("| {row} |"));
+    }
+
+    lines.join("\n") + "\n"
+}
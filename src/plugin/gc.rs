@@ -0,0 +1,72 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use crate::server::lockfile::Lockfile;
+
+// Jarfiles in the mod directories, disabled ones included, that no lockfile entry points to
+pub fn find_unmanaged(lockfile: &Lockfile) -> Result<Vec<PathBuf>> {
+    let managed: HashSet<PathBuf> = lockfile
+        .mods
+        .iter()
+        .map(|info| info.get_file_path(&lockfile.loader))
+        .collect();
+
+    let mut unmanaged = vec![];
+
+    for location in lockfile.loader.mod_locations() {
+        for dir in [location.to_string(), format!("{location}-disabled")] {
+            if !Path::new(&dir).is_dir() {
+                continue;
+            }
+
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+
+                if path.extension().is_some_and(|e| e == "jar") && !managed.contains(&path) {
+                    unmanaged.push(path);
+                }
+            }
+        }
+    }
+
+    unmanaged.sort();
+
+    Ok(unmanaged)
+}
+
+pub fn gc(dry_run: bool, yes: bool) -> Result<()> {
+    let lockfile = Lockfile::init()?;
+    if !lockfile.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    let unmanaged = find_unmanaged(&lockfile)?;
+    if unmanaged.is_empty() {
+        println!("no unmanaged jarfiles");
+        return Ok(());
+    }
+
+    println!("jarfiles not in the lockfile:");
+    for path in &unmanaged {
+        println!("  {}", path.display());
+    }
+
+    if dry_run || (!yes && !mup::confirm("Delete them?")?) {
+        return Ok(());
+    }
+
+    for path in &unmanaged {
+        info!("deleting {}", path.display());
+        fs::remove_file(path)?;
+    }
+
+    println!("deleted {} jarfiles", unmanaged.len());
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+use crate::{loader::Loader, server::lockfile::Lockfile};
+
+const BASE_URL: &str = "https://download.geysermc.org/v2/projects";
+
+#[derive(Deserialize)]
+struct Build {
+    version: String,
+    #[serde(rename = "build")]
+    number: u32,
+    downloads: HashMap<String, Download>,
+}
+
+#[derive(Deserialize)]
+struct Download {
+    name: String,
+    sha256: String,
+}
+
+// GeyserMC publishes one jar per platform rather than per loader
+pub fn platform(loader: &Loader) -> Option<&'static str> {
+    match loader.name.as_str() {
+        "fabric" => Some("fabric"),
+        "neoforge" => Some("neoforge"),
+        "waterfall" | "bungeecord" => Some("bungeecord"),
+        "velocity" => Some("velocity"),
+        _ if loader.is_paper_family() || loader.is_hybrid() => Some("spigot"),
+        _ => None,
+    }
+}
+
+// `project` is geyser or floodgate, `version` is a Geyser release such as 2.4.4
+pub fn fetch(lockfile: &Lockfile, project: &str, version: &str) -> Result<super::Info> {
+    let platform = platform(&lockfile.loader)
+        .ok_or_else(|| anyhow!("{project} is not available for {}", lockfile.loader.name))?;
+
+    info!("fetching latest {project} build of version {version}");
+
+    let formatted_url = format!("{BASE_URL}/{project}/versions/{version}/builds/latest");
+    let resp = mup::get(&formatted_url)?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("{project} version {version} does not exist"));
+    }
+
+    let build: Build = resp.json()?;
+
+    let download = build
+        .downloads
+        .get(platform)
+        .ok_or_else(|| anyhow!("{project} has no download for {platform}"))?;
+
+    Ok(super::Info {
+        name: project.to_string(),
+        id: project.to_string(),
+        version: format!("{}-b{}", build.version, build.number),
+        source: String::from("geysermc"),
+        download_url: format!(
+            "{BASE_URL}/{project}/versions/{}/builds/{}/downloads/{platform}",
+            build.version, build.number
+        ),
+        checksum: Some(super::Checksum {
+            method: String::from("sha256"),
+            hash: download.sha256.clone(),
+        }),
+        location: lockfile.loader.is_hybrid().then(|| String::from("plugins")),
+        file_name: Some(download.name.clone()),
+        ..Default::default()
+    })
+}
@@ -4,8 +4,8 @@ use crate::server::lockfile::Lockfile;
 
 use anyhow::{anyhow, Result};
 use log::info;
+use mup::version::{self, MinecraftVersion};
 use serde::Deserialize;
-use versions::SemVer;
 
 const BASE_URL: &str = "https://hangar.papermc.io/api/v1";
 
@@ -38,33 +38,239 @@ struct Download {
 struct FileInfo {
     #[serde(rename = "sha256Hash")]
     sha256: String,
+    #[serde(rename = "sizeBytes")]
+    size: u64,
+}
+
+#[derive(Deserialize)]
+struct Versions {
+    result: Vec<VersionSummary>,
+}
+
+#[derive(Deserialize)]
+struct VersionSummary {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "createdAt")]
+    created_at: String,
+    channel: Channel,
+    #[serde(rename = "platformDependencies")]
+    platform_dependencies: HashMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct Channel {
+    name: String,
 }
 
 #[derive(Deserialize)]
 struct ProjectInfo {
     id: i32,
     name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    settings: Settings,
+    stats: Stats,
+    #[serde(rename = "supportedPlatforms", default)]
+    supported_platforms: HashMap<String, Vec<String>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Settings {
+    tags: Vec<String>,
+    license: License,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct License {
+    name: Option<String>,
+    url: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchResults {
+    result: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    namespace: Namespace,
+    description: String,
+    stats: Stats,
+}
+
+#[derive(Deserialize)]
+struct Namespace {
+    slug: String,
+}
+
+#[derive(Deserialize)]
+struct Stats {
+    downloads: u64,
+}
+
+// Hangar has no Folia platform, Folia support is a tag on Paper projects instead
+fn platform(loader: &str) -> String {
+    match loader {
+        "folia" | "purpur" | "spigot" | "bukkit" => String::from("PAPER"),
+        "bungeecord" => String::from("WATERFALL"),
+        _ => loader.to_uppercase(),
+    }
 }
 
-pub fn fetch(lockfile: &Lockfile, project_id: &str, version: &str) -> Result<super::Info> {
+// The platform versions are resolved for, a --loader override replaces the server's own
+fn server_platform(lockfile: &Lockfile, options: &super::ResolveOptions) -> String {
+    platform(
+        options
+            .loader
+            .as_deref()
+            .unwrap_or_else(|| lockfile.loader.plugin_loader()),
+    )
+}
+
+pub fn search(
+    lockfile: Option<&Lockfile>,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<super::SearchResult>> {
+    info!("searching hangar for {query}");
+
+    let limit = limit.to_string();
+    let mut params = vec![("query", query.to_string()), ("limit", limit)];
+
+    if let Some(lockfile) = lockfile {
+        let loader = &lockfile.loader;
+
+        params.push(("platform", platform(loader.plugin_loader())));
+
+        if !super::is_relaxed(loader, &super::ResolveOptions::default()) {
+            params.push(("version", loader.minecraft_version.clone()));
+        }
+    }
+
+    let params: Vec<(&str, &str)> = params.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    let formatted_url = mup::with_query(&format!("{BASE_URL}/projects"), &params);
+    let results: SearchResults = mup::get_json(&formatted_url)?;
+
+    Ok(results
+        .result
+        .into_iter()
+        .map(|hit| super::SearchResult {
+            slug: hit.namespace.slug.to_lowercase(),
+            downloads: hit.stats.downloads,
+            description: hit.description,
+        })
+        .collect())
+}
+
+pub fn license(project_id: &str) -> Result<super::licenses::License> {
+    info!("fetching license of project {project_id}");
+
+    let resp = mup::get(&format!("{BASE_URL}/projects/{project_id}"))?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("project {project_id} does not exist"));
+    }
+
+    let license = resp.json::<ProjectInfo>()?.settings.license;
+
+    // Hangar only offers a handful of license types, anything else is a free-form name
+    let spdx = match license.kind.as_deref() {
+        Some("MIT") => Some("MIT"),
+        Some("Apache 2.0") => Some("Apache-2.0"),
+        Some("GPL") => Some("GPL-3.0-only"),
+        Some("LGPL") => Some("LGPL-3.0-only"),
+        Some("AGPL") => Some("AGPL-3.0-only"),
+        _ => None,
+    };
+
+    Ok(super::licenses::License {
+        name: license.name.or(license.kind),
+        spdx: spdx.map(String::from),
+        url: license.url,
+    })
+}
+
+pub fn details(lockfile: Option<&Lockfile>, project_id: &str) -> Result<super::ProjectDetails> {
+    info!("fetching info of project {project_id}");
+
+    let resp = mup::get(&format!("{BASE_URL}/projects/{project_id}"))?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("project {project_id} does not exist"));
+    }
+
+    let project_info: ProjectInfo = resp.json()?;
+
+    let latest = lockfile.and_then(|lockfile| {
+        let options = super::ResolveOptions::default();
+        let mut compat = super::Compatibility::new(&options);
+
+        get_latest_version(lockfile, &project_info.name, &options, &mut compat).ok()
+    });
+
+    let mut loaders: Vec<String> = project_info
+        .supported_platforms
+        .keys()
+        .map(|p| p.to_lowercase())
+        .collect();
+    loaders.sort();
+
+    // Every platform lists its own versions, they usually overlap
+    let mut game_versions: Vec<String> = project_info
+        .supported_platforms
+        .into_values()
+        .flatten()
+        .collect();
+    game_versions.sort_by(|a, b| version::compare_versions(a, b));
+    game_versions.dedup();
+
+    Ok(super::ProjectDetails {
+        name: project_info.name.to_lowercase(),
+        description: project_info.description,
+        license: project_info.settings.license.name,
+        downloads: project_info.stats.downloads,
+        loaders,
+        game_versions,
+        latest,
+    })
+}
+
+pub fn fetch(
+    lockfile: &Lockfile,
+    project_id: &str,
+    version: &str,
+    options: &super::ResolveOptions,
+) -> Result<super::Info> {
     info!("fetching info of project {project_id}");
 
     let formatted_url = format!("{BASE_URL}/projects/{project_id}");
-    let mut resp = mup::get(&formatted_url).call()?;
+    let resp = mup::get(&formatted_url)?;
 
-    if resp.status() == 404 {
+    if resp.status == 404 {
         return Err(anyhow!("project {project_id} does not exist"));
     }
 
-    let project_info: ProjectInfo = resp.body_mut().read_json()?;
+    let project_info: ProjectInfo = resp.json()?;
     let project = project_info.name;
 
-    let version = if version == "latest" {
-        info!("fetching latest version of project {project}");
+    let mut compat = super::Compatibility::new(options);
 
-        let formatted_url = format!("{BASE_URL}/projects/{project}/latestrelease");
+    if lockfile.loader.name == "folia" && options.loader.is_none() {
+        compat.check(
+            project_info.settings.tags.iter().any(|t| t == "SUPPORTS_FOLIA"),
+            || format!("{project} does not declare Folia support, most Paper plugins do not run on Folia"),
+        )?;
+    }
 
-        mup::get_string(&formatted_url)?
+    let version = if version == "latest" {
+        get_latest_version(lockfile, &project, options, &mut compat)?
     } else {
         version.into()
     };
@@ -74,22 +280,29 @@ pub fn fetch(lockfile: &Lockfile, project_id: &str, version: &str) -> Result<sup
     let formatted_url = format!("{BASE_URL}/projects/{project}/versions/{version}");
     let version_info: VersionInfo = mup::get_json(&formatted_url)?;
 
-    let loader = lockfile.loader.name.to_uppercase();
+    let loader = server_platform(lockfile, options);
     if !version_info.platform_dependencies.contains_key(&loader) {
         return Err(anyhow!(
             "{project} version {version} does not support {loader}"
         ));
     }
 
-    let minecraft_version = SemVer::new(&lockfile.loader.minecraft_version).unwrap();
-    let is_compatible = version_info.platform_dependencies[&loader]
-        .iter()
-        .filter_map(SemVer::new)
-        .any(|v| v == minecraft_version);
+    let minecraft_version = MinecraftVersion::parse(&lockfile.loader.minecraft_version)
+        .ok_or_else(|| {
+            anyhow!(
+                "invalid minecraft version {}",
+                lockfile.loader.minecraft_version
+            )
+        })?;
+    let is_compatible = super::supports_minecraft(
+        &lockfile.loader,
+        &version_info.platform_dependencies[&loader],
+        options,
+    );
 
-    if !is_compatible {
-        return Err(anyhow!("{project} version {version} is incompatible with Minecraft version {minecraft_version}"));
-    }
+    compat.check(is_compatible, || {
+        format!("{project} version {version} is incompatible with Minecraft version {minecraft_version}")
+    })?;
 
     let dependencies = if version_info.dependencies.contains_key(&loader) {
         let deps = version_info.dependencies[&loader]
@@ -112,8 +325,141 @@ pub fn fetch(lockfile: &Lockfile, project_id: &str, version: &str) -> Result<sup
             method: String::from("sha256"),
             hash: version_info.downloads[&loader].file_info.sha256.clone(),
         }),
+        size: Some(version_info.downloads[&loader].file_info.size),
         dependencies,
+        forced: compat.into_bypassed(),
+        location: lockfile.loader.is_hybrid().then(|| String::from("plugins")),
+        ..Default::default()
     };
 
     Ok(info)
 }
+
+// Changelogs of every version for this platform published after `from`, up to and including `to`
+pub fn changelogs(
+    lockfile: &Lockfile,
+    project: &str,
+    from: &str,
+    to: &str,
+) -> Result<Vec<super::changelog::Changelog>> {
+    info!("fetching changelogs of project {project}");
+
+    let loader = platform(lockfile.loader.plugin_loader());
+    let formatted_url =
+        format!("{BASE_URL}/projects/{project}/versions?limit=25&platform={loader}");
+    let versions: Versions = mup::get_json(&formatted_url)?;
+
+    let created = |name: &str| {
+        versions
+            .result
+            .iter()
+            .find(|v| v.name == name)
+            .map(|v| v.created_at.clone())
+    };
+
+    let end = created(to).ok_or_else(|| anyhow!("version {to} of {project} not found"))?;
+    // Versions that fell out of the first page are older than everything listed
+    let start = created(from).unwrap_or_default();
+
+    let mut between: Vec<VersionSummary> = versions
+        .result
+        .into_iter()
+        .filter(|v| v.created_at > start && v.created_at <= end)
+        .collect();
+    between.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    Ok(between
+        .into_iter()
+        .map(|v| super::changelog::Changelog {
+            version: v.name,
+            text: v.description,
+        })
+        .collect())
+}
+
+fn get_latest_version(
+    lockfile: &Lockfile,
+    project: &str,
+    options: &super::ResolveOptions,
+    compat: &mut super::Compatibility,
+) -> Result<String> {
+    info!("fetching latest version of project {project}");
+
+    let loader = server_platform(lockfile, options);
+    let minecraft_version = &lockfile.loader.minecraft_version;
+
+    let formatted_url =
+        format!("{BASE_URL}/projects/{project}/versions?limit=25&platform={loader}");
+    let versions: Versions = mup::get_json(&formatted_url)?;
+
+    let (compatible, incompatible): (Vec<_>, Vec<_>) = versions.result.into_iter().partition(|v| {
+        v.platform_dependencies
+            .get(&loader)
+            .is_some_and(|specs| super::supports_minecraft(&lockfile.loader, specs, options))
+    });
+
+    let candidates = if compatible.is_empty() && !incompatible.is_empty() {
+        compat.check(false, || {
+            format!(
+                "{project} for {loader} has no version that supports Minecraft {minecraft_version}"
+            )
+        })?;
+
+        incompatible
+    } else {
+        compatible
+    };
+
+    let latest = super::select_latest(candidates, options, |v| {
+        (
+            v.name.as_str(),
+            v.created_at.as_str(),
+            v.channel.name.as_str(),
+        )
+    })
+    .ok_or_else(|| {
+        let matching = super::constraint_note(options);
+        anyhow!("{project} for {loader} has no version{matching} that supports Minecraft {minecraft_version}")
+    })?;
+
+    Ok(latest.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        loader::Loader,
+        plugin::{Compatibility, ResolveOptions},
+    };
+
+    #[test]
+    fn test_latest_version_within_window() {
+        let cassette =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cassettes/hangar-latest-version");
+        let lockfile = Lockfile {
+            loader: Loader::new("paper", "1.21.1", "latest", false),
+            ..Default::default()
+        };
+        let strict = ResolveOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        let (relaxed, strict) = mup::cassette::replaying(&cassette, || {
+            let latest = |options| {
+                let mut compat = Compatibility::new(options);
+                get_latest_version(&lockfile, "chunky", options, &mut compat)
+            };
+
+            (latest(&ResolveOptions::default()), latest(&strict))
+        })
+        .unwrap();
+
+        // 1.21 covers 1.21.1, which an exact match on 1.21.1 alone would reject
+        assert_eq!(relaxed.unwrap(), "3.0.0");
+        assert!(strict.is_err());
+    }
+}
@@ -0,0 +1,71 @@
+#![allow(clippy::case_sensitive_file_extension_comparisons)]
+
+use anyhow::{anyhow, Result};
+use mup::jenkins::{self, Artifact};
+
+use crate::server::lockfile::Lockfile;
+
+// `job_url` is the full URL of the job, e.g. https://ci.ender.zone/job/EssentialsX/
+pub fn fetch(
+    lockfile: &Lockfile,
+    job_url: &str,
+    version: &str,
+    options: &super::ResolveOptions,
+) -> Result<super::Info> {
+    let job_url = job_url.trim_end_matches('/');
+
+    let name = job_url
+        .rsplit('/')
+        .next()
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| anyhow!("invalid jenkins job url {job_url}"))?
+        .to_lowercase();
+
+    // Updates reuse the artifact filter the entry was added with
+    let filter = options
+        .artifact
+        .clone()
+        .or_else(|| lockfile.get(job_url).ok().and_then(|p| p.artifact.clone()));
+
+    let build = jenkins::build(job_url, version)?;
+
+    let candidates: Vec<&Artifact> = build
+        .artifacts
+        .iter()
+        .filter(|a| a.file_name.ends_with(".jar"))
+        .filter(|a| {
+            filter
+                .as_ref()
+                .is_none_or(|f| mup::glob_match(f, &a.file_name))
+        })
+        .collect();
+
+    let artifact = match candidates.as_slice() {
+        [artifact] => artifact,
+        [] => {
+            return Err(anyhow!(
+                "build {} has no matching jar artifacts",
+                build.number
+            ))
+        }
+        _ => {
+            let names: Vec<&str> = candidates.iter().map(|a| a.file_name.as_str()).collect();
+
+            return Err(anyhow!(
+                "build {} has multiple jar artifacts, pick one with --artifact: {}",
+                build.number,
+                names.join(", ")
+            ));
+        }
+    };
+
+    Ok(super::Info {
+        name,
+        id: job_url.to_string(),
+        version: build.number.to_string(),
+        source: String::from("jenkins"),
+        download_url: build.artifact_url(artifact),
+        artifact: filter,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::Serialize;
+
+use super::{hangar, modrinth, Info};
+use crate::server::lockfile::Lockfile;
+
+#[derive(Default, Serialize)]
+pub struct License {
+    pub name: Option<String>,
+    /// SPDX identifier, only known when the provider uses one of the standard licenses
+    pub spdx: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    project: &'a str,
+    source: &'a str,
+    #[serde(flatten)]
+    license: License,
+}
+
+// Only Modrinth and Hangar publish license information
+fn fetch(info: &Info) -> Result<Option<License>> {
+    match info.source.as_str() {
+        "modrinth" => modrinth::license(&info.id).map(Some),
+        "hangar" => hangar::license(&info.name).map(Some),
+        _ => Ok(None),
+    }
+}
+
+pub fn licenses(json: bool) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+
+    let entries: Vec<Entry> = lockfile
+        .mods
+        .iter()
+        .map(|info| {
+            let license = fetch(info).unwrap_or_else(|e| {
+                warn!("failed to fetch the license of {}: {e}", info.name);
+                None
+            });
+
+            Entry {
+                project: &info.name,
+                source: &info.source,
+                license: license.unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("no projects installed");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|e| {
+            vec![
+                e.project.to_string(),
+                e.source.to_string(),
+                e.license.spdx.clone().unwrap_or_else(|| String::from("-")),
+                e.license
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| String::from("unknown")),
+            ]
+        })
+        .collect();
+
+    println!(
+        "{}",
+        mup::format_table(&["NAME", "SOURCE", "SPDX", "LICENSE"], &rows)
+    );
+
+    // Grouped by license so redistribution terms can be reviewed once per license
+    let mut summary: BTreeMap<&str, usize> = BTreeMap::new();
+    for e in &entries {
+        let key = e
+            .license
+            .spdx
+            .as_deref()
+            .or(e.license.name.as_deref())
+            .unwrap_or("unknown");
+
+        *summary.entry(key).or_default() += 1;
+    }
+
+    println!();
+    for (license, count) in summary {
+        println!("{license}: {count}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use crate::server::lockfile::Lockfile;
+
+#[derive(Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+struct Entry<'a> {
+    name: &'a str,
+    version: &'a str,
+    source: &'a str,
+    path: String,
+    dependency: bool,
+    pinned: bool,
+    disabled: bool,
+    force_client: bool,
+    groups: &'a [String],
+}
+
+pub fn list(json: bool) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+
+    let entries: Vec<Entry> = lockfile
+        .mods
+        .iter()
+        .map(|info| Entry {
+            name: &info.name,
+            version: &info.version,
+            source: &info.source,
+            path: info
+                .get_file_path(&lockfile.loader)
+                .to_string_lossy()
+                .into_owned(),
+            dependency: info.dependency,
+            pinned: info.pinned,
+            disabled: info.disabled,
+            force_client: info.force_client,
+            groups: &info.groups,
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("no projects installed");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = entries
+        .into_iter()
+        .map(|e| {
+            let flags = [
+                (e.dependency, "dependency"),
+                (e.pinned, "pinned"),
+                (e.disabled, "disabled"),
+                (e.force_client, "client"),
+            ]
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, flag)| *flag)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+            vec![
+                e.name.to_string(),
+                e.version.to_string(),
+                e.source.to_string(),
+                e.path,
+                e.groups.join(", "),
+                flags,
+            ]
+        })
+        .collect();
+
+    println!(
+        "{}",
+        mup::format_table(
+            &["NAME", "VERSION", "SOURCE", "PATH", "GROUPS", "FLAGS"],
+            &rows
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use sha2::Sha512;
+
+use super::verify::{self, Status};
+use crate::server::lockfile::Lockfile;
+
+// Local jars keep the path they were imported from as their download url,
+// so they can be copied again as long as that file is still around
+pub fn fetch(path: &str, version: &str) -> Result<super::Info> {
+    let path = Path::new(path);
+
+    if path
+        .extension()
+        .is_none_or(|ext| !ext.eq_ignore_ascii_case("jar"))
+    {
+        return Err(anyhow!("{} is not a jar file", path.display()));
+    }
+
+    let path = path
+        .canonicalize()
+        .map_err(|e| anyhow!("failed to read {}: {e}", path.display()))?;
+
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow!("invalid path {}", path.display()))?
+        .to_lowercase();
+
+    let source = path
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid path {}", path.display()))?;
+
+    info!("importing {source}");
+
+    let version = if version == "latest" {
+        "local"
+    } else {
+        version
+    };
+
+    Ok(super::Info {
+        name,
+        id: source.to_string(),
+        version: version.to_string(),
+        source: String::from("local"),
+        download_url: source.to_string(),
+        checksum: Some(super::Checksum {
+            method: String::from("sha512"),
+            hash: mup::hash_file::<Sha512>(&path)?,
+        }),
+        size: Some(fs::metadata(&path)?.len()),
+        ..Default::default()
+    })
+}
+
+pub fn install(lockfile: &Lockfile, info: &super::Info) -> Result<()> {
+    if matches!(verify::verify_entry(lockfile, info)?, Status::Ok) {
+        return Ok(());
+    }
+
+    let source = Path::new(&info.download_url);
+    if !source.exists() {
+        return Err(anyhow!(
+            "{} is a local plugin, copy {} to {} by hand",
+            info.name,
+            info.download_url,
+            info.get_file_path(&lockfile.loader).display()
+        ));
+    }
+
+    let target = info.get_file_path(&lockfile.loader);
+
+    info!("copying {} to {}", source.display(), target.display());
+
+    if let Some(prefix) = target.parent() {
+        fs::create_dir_all(prefix)?;
+    }
+    fs::copy(source, &target)?;
+
+    match verify::verify_entry(lockfile, info)? {
+        Status::Ok => Ok(()),
+        _ => Err(anyhow!(
+            "{} has changed since it was imported, add it again to update the lockfile",
+            info.download_url
+        )),
+    }
+}
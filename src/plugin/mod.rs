@@ -1,45 +1,227 @@
-use std::path::PathBuf;
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    sync::OnceLock,
+};
 
 use anyhow::{anyhow, Result};
-use clap::Subcommand;
-use log::info;
+use clap::{Args, Subcommand};
+use dialoguer::MultiSelect;
+use log::{info, warn};
+use mup::version::{self, MinecraftVersion};
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Sha512};
 
-use crate::{loader::Loader, server::lockfile::Lockfile};
+use crate::{
+    history,
+    loader::Loader,
+    server::{
+        hooks::{self, Event},
+        lockfile::Lockfile,
+    },
+};
 
+mod changelog;
+mod details;
+mod export;
+pub mod gc;
+mod geysermc;
 mod hangar;
-mod modrinth;
+mod jenkins;
+mod licenses;
+mod list;
+pub mod local;
+pub mod modrinth;
+mod search;
+mod transaction;
+mod tree;
+mod url;
+pub mod verify;
+mod why;
+
+use details::ProjectDetails;
+use search::SearchResult;
+use transaction::Transaction;
+
+// Directory downloads with a known checksum are shared through, so servers of a network only
+// download each jar once
+static SHARED_CACHE: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn share_downloads(dir: PathBuf) {
+    let _ = SHARED_CACHE.set(dir);
+}
 
 #[derive(Debug, Subcommand)]
 pub enum Plugin {
     /// Add mods or plugins and their dependencies
-    Add {
+    Add(Add),
+    /// Remove installed mods or plugins
+    Remove {
+        /// The project IDs, slugs or glob patterns such as "essentialsx*"
+        #[arg(required = true)]
+        ids: Vec<String>,
+
+        /// Keep the downloaded jarfiles
+        #[arg(long, action)]
+        keep_jarfile: bool,
+
+        /// Also remove dependencies that nothing else requires anymore
+        #[arg(long, action)]
+        remove_orphans: bool,
+
+        /// Do not ask for confirmation when removing multiple projects
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+    /// Remove dependencies that no installed project requires anymore
+    Autoremove {
+        /// Keep the downloaded jarfiles
+        #[arg(long, action)]
+        keep_jarfile: bool,
+
+        /// Do not ask for confirmation
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+    /// Substitute or ignore a dependency whenever another project requires it
+    Override {
+        /// Name or project ID of the dependency
+        dependency: String,
+
+        /// The project to install instead
+        #[arg(long, required_unless_present_any = ["ignore", "clear"])]
+        with: Option<String>,
+
+        /// Never install this dependency
+        #[arg(long, action, conflicts_with = "with")]
+        ignore: bool,
+
+        /// Remove the existing override
+        #[arg(long, action, conflicts_with_all = ["with", "ignore"])]
+        clear: bool,
+    },
+    /// Declare that a project satisfies dependencies on other projects
+    Provides {
+        /// Name or project ID of the project, such as "vaultunlocked"
+        project: String,
+
+        /// Names or project IDs of the dependencies it satisfies, such as "vault"
+        #[arg(required_unless_present = "clear")]
+        dependencies: Vec<String>,
+
+        /// Remove every declaration for the project
+        #[arg(long, action, conflicts_with = "dependencies")]
+        clear: bool,
+    },
+    /// List installed mods or plugins
+    List {
+        /// Print the lockfile entries as JSON
+        #[arg(long, action)]
+        json: bool,
+    },
+    /// Show installed projects and the dependencies they pulled in
+    Tree,
+    /// Explain why a project is installed and what requires it
+    Why {
+        /// The project ID or slug
+        #[clap(alias = "slug")]
+        id: String,
+    },
+    /// Show details about a project and whether it is installed
+    Info {
         /// The project ID or slug
         #[clap(alias = "slug")]
         id: String,
 
-        /// Which provider to download dependencies from
+        /// Which provider to look the project up on when it is not installed
         #[arg(short, long, default_value = "modrinth", value_parser = ["modrinth", "hangar"])]
         provider: String,
+    },
+    /// Search for projects, filtered by the server's loader and Minecraft version
+    Search {
+        /// What to search for
+        query: String,
 
-        /// The version to add.
-        /// For Modrinth plugins, this is the version ID.
-        #[arg(short, long, default_value = "latest")]
-        version: String,
+        /// Which provider to search
+        #[arg(short, long, default_value = "modrinth", value_parser = ["modrinth", "hangar"])]
+        provider: String,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+
+        /// Print the results as JSON
+        #[arg(long, action)]
+        json: bool,
+    },
+    /// Check installed jarfiles against the checksums in the lockfile
+    Verify {
+        /// Number of files to hash in parallel
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Print the license of every installed project
+    Licenses {
+        /// Print the licenses as JSON
+        #[arg(long, action)]
+        json: bool,
+    },
+    /// Write a manifest of the installed projects, e.g. for a server wiki
+    Export {
+        /// Format of the manifest
+        #[arg(long, default_value = "md", value_parser = ["md", "csv", "json"])]
+        format: String,
+
+        /// File to write the manifest to instead of printing it
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Delete jarfiles in the mod directories that are not in the lockfile
+    Gc {
+        /// Only list the jarfiles that would be deleted
+        #[arg(long, action)]
+        dry_run: bool,
 
-        /// Do not install any dependencies
+        /// Do not ask for confirmation
         #[arg(short, long, action)]
-        no_deps: bool,
+        yes: bool,
     },
-    /// Remove an installed mod or plugin
-    Remove {
-        /// The project ID or slug
-        id: String,
+    /// List projects that have newer compatible versions
+    Outdated {
+        /// Consider pre-release versions when resolving the latest version
+        #[arg(long, action)]
+        include_prerelease: bool,
 
-        /// Keep the downloaded jarfile
+        /// Require exact Minecraft version matches, even for Paper plugins
         #[arg(long, action)]
-        keep_jarfile: bool,
+        strict: bool,
+    },
+    /// Hold a project at its installed version so updates skip it
+    Pin {
+        /// The project ID or slug
+        #[clap(alias = "slug")]
+        id: String,
+    },
+    /// Let updates change a pinned project again
+    Unpin {
+        /// The project ID or slug
+        #[clap(alias = "slug")]
+        id: String,
+    },
+    /// Stop the server from loading a project without removing it
+    Disable {
+        /// The project ID or slug
+        #[clap(alias = "slug")]
+        id: String,
+    },
+    /// Let the server load a disabled project again
+    Enable {
+        /// The project ID or slug
+        #[clap(alias = "slug")]
+        id: String,
     },
     /// Update mods or plugins
     Update {
@@ -51,10 +233,191 @@ pub enum Plugin {
         /// For Modrinth plugins, this is the version ID.
         #[arg(short, long, default_value = "latest")]
         version: String,
+
+        /// Do not ask for confirmation before downloading
+        #[arg(short, long, action)]
+        yes: bool,
+
+        /// Consider pre-release versions when resolving the latest version
+        #[arg(long, action)]
+        include_prerelease: bool,
+
+        /// Require exact Minecraft version matches, even for Paper plugins
+        #[arg(long, action)]
+        strict: bool,
+
+        /// Only print the changelogs of available updates
+        #[arg(long, action)]
+        changelog_only: bool,
+
+        /// Least stable release channel to consider, instead of the one recorded in the lockfile
+        #[arg(long, value_parser = ["release", "beta", "alpha"])]
+        channel: Option<String>,
+
+        /// Only update projects in this group
+        #[arg(short, long, conflicts_with = "id")]
+        group: Option<String>,
     },
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Debug, Args)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Add {
+    /// The project IDs or slugs
+    #[clap(alias = "slug", required_unless_present = "collection")]
+    ids: Vec<String>,
+
+    /// Install every project of a Modrinth collection that works on this server
+    #[arg(long, conflicts_with_all = ["ids", "version"])]
+    collection: Option<String>,
+
+    /// Which provider to download dependencies from.
+    /// For Jenkins, the project ID is the URL of the job, for url it is a direct link to a jar
+    /// and for local it is the path to a jar on disk.
+    #[arg(short, long, default_value = "modrinth", value_parser = ["modrinth", "hangar", "jenkins", "url", "local", "geysermc"])]
+    provider: String,
+
+    /// The version to add.
+    /// For Modrinth plugins, this is the version ID.
+    #[arg(short, long, default_value = "latest")]
+    version: String,
+
+    /// Do not install any dependencies
+    #[arg(short, long, action)]
+    no_deps: bool,
+
+    /// Consider pre-release versions when resolving the latest version
+    #[arg(long, action)]
+    include_prerelease: bool,
+
+    /// Downgrade loader and Minecraft version mismatches to warnings
+    #[arg(long, action, alias = "allow-mismatch")]
+    force: bool,
+
+    /// Require exact Minecraft version matches, even for Paper plugins
+    #[arg(long, action)]
+    strict: bool,
+
+    /// Glob pattern selecting which Jenkins build artifact to install
+    #[arg(long)]
+    artifact: Option<String>,
+
+    /// Version constraint that updates must satisfy, such as "~2.19" or ">=2.1, <3".
+    /// Only supported for Modrinth and Hangar.
+    #[arg(long)]
+    constraint: Option<String>,
+
+    /// Least stable release channel to consider, only supported for Modrinth and Hangar
+    #[arg(long, value_parser = ["release", "beta", "alpha"])]
+    channel: Option<String>,
+
+    /// Resolve versions made for this loader instead of the server's, such as "bukkit".
+    /// Only supported for Modrinth and Hangar.
+    #[arg(long)]
+    loader: Option<String>,
+
+    /// Install Modrinth mods that declare they are required on the client but not the server
+    #[arg(long, action)]
+    force_client: bool,
+
+    /// Tag the projects with a group, can be given multiple times
+    #[arg(long = "group")]
+    groups: Vec<String>,
+}
+
+impl Add {
+    fn options(&self) -> ResolveOptions {
+        ResolveOptions {
+            include_prerelease: self.include_prerelease,
+            force: self.force,
+            strict: self.strict,
+            artifact: self.artifact.clone(),
+            constraint: self.constraint.clone(),
+            channel: self.channel.clone(),
+            loader: self.loader.clone(),
+            force_client: self.force_client,
+            groups: self.groups.clone(),
+        }
+    }
+}
+
+// How a dependency should be handled during resolution, keyed by the dependency's name or ID
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Override {
+    Replace(String),
+    Ignore,
+}
+
+// Why a project is being installed, which decides what gets recorded about it in the lockfile
+#[derive(Clone, Copy)]
+pub enum Reason<'a> {
+    Requested,
+    Update,
+    DependencyOf(&'a str),
+}
+
+#[derive(Clone, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ResolveOptions {
+    pub include_prerelease: bool,
+    pub force: bool,
+    pub strict: bool,
+    pub artifact: Option<String>,
+    /// Only versions satisfying this, e.g. `~2.19`, are considered when resolving the latest
+    pub constraint: Option<String>,
+    /// Least stable channel, release, beta or alpha, versions are picked from
+    pub channel: Option<String>,
+    /// Loader versions are resolved for in place of the server's
+    pub loader: Option<String>,
+    /// Install client-side mods on mod loaders, which the server usually fails to load
+    pub force_client: bool,
+    /// Groups an explicitly added project is tagged with
+    pub groups: Vec<String>,
+}
+
+// Collects compatibility problems, which are only fatal when the resolution is not forced
+pub struct Compatibility {
+    force: bool,
+    bypassed: Vec<String>,
+}
+
+impl Compatibility {
+    pub const fn new(options: &ResolveOptions) -> Self {
+        Self {
+            force: options.force,
+            bypassed: vec![],
+        }
+    }
+
+    pub fn check(&mut self, compatible: bool, message: impl FnOnce() -> String) -> Result<()> {
+        if compatible {
+            return Ok(());
+        }
+
+        let message = message();
+
+        if !self.force {
+            return Err(anyhow!("{message} (use --force to install it anyway)"));
+        }
+
+        warn!("{message}, continuing anyway");
+        self.bypassed.push(message);
+
+        Ok(())
+    }
+
+    pub const fn is_forced(&self) -> bool {
+        self.force
+    }
+
+    pub fn into_bypassed(self) -> Vec<String> {
+        self.bypassed
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Info {
     pub name: String,
     pub id: String,
@@ -62,8 +425,82 @@ pub struct Info {
     pub source: String,
     pub download_url: String,
 
+    #[serde(serialize_with = "sorted_dependencies")]
     pub dependencies: Option<Vec<Dependency>>,
     pub checksum: Option<Checksum>,
+    pub size: Option<u64>,
+
+    /// Compatibility checks that were bypassed with --force when this entry was added
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forced: Vec<String>,
+
+    /// Glob pattern selecting the build artifact for Jenkins entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<String>,
+
+    /// Directory the jar goes in when it isn't the loader's default, e.g. plugins on hybrid servers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    /// Name of the jar when the download url doesn't end with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+
+    /// Whether this was only installed because another project requires it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dependency: bool,
+
+    /// Projects whose installation pulled this one in as a dependency
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "mup::serialize_sorted"
+    )]
+    pub required_by: Vec<String>,
+
+    /// Held at its current version, updates skip it until it is unpinned
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+
+    /// Version constraint the project is held to when updating
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<String>,
+
+    /// Least stable release channel updates are picked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+
+    /// Loader the project was resolved for when it differs from the server's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<String>,
+
+    /// Kept out of the server's plugin directory, in a sibling directory suffixed with -disabled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+
+    /// User-defined groups, such as "admin" or "seasonal", for group-scoped operations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+
+    /// Installed with --force-client even though the project only works on clients
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub force_client: bool,
+}
+
+// Written in a stable order, like the lockfile entries themselves. serde hands fields over by
+// reference, hence the &Option
+#[allow(clippy::ref_option)]
+fn sorted_dependencies<S: serde::Serializer>(
+    dependencies: &Option<Vec<Dependency>>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let sorted = dependencies.as_ref().map(|deps| {
+        let mut sorted: Vec<&Dependency> = deps.iter().collect();
+        sorted.sort_by(|a, b| a.name.cmp(&b.name));
+        sorted
+    });
+
+    sorted.serialize(serializer)
 }
 
 #[derive(Deserialize, Serialize)]
@@ -74,8 +511,16 @@ pub struct Checksum {
 
 impl Info {
     pub fn get_file_path(&self, loader: &Loader) -> PathBuf {
-        let filename = self.download_url.rsplit_once('/').unwrap().1;
-        let formatted = format!("{}/{}", loader.mod_location(), filename);
+        let filename = self
+            .file_name
+            .as_deref()
+            .unwrap_or_else(|| self.download_url.rsplit_once('/').unwrap().1);
+        let location = self
+            .location
+            .as_deref()
+            .unwrap_or_else(|| loader.mod_location());
+        let suffix = if self.disabled { "-disabled" } else { "" };
+        let formatted = format!("{location}{suffix}/{filename}");
 
         formatted.into()
     }
@@ -89,6 +534,12 @@ pub struct Dependency {
     pub source: String,
     pub name: String,
     pub required: bool,
+    /// The project refuses to run alongside this one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub incompatible: bool,
+    /// Bundled inside the project's jar, so it must not be installed separately
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub embedded: bool,
 }
 
 impl From<modrinth::ModrinthDependency> for Dependency {
@@ -98,6 +549,8 @@ impl From<modrinth::ModrinthDependency> for Dependency {
             source: "modrinth".to_string(),
             name: val.slug.to_lowercase(),
             required: val.dependency_type == "required",
+            incompatible: val.dependency_type == "incompatible",
+            embedded: val.dependency_type == "embedded",
         }
     }
 }
@@ -109,6 +562,8 @@ impl From<&hangar::HangarDependency> for Dependency {
             source: "hangar".to_string(),
             name: val.name.to_lowercase(),
             required: val.required,
+            incompatible: false,
+            embedded: false,
         }
     }
 }
@@ -125,124 +580,998 @@ impl PartialEq for Dependency {
 
 pub fn action(plugin: &Plugin) -> Result<()> {
     match plugin {
-        Plugin::Add {
-            id,
+        Plugin::Add(args) => {
+            history::record(|| hooks::around(Event::Install, &args.ids, || add_requested(args)))?;
+        }
+        Plugin::Remove {
+            ids,
+            keep_jarfile,
+            remove_orphans,
+            yes,
+        } => history::record(|| remove_all(ids, *keep_jarfile, *remove_orphans, *yes))?,
+        Plugin::Autoremove { keep_jarfile, yes } => {
+            history::record(|| autoremove(*keep_jarfile, *yes))?;
+        }
+        Plugin::Override {
+            dependency,
+            with,
+            ignore,
+            clear,
+        } => history::record(|| set_override(dependency, with.as_deref(), *ignore, *clear))?,
+        Plugin::Provides {
+            project,
+            dependencies,
+            clear,
+        } => history::record(|| set_provides(project, dependencies, *clear))?,
+        Plugin::List { json } => list::list(*json)?,
+        Plugin::Tree => tree::tree()?,
+        Plugin::Why { id } => why::why(id)?,
+        Plugin::Info { id, provider } => details::show(id, provider)?,
+        Plugin::Search {
+            query,
             provider,
+            limit,
+            json,
+        } => search::search(query, provider, *limit, *json)?,
+        Plugin::Verify { jobs } => verify::verify(*jobs)?,
+        Plugin::Licenses { json } => licenses::licenses(*json)?,
+        Plugin::Export { format, output } => export::export(format, output.as_deref())?,
+        Plugin::Gc { dry_run, yes } => gc::gc(*dry_run, *yes)?,
+        Plugin::Outdated {
+            include_prerelease,
+            strict,
+        } => outdated(&ResolveOptions {
+            include_prerelease: *include_prerelease,
+            strict: *strict,
+            ..Default::default()
+        })?,
+        Plugin::Pin { id } => history::record(|| set_pinned(id, true))?,
+        Plugin::Disable { id } => history::record(|| set_enabled(id, false))?,
+        Plugin::Enable { id } => history::record(|| set_enabled(id, true))?,
+        Plugin::Unpin { id } => history::record(|| set_pinned(id, false))?,
+        Plugin::Update {
+            id,
             version,
-            no_deps,
-        } => {
-            add(provider, id, version, *no_deps)?;
-        }
-        Plugin::Remove { id, keep_jarfile } => remove(id, *keep_jarfile)?,
-        Plugin::Update { id, version } => update(id, version)?,
+            yes,
+            include_prerelease,
+            strict,
+            changelog_only,
+            channel,
+            group,
+        } => history::record(|| {
+            update(
+                id,
+                version,
+                *yes,
+                *changelog_only,
+                group.as_deref(),
+                &ResolveOptions {
+                    include_prerelease: *include_prerelease,
+                    strict: *strict,
+                    channel: channel.clone(),
+                    ..Default::default()
+                },
+            )
+        })?,
     }
 
     Ok(())
 }
 
-pub fn add(provider: &str, project_id: &str, version: &str, no_deps: bool) -> Result<()> {
-    info!("adding {project_id} version {version} from {provider}");
-
-    let mut lockfile = Lockfile::init()?;
+pub fn add(
+    provider: &str,
+    project_ids: &[&str],
+    version: &str,
+    no_deps: bool,
+    reason: Reason,
+    options: &ResolveOptions,
+) -> Result<()> {
+    let lockfile = Lockfile::init()?;
 
     if !lockfile.is_initialized() {
+        return Err(anyhow!("Server must be initialized before adding projects"));
+    }
+
+    if lockfile.loader.name == "vanilla" || lockfile.loader.is_bedrock() {
         return Err(anyhow!(
-            "Server must be initialized before removing projects"
+            "{} servers do not support plugins",
+            lockfile.loader.name
+        ));
+    }
+
+    if project_ids.len() > 1 && version != "latest" {
+        return Err(anyhow!(
+            "--version can only be used when adding a single project"
         ));
     }
 
-    if lockfile.loader.name == "vanilla" {
-        return Err(anyhow!("vanilla servers do not support plugins"));
+    if options.channel.is_some() && !matches!(provider, "modrinth" | "hangar") {
+        return Err(anyhow!("--channel is not supported for {provider}"));
     }
 
-    let old_version = lockfile.get(project_id).ok();
+    if options.loader.is_some() && !matches!(provider, "modrinth" | "hangar") {
+        return Err(anyhow!("--loader is not supported for {provider}"));
+    }
 
-    let info = match provider {
-        "modrinth" => modrinth::fetch(&lockfile, project_id, version)?,
-        "hangar" => hangar::fetch(&lockfile, project_id, version)?,
-        _ => unimplemented!(),
+    if let Some(constraint) = &options.constraint {
+        if !matches!(provider, "modrinth" | "hangar") {
+            return Err(anyhow!("--constraint is not supported for {provider}"));
+        }
+
+        if version::satisfies_constraint("0", constraint).is_none() {
+            return Err(anyhow!("invalid version constraint {constraint}"));
+        }
+    }
+
+    let mut transaction = Transaction::new(lockfile);
+
+    let result = project_ids.iter().try_for_each(|id| {
+        install(
+            &mut transaction,
+            provider,
+            id,
+            version,
+            no_deps,
+            reason,
+            options,
+        )
+    });
+
+    // Nothing is kept unless every project and its dependencies were installed
+    match result {
+        Ok(()) => transaction.commit(),
+        Err(e) => {
+            transaction.rollback();
+            Err(e)
+        }
+    }
+}
+
+// Projects of a collection that fail to install, usually because they do not support this
+// server, are skipped and reported at the end instead of aborting the rest of the collection
+fn add_requested(args: &Add) -> Result<()> {
+    let options = &args.options();
+
+    let Some(collection) = &args.collection else {
+        let ids: Vec<&str> = args.ids.iter().map(String::as_str).collect();
+        return add(
+            &args.provider,
+            &ids,
+            &args.version,
+            args.no_deps,
+            Reason::Requested,
+            options,
+        );
     };
 
-    if let Some(p) = old_version {
-        if p.name == project_id && p.version == info.version {
+    if args.provider != "modrinth" {
+        return Err(anyhow!("--collection is only supported for modrinth"));
+    }
+
+    let collection = modrinth::collection(collection)?;
+    let ids: Vec<&str> = collection.projects.iter().map(String::as_str).collect();
+    let slugs = modrinth::project_slugs(&ids)?;
+
+    let mut installed = 0;
+    let mut skipped = vec![];
+
+    for id in ids {
+        let name = slugs.get(id).map_or(id, String::as_str);
+
+        let lockfile = Lockfile::init()?;
+        if lockfile.get(id).is_ok() || lockfile.get(name).is_ok() {
+            skipped.push(format!("{name}: already installed"));
+            continue;
+        }
+
+        let added = add(
+            &args.provider,
+            &[id],
+            "latest",
+            args.no_deps,
+            Reason::Requested,
+            options,
+        );
+
+        match added {
+            Ok(()) => installed += 1,
+            Err(e) => skipped.push(format!("{name}: {e}")),
+        }
+    }
+
+    println!(
+        "installed {installed} of {} projects from {}",
+        collection.projects.len(),
+        collection.name
+    );
+
+    if !skipped.is_empty() {
+        println!("skipped {}:", skipped.len());
+        for reason in skipped {
+            println!("  {reason}");
+        }
+    }
+
+    Ok(())
+}
+
+// Installs a project and its dependencies into the in-memory lockfile, dependencies see
+// everything added earlier in the same run
+fn install(
+    transaction: &mut Transaction,
+    provider: &str,
+    project_id: &str,
+    version: &str,
+    no_deps: bool,
+    reason: Reason,
+    options: &ResolveOptions,
+) -> Result<()> {
+    info!("adding {project_id} version {version} from {provider}");
+
+    let old = transaction.lockfile.get(project_id).ok();
+    let options = &recorded_options(options, old, reason);
+
+    let info = fetch(
+        &transaction.lockfile,
+        provider,
+        project_id,
+        version,
+        options,
+    )?;
+
+    if let Some(old) = old {
+        if old.version == info.version {
+            if let Reason::DependencyOf(parent) = reason {
+                info!("dependency {} is already installed", info.name);
+
+                let existing = transaction.lockfile.get_mut(&info.name)?;
+                if !existing.required_by.iter().any(|p| p == parent) {
+                    existing.required_by.push(parent.to_string());
+                }
+
+                return Ok(());
+            }
+
             return Err(anyhow!(
                 "Project '{project_id}' version {version} is already installed"
             ));
         }
     }
 
+    install_resolved(transaction, provider, info, no_deps, reason, options)
+}
+
+// Installs a version that was already fetched, replacing whatever version is installed
+fn install_resolved(
+    transaction: &mut Transaction,
+    provider: &str,
+    mut info: Info,
+    no_deps: bool,
+    reason: Reason,
+    options: &ResolveOptions,
+) -> Result<()> {
+    let old = transaction.lockfile.get(&info.name).ok();
+    let replacing = old.is_some();
+    carry_over(&mut info, old, reason, options);
+
+    let mut compat = Compatibility::new(options);
+    for conflict in find_conflicts(&transaction.lockfile, &info) {
+        compat.check(false, || conflict)?;
+    }
+    info.forced.extend(compat.into_bypassed());
+
     if let Some(deps) = &info.dependencies {
         for dep in deps {
             if no_deps {
                 break;
             }
 
+            if dep.embedded {
+                info!("{} is bundled with {}", dep.name, info.name);
+                continue;
+            }
+
             if !dep.required {
                 continue;
             }
 
-            add(provider, &dep.id, "latest", false)?;
+            match transaction.lockfile.get_override(dep) {
+                Some(Override::Ignore) => {
+                    info!("ignoring dependency {} because of an override", dep.name);
+                }
+                Some(Override::Replace(replacement)) => {
+                    info!(
+                        "installing {replacement} in place of dependency {}",
+                        dep.name
+                    );
+
+                    let replacement = replacement.clone();
+                    install(
+                        transaction,
+                        provider,
+                        &replacement,
+                        "latest",
+                        false,
+                        Reason::DependencyOf(&info.name),
+                        options,
+                    )?;
+                }
+                None => {
+                    if let Some(satisfied_by) = transaction.lockfile.get_provider(dep) {
+                        info!(
+                            "dependency {} is provided by {}",
+                            dep.name, satisfied_by.name
+                        );
+                        continue;
+                    }
+
+                    install(
+                        transaction,
+                        provider,
+                        &dep.id,
+                        "latest",
+                        false,
+                        Reason::DependencyOf(&info.name),
+                        options,
+                    )?;
+                }
+            }
         }
     }
 
-    if old_version.is_some_and(|p| p.version != info.version) {
+    if replacing {
         info!("removing old version of {}", info.name);
 
-        remove(&info.name, false)?;
+        let old_path = transaction
+            .lockfile
+            .get(&info.name)?
+            .get_file_path(&transaction.lockfile.loader);
+        transaction.set_aside(&old_path)?;
+        transaction.lockfile.remove_entry(&info.name, true)?;
     }
 
-    download_plugin(&lockfile, &info)?;
-
-    lockfile.add(info)
-}
+    transaction.download(&info)?;
 
-pub fn download_plugin(lockfile: &Lockfile, info: &Info) -> Result<()> {
-    info!(
-        "downloading {} for {} version {}",
-        info.name, lockfile.loader.name, info.version
-    );
+    let info = record_checksum(&transaction.lockfile, info)?;
 
-    let file_path = info.get_file_path(&lockfile.loader);
+    transaction.lockfile.add(info);
 
-    info.checksum.as_ref().map_or_else(
-        || mup::download(&info.download_url, &file_path),
-        |checksum| {
-            info!(
-                "downloading jarfile to {} from {}",
-                file_path.to_str().unwrap(),
-                info.download_url
-            );
+    Ok(())
+}
 
-            match checksum.method.as_str() {
-                "sha256" => mup::download_with_checksum::<Sha256>(
-                    &info.download_url,
-                    &file_path,
-                    &checksum.hash,
-                ),
-                "sha512" => mup::download_with_checksum::<Sha512>(
-                    &info.download_url,
-                    &file_path,
-                    &checksum.hash,
-                ),
-                _ => unimplemented!(),
-            }
+// Only an explicit add sets the constraint and loader, everything else keeps the recorded ones
+fn recorded_options(
+    options: &ResolveOptions,
+    old: Option<&Info>,
+    reason: Reason,
+) -> ResolveOptions {
+    ResolveOptions {
+        constraint: match reason {
+            Reason::Requested => options.constraint.clone(),
+            _ => old.and_then(|p| p.constraint.clone()),
         },
-    )
+        loader: match reason {
+            Reason::Requested => options.loader.clone(),
+            _ => old.and_then(|p| p.loader.clone()),
+        },
+        channel: match reason {
+            Reason::Requested => options.channel.clone(),
+            _ => options
+                .channel
+                .clone()
+                .or_else(|| old.and_then(|p| p.channel.clone())),
+        },
+        force_client: options.force_client || old.is_some_and(|p| p.force_client),
+        ..options.clone()
+    }
 }
 
-fn remove(id: &str, keep_jarfile: bool) -> Result<()> {
-    let mut lockfile = Lockfile::init()?;
+// Keeps what the lockfile recorded about a project across reinstalls and updates
+fn carry_over(info: &mut Info, old: Option<&Info>, reason: Reason, options: &ResolveOptions) {
+    info.constraint.clone_from(&options.constraint);
+    info.loader.clone_from(&options.loader);
+    info.channel = match reason {
+        Reason::DependencyOf(_) => old.and_then(|p| p.channel.clone()),
+        _ => options.channel.clone(),
+    };
+    info.pinned = old.is_some_and(|p| p.pinned);
+    info.disabled = old.is_some_and(|p| p.disabled);
+    info.groups = old.map(|p| p.groups.clone()).unwrap_or_default();
+    info.required_by = old.map(|p| p.required_by.clone()).unwrap_or_default();
 
-    if !lockfile.is_initialized() {
-        return Err(anyhow!(
-            "Server must be initialized before updating projects"
-        ));
-    }
+    // Explicitly adding a project that came in as a dependency makes it explicit
+    info.dependency = match reason {
+        Reason::Requested => {
+            for group in &options.groups {
+                if !info.groups.contains(group) {
+                    info.groups.push(group.clone());
+                }
+            }
 
-    lockfile.remove(id, keep_jarfile)
+            false
+        }
+        Reason::Update => old.is_some_and(|p| p.dependency),
+        Reason::DependencyOf(parent) => {
+            if !info.required_by.iter().any(|p| p == parent) {
+                info.required_by.push(parent.to_string());
+            }
+
+            old.is_none_or(|p| p.dependency)
+        }
+    };
+}
+
+// Installed projects that the new one declares incompatible, or that declare it incompatible
+fn find_conflicts(lockfile: &Lockfile, info: &Info) -> Vec<String> {
+    let mut conflicts = vec![];
+
+    for dep in info
+        .dependencies
+        .iter()
+        .flatten()
+        .filter(|d| d.incompatible)
+    {
+        if let Some(p) = lockfile
+            .mods
+            .iter()
+            .find(|p| p.name == dep.name || (!dep.id.is_empty() && p.id == dep.id))
+        {
+            conflicts.push(format!("{} is incompatible with {}", info.name, p.name));
+        }
+    }
+
+    for p in &lockfile.mods {
+        let declared = p
+            .dependencies
+            .iter()
+            .flatten()
+            .any(|d| d.incompatible && d.name == info.name);
+
+        if declared && p.name != info.name {
+            conflicts.push(format!("{} is incompatible with {}", p.name, info.name));
+        }
+    }
+
+    conflicts
+}
+
+// Providers without published hashes get one computed from the downloaded file,
+// so later installs and verification are still checked
+pub fn record_checksum(lockfile: &Lockfile, mut info: Info) -> Result<Info> {
+    if info.checksum.is_none() {
+        let path = info.get_file_path(&lockfile.loader);
+
+        info.checksum = Some(Checksum {
+            method: String::from("sha512"),
+            hash: mup::hash_file::<Sha512>(&path)?,
+        });
+        info.size = Some(std::fs::metadata(&path)?.len());
+    }
+
+    Ok(info)
+}
+
+fn fetch(
+    lockfile: &Lockfile,
+    provider: &str,
+    project_id: &str,
+    version: &str,
+    options: &ResolveOptions,
+) -> Result<Info> {
+    match provider {
+        "modrinth" => modrinth::fetch(lockfile, project_id, version, options),
+        "hangar" => hangar::fetch(lockfile, project_id, version, options),
+        "jenkins" => jenkins::fetch(lockfile, project_id, version, options),
+        "url" => url::fetch(project_id, version),
+        "local" => local::fetch(project_id, version),
+        "geysermc" => geysermc::fetch(lockfile, project_id, version),
+        _ => unimplemented!(),
+    }
+}
+
+// Paper-family plugins are accepted across nearby Minecraft versions unless strict matching is on
+pub fn is_relaxed(loader: &Loader, options: &ResolveOptions) -> bool {
+    loader.is_paper_family() && !options.strict && !mup::config::get().compatibility.strict
+}
+
+// Whether any of the supported version specs covers the server's Minecraft version
+pub fn supports_minecraft<S: AsRef<str>>(
+    loader: &Loader,
+    specs: &[S],
+    options: &ResolveOptions,
+) -> bool {
+    // Velocity plugins list Velocity releases, and the proxy accepts every Minecraft version
+    if loader.name == "velocity" {
+        return true;
+    }
+
+    let Some(target) = MinecraftVersion::parse(&loader.minecraft_version) else {
+        return specs.iter().any(|s| s.as_ref() == loader.minecraft_version);
+    };
+
+    let window = mup::config::get().compatibility.paper_window;
+    let relaxed = is_relaxed(loader, options);
+
+    specs.iter().any(|spec| {
+        if relaxed {
+            version::spec_within_window(spec.as_ref(), &target, window)
+        } else {
+            version::spec_matches(spec.as_ref(), &target)
+        }
+    })
+}
+
+// Loaders a project may target, a --loader override replaces the server's own
+fn accepted_loaders<'a>(loader: &'a Loader, options: &'a ResolveOptions) -> Vec<&'a str> {
+    options
+        .loader
+        .as_deref()
+        .map_or_else(|| loader.plugin_loaders(), |l| vec![l])
+}
+
+// Lower is more stable, custom Hangar channels such as snapshots count as alpha
+const fn channel_rank(channel: &str) -> u8 {
+    if channel.eq_ignore_ascii_case("release") {
+        0
+    } else if channel.eq_ignore_ascii_case("beta") {
+        1
+    } else {
+        2
+    }
+}
+
+// Describes the constraint for errors about finding no suitable version
+fn constraint_note(options: &ResolveOptions) -> String {
+    let constraint = options
+        .constraint
+        .as_ref()
+        .map_or_else(String::new, |c| format!(" matching {c}"));
+
+    match options.channel.as_deref() {
+        None => constraint,
+        Some("release") => format!("{constraint} in the release channel"),
+        Some(channel) => format!("{constraint} in the {channel} channel or more stable"),
+    }
+}
+
+// Picks the newest candidate by version number, falling back to publish date.
+// Stable releases are preferred over pre-releases unless the options say otherwise.
+fn select_latest<T>(
+    candidates: Vec<T>,
+    options: &ResolveOptions,
+    key: impl Fn(&T) -> (&str, &str, &str),
+) -> Option<T> {
+    let allowed = |candidate: &T| {
+        let (number, _, channel) = key(candidate);
+
+        let in_channel = options
+            .channel
+            .as_deref()
+            .is_none_or(|least| channel_rank(channel) <= channel_rank(least));
+
+        in_channel
+            && options.constraint.as_deref().is_none_or(|constraint| {
+                version::satisfies_constraint(number, constraint).unwrap_or(false)
+            })
+    };
+
+    candidates.into_iter().filter(allowed).max_by(|a, b| {
+        let (a_version, a_date, a_channel) = key(a);
+        let (b_version, b_date, b_channel) = key(b);
+
+        let stability = if options.include_prerelease {
+            Ordering::Equal
+        } else {
+            channel_rank(b_channel).cmp(&channel_rank(a_channel))
+        };
+
+        stability
+            .then_with(|| mup::version::compare_versions(a_version, b_version))
+            .then_with(|| a_date.cmp(b_date))
+    })
+}
+
+pub fn confirm_downloads<I: Borrow<Info>>(infos: &[I], yes: bool) -> Result<bool> {
+    confirm_sizes(infos, yes, true)
+}
+
+// Only counts the sizes recorded in the lockfile instead of asking the providers for the rest
+pub fn confirm_recorded_downloads<I: Borrow<Info>>(infos: &[I], yes: bool) -> Result<bool> {
+    confirm_sizes(infos, yes, false)
+}
+
+fn confirm_sizes<I: Borrow<Info>>(infos: &[I], yes: bool, probe: bool) -> Result<bool> {
+    let mut total = 0;
+    let mut unknown = 0;
+
+    for info in infos.iter().map(Borrow::borrow) {
+        let size = match info.size {
+            Some(size) => Some(size),
+            None if probe => mup::content_length(&info.download_url)?,
+            None => None,
+        };
+
+        if let Some(size) = size {
+            total += size;
+        } else {
+            unknown += 1;
+        }
+    }
+
+    print!(
+        "{} files to download, {} total",
+        infos.len(),
+        mup::format_size(total)
+    );
+    if unknown > 0 {
+        print!(" ({unknown} of unknown size)");
+    }
+    println!();
+
+    if yes {
+        return Ok(true);
+    }
+
+    mup::confirm("Proceed?")
+}
+
+pub fn download_plugin(lockfile: &Lockfile, info: &Info) -> Result<()> {
+    info!(
+        "downloading {} for {} version {}",
+        info.name, lockfile.loader.name, info.version
+    );
+
+    if info.source == "local" {
+        return local::install(lockfile, info);
+    }
+
+    let file_path = info.get_file_path(&lockfile.loader);
+
+    let cached = SHARED_CACHE
+        .get()
+        .zip(info.checksum.as_ref())
+        .map(|(dir, checksum)| dir.join(format!("{}.jar", checksum.hash)));
+
+    if let Some(cached) = cached.as_ref().filter(|c| c.exists()) {
+        info!("copying {} from the shared cache", info.name);
+
+        if let Some(prefix) = file_path.parent() {
+            fs::create_dir_all(prefix)?;
+        }
+        fs::copy(cached, &file_path)?;
+
+        return Ok(());
+    }
+
+    info.checksum.as_ref().map_or_else(
+        || mup::download(&info.download_url, &file_path),
+        |checksum| {
+            info!(
+                "downloading jarfile to {} from {}",
+                file_path.to_str().unwrap(),
+                info.download_url
+            );
+
+            match checksum.method.as_str() {
+                "sha256" => mup::download_with_checksum::<Sha256>(
+                    &info.download_url,
+                    &file_path,
+                    &checksum.hash,
+                ),
+                "sha512" => mup::download_with_checksum::<Sha512>(
+                    &info.download_url,
+                    &file_path,
+                    &checksum.hash,
+                ),
+                _ => unimplemented!(),
+            }
+        },
+    )?;
+
+    if let Some(cached) = cached {
+        if let Some(prefix) = cached.parent() {
+            fs::create_dir_all(prefix)?;
+        }
+        fs::copy(&file_path, cached)?;
+    }
+
+    Ok(())
+}
+
+fn set_pinned(id: &str, pinned: bool) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
+
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "you must initialize a server before modifying projects"
+        ));
+    }
+
+    let entry = lockfile.get_mut(id)?;
+
+    if entry.pinned == pinned {
+        let state = if pinned { "already" } else { "not" };
+        println!("{} is {state} pinned", entry.name);
+        return Ok(());
+    }
+
+    entry.pinned = pinned;
+
+    if pinned {
+        println!("pinned {} at version {}", entry.name, entry.version);
+    } else {
+        println!("unpinned {}", entry.name);
+    }
+
+    lockfile.save()
+}
+
+// Moves the jar out of the way so the server doesn't load it, without touching the lockfile entry
+fn set_enabled(id: &str, enabled: bool) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
+
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "you must initialize a server before modifying projects"
+        ));
+    }
+
+    let loader = &lockfile.loader;
+    let entry = lockfile
+        .mods
+        .iter_mut()
+        .find(|p| p.name == id || p.id == id)
+        .ok_or_else(|| anyhow!("key {id} not found"))?;
+
+    if entry.disabled != enabled {
+        let state = if enabled { "enabled" } else { "disabled" };
+        println!("{} is already {state}", entry.name);
+        return Ok(());
+    }
+
+    let from = entry.get_file_path(loader);
+    entry.disabled = !enabled;
+    let to = entry.get_file_path(loader);
+
+    if from.exists() {
+        if let Some(prefix) = to.parent() {
+            std::fs::create_dir_all(prefix)?;
+        }
+
+        info!("moving {} to {}", from.display(), to.display());
+        std::fs::rename(&from, &to)?;
+    } else {
+        warn!(
+            "{} is missing, it will be installed to {}",
+            from.display(),
+            to.display()
+        );
+    }
+
+    if enabled {
+        println!("enabled {}", entry.name);
+    } else {
+        println!("disabled {}", entry.name);
+    }
+
+    lockfile.save()
+}
+
+fn set_provides(project: &str, dependencies: &[String], clear: bool) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
+
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "you must initialize a server before modifying projects"
+        ));
+    }
+
+    // Keyed by name so the declaration survives reinstalls that change the project ID
+    let project = lockfile
+        .get(project)
+        .map_or_else(|_| project.to_string(), |p| p.name.clone());
+
+    if clear {
+        lockfile
+            .provides
+            .remove(&project)
+            .ok_or_else(|| anyhow!("{project} is not declared to provide anything"))?;
+    } else {
+        let provided = lockfile.provides.entry(project).or_default();
+
+        for dependency in dependencies {
+            if !provided.contains(dependency) {
+                provided.push(dependency.clone());
+            }
+        }
+    }
+
+    lockfile.save()
+}
+
+fn set_override(dependency: &str, with: Option<&str>, ignore: bool, clear: bool) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
+
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "you must initialize a server before modifying projects"
+        ));
+    }
+
+    if clear {
+        lockfile
+            .overrides
+            .remove(dependency)
+            .ok_or_else(|| anyhow!("no override exists for {dependency}"))?;
+    } else if ignore {
+        lockfile
+            .overrides
+            .insert(dependency.to_string(), Override::Ignore);
+    } else if let Some(with) = with {
+        lockfile
+            .overrides
+            .insert(dependency.to_string(), Override::Replace(with.to_string()));
+    }
+
+    lockfile.save()
 }
 
-pub fn update(id: &str, version: &str) -> Result<()> {
+fn remove_all(
+    patterns: &[String],
+    keep_jarfile: bool,
+    remove_orphans: bool,
+    yes: bool,
+) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
+
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "Server must be initialized before removing projects"
+        ));
+    }
+
+    let mut targets = resolve_targets(&lockfile, patterns)?;
+
+    println!("removing {}", targets.join(", "));
+
+    let orphans = find_orphans(&lockfile, &targets);
+    if !orphans.is_empty() {
+        if remove_orphans {
+            println!("removing orphaned dependencies {}", orphans.join(", "));
+            targets.extend(orphans);
+        } else {
+            println!(
+                "no longer required by any installed project: {} (use --remove-orphans to remove them)",
+                orphans.join(", ")
+            );
+        }
+    }
+
+    if targets.len() > 1 && !yes && !mup::confirm("Proceed?")? {
+        return Ok(());
+    }
+
+    hooks::around(Event::Remove, &targets, || {
+        lockfile.remove_all(&targets, keep_jarfile)
+    })
+}
+
+fn resolve_targets(lockfile: &Lockfile, patterns: &[String]) -> Result<Vec<String>> {
+    let mut targets: Vec<String> = vec![];
+
+    for pattern in patterns {
+        let matches: Vec<String> = if mup::is_glob(pattern) {
+            lockfile
+                .mods
+                .iter()
+                .filter(|p| mup::glob_match(pattern, &p.name))
+                .map(|p| p.name.clone())
+                .collect()
+        } else {
+            vec![lockfile.get(pattern)?.name.clone()]
+        };
+
+        if matches.is_empty() {
+            return Err(anyhow!("no installed projects match {pattern}"));
+        }
+
+        for name in matches {
+            if !targets.contains(&name) {
+                targets.push(name);
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+// Dependencies that nothing left in the lockfile requires once the targets are gone, found by
+// walking down from every explicitly requested project so that dependency cycles are caught too
+fn find_orphans(lockfile: &Lockfile, targets: &[String]) -> Vec<String> {
+    let remaining: Vec<&Info> = lockfile
+        .mods
+        .iter()
+        .filter(|p| !targets.contains(&p.name))
+        .collect();
+
+    let mut needed: Vec<&Info> = remaining
+        .iter()
+        .copied()
+        .filter(|p| !p.dependency)
+        .collect();
+    let mut idx = 0;
+
+    while let Some(project) = needed.get(idx).copied() {
+        for p in &remaining {
+            if requires(lockfile, project, p) && !needed.iter().any(|n| n.name == p.name) {
+                needed.push(p);
+            }
+        }
+
+        idx += 1;
+    }
+
+    remaining
+        .into_iter()
+        .filter(|p| !needed.iter().any(|n| n.name == p.name))
+        .map(|p| p.name.clone())
+        .collect()
+}
+
+// Whether `project` has a required dependency on `dependency`, directly or through an override
+fn requires(lockfile: &Lockfile, project: &Info, dependency: &Info) -> bool {
+    project
+        .dependencies
+        .iter()
+        .flatten()
+        .filter(|d| d.required)
+        .any(|d| match lockfile.overrides.get(&d.name) {
+            Some(Override::Replace(with)) => *with == dependency.name || *with == dependency.id,
+            Some(Override::Ignore) => false,
+            None => {
+                d.name == dependency.name
+                    || lockfile
+                        .provides
+                        .get(&dependency.name)
+                        .is_some_and(|provided| provided.contains(&d.name))
+            }
+        })
+}
+
+fn autoremove(keep_jarfile: bool, yes: bool) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
+
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "Server must be initialized before removing projects"
+        ));
+    }
+
+    let orphans = find_orphans(&lockfile, &[]);
+    if orphans.is_empty() {
+        println!("no orphaned dependencies");
+        return Ok(());
+    }
+
+    println!("removing {}", orphans.join(", "));
+
+    if !yes && !mup::confirm("Proceed?")? {
+        return Ok(());
+    }
+
+    hooks::around(Event::Remove, &orphans, || {
+        lockfile.remove_all(&orphans, keep_jarfile)
+    })
+}
+
+pub fn update(
+    id: &str,
+    version: &str,
+    yes: bool,
+    changelog_only: bool,
+    group: Option<&str>,
+    options: &ResolveOptions,
+) -> Result<()> {
     let lockfile = Lockfile::init()?;
 
     if !lockfile.is_initialized() {
@@ -251,13 +1580,422 @@ pub fn update(id: &str, version: &str) -> Result<()> {
         ));
     }
 
+    if let Some(group) = group {
+        if !lockfile
+            .mods
+            .iter()
+            .any(|p| p.groups.iter().any(|g| g == group))
+        {
+            return Err(anyhow!("no installed projects are in group {group}"));
+        }
+    }
+
     if id == "all" {
-        for plugin in lockfile.mods {
-            update(&plugin.name, version)?;
+        let updates = find_updates(&lockfile, version, group, options)?;
+
+        if updates.is_empty() {
+            println!("all projects are up to date");
+            return Ok(());
+        }
+
+        for (plugin, info) in &updates {
+            changelog::print(&lockfile, plugin, info);
+        }
+
+        if changelog_only {
+            return Ok(());
+        }
+
+        let (labels, mut pending): (Vec<_>, Vec<_>) = updates
+            .into_iter()
+            .map(|(plugin, info)| {
+                let label = format!("{}: {} -> {}", plugin.name, plugin.version, info.version);
+
+                (label, info)
+            })
+            .unzip();
+
+        // Let the user deselect risky updates instead of taking all or nothing
+        let interactive = pending.len() > 1 && !yes && io::stdin().is_terminal();
+        if interactive {
+            pending = select_updates(pending, &labels)?;
+
+            if pending.is_empty() {
+                println!("no updates selected");
+                return Ok(());
+            }
+        }
+
+        if !confirm_downloads(&pending, yes || interactive)? {
+            return Ok(());
+        }
+
+        let names: Vec<String> = pending.iter().map(|p| p.name.clone()).collect();
+        hooks::around(Event::Update, &names, || apply_updates(pending, options))?;
+    } else if let Ok(plugin) = lockfile.get(id) {
+        let provider = updatable(plugin)?;
+        let options = ResolveOptions {
+            constraint: plugin.constraint.clone(),
+            loader: plugin.loader.clone(),
+            channel: options.channel.clone().or_else(|| plugin.channel.clone()),
+            force_client: plugin.force_client,
+            ..options.clone()
+        };
+        let info = fetch(&lockfile, provider, &plugin.id, version, &options)?;
+
+        if info.version == plugin.version {
+            println!("{} is up to date", plugin.name);
+            return Ok(());
         }
+
+        changelog::print(&lockfile, plugin, &info);
+
+        if changelog_only {
+            return Ok(());
+        }
+
+        hooks::around(Event::Update, std::slice::from_ref(&plugin.name), || {
+            apply_updates(vec![info], &options)
+        })?;
     } else {
-        add("modrinth", id, version, true)?;
+        add("modrinth", &[id], version, true, Reason::Requested, options)?;
     }
 
     Ok(())
 }
+
+// Every update saves the lockfile, so the entry is read again before replacing it
+pub fn apply_update(id: &str, version: &str, options: &ResolveOptions) -> Result<()> {
+    let lockfile = Lockfile::init()?;
+    let plugin = lockfile.get(id)?;
+
+    add(
+        updatable(plugin)?,
+        &[&plugin.id],
+        version,
+        true,
+        Reason::Update,
+        options,
+    )
+}
+
+// Installs versions that were already resolved, so nothing is fetched from the provider twice
+fn apply_updates(pending: Vec<Info>, options: &ResolveOptions) -> Result<()> {
+    let mut transaction = Transaction::new(Lockfile::init()?);
+
+    let result = pending.into_iter().try_for_each(|info| {
+        let old = transaction.lockfile.get(&info.name)?;
+        let provider = updatable(old)?;
+        let options = recorded_options(options, Some(old), Reason::Update);
+
+        install_resolved(
+            &mut transaction,
+            provider,
+            info,
+            true,
+            Reason::Update,
+            &options,
+        )
+    });
+
+    match result {
+        Ok(()) => transaction.commit(),
+        Err(e) => {
+            transaction.rollback();
+            Err(e)
+        }
+    }
+}
+
+fn updatable(plugin: &Info) -> Result<&'static str> {
+    if plugin.pinned {
+        return Err(anyhow!(
+            "{} is pinned at version {}, unpin it to update",
+            plugin.name,
+            plugin.version
+        ));
+    }
+
+    update_provider(plugin).ok_or_else(|| {
+        anyhow!(
+            "{} is installed from {} and cannot be updated",
+            plugin.name,
+            plugin.download_url
+        )
+    })
+}
+
+// Resolves every lockfile entry that can be updated, pairing it with the newer version
+fn find_updates<'a>(
+    lockfile: &'a Lockfile,
+    version: &str,
+    group: Option<&str>,
+    options: &ResolveOptions,
+) -> Result<Vec<(&'a Info, Info)>> {
+    let mut updates = vec![];
+
+    let in_group = |p: &Info| group.is_none_or(|g| p.groups.iter().any(|pg| pg == g));
+
+    for plugin in lockfile.mods.iter().filter(|p| in_group(p)) {
+        if plugin.pinned {
+            println!(
+                "skipping {}, it is pinned at version {}",
+                plugin.name, plugin.version
+            );
+            continue;
+        }
+
+        let Some(provider) = update_provider(plugin) else {
+            info!(
+                "skipping {}, it is installed from {}",
+                plugin.name, plugin.download_url
+            );
+            continue;
+        };
+
+        let options = ResolveOptions {
+            constraint: plugin.constraint.clone(),
+            loader: plugin.loader.clone(),
+            channel: options.channel.clone().or_else(|| plugin.channel.clone()),
+            force_client: plugin.force_client,
+            ..options.clone()
+        };
+        let info = fetch(lockfile, provider, &plugin.id, version, &options)?;
+
+        if info.version != plugin.version {
+            updates.push((plugin, info));
+        }
+    }
+
+    Ok(updates)
+}
+
+// Exits with an error when anything is out of date, so it can be used in scripts
+fn outdated(options: &ResolveOptions) -> Result<()> {
+    let lockfile = Lockfile::init()?;
+    if !lockfile.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    let updates = find_updates(&lockfile, "latest", None, options)?;
+
+    if updates.is_empty() {
+        println!("all projects are up to date");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<&str>> = updates
+        .iter()
+        .map(|(plugin, info)| {
+            vec![
+                plugin.name.as_str(),
+                plugin.version.as_str(),
+                info.version.as_str(),
+                plugin.source.as_str(),
+            ]
+        })
+        .collect();
+
+    println!(
+        "{}",
+        mup::format_table(&["NAME", "CURRENT", "LATEST", "SOURCE"], &rows)
+    );
+
+    Err(anyhow!("{} projects are outdated", updates.len()))
+}
+
+// Newest version of an installed project for the lockfile's loader and Minecraft version, None
+// when it is pinned or its provider has no notion of versions
+pub fn latest_for(lockfile: &Lockfile, plugin: &Info) -> Result<Option<Info>> {
+    if plugin.pinned {
+        return Ok(None);
+    }
+
+    let Some(provider) = update_provider(plugin) else {
+        return Ok(None);
+    };
+
+    let options = ResolveOptions {
+        constraint: plugin.constraint.clone(),
+        loader: plugin.loader.clone(),
+        channel: plugin.channel.clone(),
+        force_client: plugin.force_client,
+        ..Default::default()
+    };
+
+    fetch(lockfile, provider, &plugin.id, "latest", &options).map(Some)
+}
+
+// Projects are resolved again through the provider they were installed from, direct downloads and
+// local jars have nothing to update to
+fn update_provider(plugin: &Info) -> Option<&'static str> {
+    match plugin.source.as_str() {
+        "modrinth" => Some("modrinth"),
+        "hangar" => Some("hangar"),
+        "jenkins" => Some("jenkins"),
+        "geysermc" => Some("geysermc"),
+        _ => None,
+    }
+}
+
+fn select_updates(pending: Vec<Info>, labels: &[String]) -> Result<Vec<Info>> {
+    let defaults = vec![true; labels.len()];
+
+    let selected = MultiSelect::new()
+        .with_prompt("Select the updates to apply")
+        .items(labels)
+        .defaults(&defaults)
+        .interact()?;
+
+    let chosen = pending
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| selected.contains(i))
+        .map(|(_, info)| info)
+        .collect();
+
+    Ok(chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, path::Path};
+
+    use super::*;
+    use crate::{
+        server::lockfile,
+        testing::{self, lockfile_with, project},
+    };
+
+    #[test]
+    fn test_find_orphans() {
+        let lockfile = lockfile_with(vec![
+            project("towny", &["townychat", "vault"], &[]),
+            project("townychat", &["chatlib"], &["towny"]),
+            project("chatlib", &[], &["townychat"]),
+            project("vault", &[], &["towny", "essentialsx"]),
+            project("essentialsx", &["vault"], &[]),
+        ]);
+
+        // vault is still required by essentialsx, everything towny pulled in only for itself goes
+        assert_eq!(
+            find_orphans(&lockfile, &[String::from("towny")]),
+            ["townychat", "chatlib"]
+        );
+        assert!(find_orphans(&lockfile, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_find_conflicts() -> Result<()> {
+        let mut essentials = project("essentialsx", &[], &[]);
+        essentials.dependencies = Some(vec![serde_json::from_value(serde_json::json!({
+            "name": "cmi",
+            "required": false,
+            "incompatible": true,
+        }))?]);
+
+        let lockfile = lockfile_with(vec![essentials, project("vault", &[], &[])]);
+
+        assert_eq!(
+            find_conflicts(&lockfile, &project("cmi", &[], &[])),
+            ["essentialsx is incompatible with cmi"]
+        );
+        assert!(find_conflicts(&lockfile, &project("luckperms", &[], &[])).is_empty());
+
+        // Declared by the project being installed instead of the one already there
+        let mut cmi = project("cmi", &[], &[]);
+        cmi.dependencies = Some(vec![serde_json::from_value(serde_json::json!({
+            "name": "vault",
+            "required": false,
+            "incompatible": true,
+        }))?]);
+        assert_eq!(
+            find_conflicts(&lockfile_with(vec![project("vault", &[], &[])]), &cmi),
+            ["cmi is incompatible with vault"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_rolls_back_on_failure() -> Result<()> {
+        testing::in_temp_dir(|| {
+            fs::create_dir_all("jars")?;
+            fs::create_dir_all("plugins")?;
+            fs::write("jars/a.jar", "new a")?;
+            fs::write("jars/b.jar", "b")?;
+            fs::write("plugins/a.jar", "old a")?;
+
+            let mut installed = local::fetch("jars/a.jar", "1.0")?;
+            installed.checksum = None;
+
+            File::create(lockfile::LOCKFILE_PATH)?;
+            Lockfile {
+                loader: Loader::new("paper", "1.21.4", "latest", false),
+                mods: vec![installed],
+                ..Default::default()
+            }
+            .save()?;
+            let before = fs::read_to_string(lockfile::LOCKFILE_PATH)?;
+
+            // a is replaced and b is added before the third project fails to resolve
+            let result = add(
+                "local",
+                &["jars/a.jar", "jars/b.jar", "jars/missing.jar"],
+                "latest",
+                false,
+                Reason::Requested,
+                &ResolveOptions::default(),
+            );
+
+            assert!(result.is_err_and(|e| e.to_string().contains("missing.jar")));
+            assert_eq!(fs::read_to_string(lockfile::LOCKFILE_PATH)?, before);
+            assert_eq!(fs::read_to_string("plugins/a.jar")?, "old a");
+            assert!(!Path::new("plugins/b.jar").exists());
+            assert!(!Path::new(".mup/staging").exists());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_select_latest() {
+        let latest = |candidates: Vec<(&'static str, &str, &str)>, options: &ResolveOptions| {
+            select_latest(candidates, options, |&(v, d, c)| (v, d, c)).map(|c| c.0)
+        };
+        let candidates = || {
+            vec![
+                ("1.0.0", "2024-01-01", "release"),
+                ("1.1.0", "2024-02-01", "beta"),
+                ("1.2.0", "2024-03-01", "alpha"),
+            ]
+        };
+
+        assert_eq!(
+            latest(candidates(), &ResolveOptions::default()),
+            Some("1.0.0")
+        );
+
+        let prerelease = ResolveOptions {
+            include_prerelease: true,
+            ..Default::default()
+        };
+        assert_eq!(latest(candidates(), &prerelease), Some("1.2.0"));
+
+        let beta = ResolveOptions {
+            channel: Some(String::from("beta")),
+            ..prerelease
+        };
+        assert_eq!(latest(candidates(), &beta), Some("1.1.0"));
+
+        // Pre-releases are still picked when a project has nothing else
+        let only_betas = vec![
+            ("0.1.0", "2024-01-01", "beta"),
+            ("0.2.0", "2024-02-01", "beta"),
+        ];
+        assert_eq!(
+            latest(only_betas, &ResolveOptions::default()),
+            Some("0.2.0")
+        );
+    }
+}
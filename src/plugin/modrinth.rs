@@ -1,5 +1,7 @@
 #![allow(clippy::case_sensitive_file_extension_comparisons)]
 
+use std::collections::HashMap;
+
 use anyhow::{anyhow, Result};
 use log::{info, warn};
 use serde::Deserialize;
@@ -7,12 +9,21 @@ use serde::Deserialize;
 use crate::server::lockfile::Lockfile;
 
 const BASE_URL: &str = "https://api.modrinth.com/v2";
+// Collections are only part of the v3 API
+const V3_URL: &str = "https://api.modrinth.com/v3";
 
 #[derive(Clone, Deserialize)]
 pub struct Version {
     pub id: String,
     pub project_id: String,
+    #[serde(rename = "version_number")]
+    pub number: String,
+    #[serde(rename = "version_type")]
+    pub channel: String,
+    pub date_published: String,
     pub dependencies: Vec<ModrinthDependency>,
+    #[serde(default)]
+    changelog: Option<String>,
     game_versions: Vec<String>,
     loaders: Vec<String>,
     files: Vec<ProjectFile>,
@@ -30,71 +41,123 @@ pub struct ModrinthDependency {
 pub struct ProjectFile {
     pub hashes: Hashes,
     pub url: String,
+    pub size: u64,
     filename: String,
 }
 
 #[derive(Clone, Deserialize)]
 pub struct Hashes {
     pub sha512: String,
+    #[serde(default)]
+    pub sha1: String,
+}
+
+#[derive(Deserialize)]
+struct SearchResults {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    slug: String,
+    description: String,
+    downloads: u64,
+}
+
+#[derive(Deserialize)]
+pub struct Collection {
+    pub name: String,
+    pub projects: Vec<String>,
 }
 
 #[derive(Deserialize)]
 pub struct ProjectInfo {
     pub slug: String,
     server_side: String,
+    client_side: String,
     id: String,
     loaders: Vec<String>,
     game_versions: Vec<String>,
     versions: Vec<String>,
+    description: String,
+    downloads: u64,
+    license: Option<License>,
+}
+
+#[derive(Deserialize)]
+struct License {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    url: Option<String>,
 }
 
-pub fn fetch(lockfile: &Lockfile, id: &str, version: &str) -> Result<super::Info> {
+pub fn fetch(
+    lockfile: &Lockfile,
+    id: &str,
+    version: &str,
+    options: &super::ResolveOptions,
+) -> Result<super::Info> {
     info!("Fetching project info for {id}");
 
     let formatted_url = format!("{BASE_URL}/project/{id}");
-    let mut resp = mup::get(&formatted_url).call()?;
+    let resp = mup::get(&formatted_url)?;
 
-    if resp.status() == 404 {
+    if resp.status == 404 {
         return Err(anyhow!("project {id} does not exist"));
     }
 
-    let project_info: ProjectInfo = resp.body_mut().read_json()?;
+    let project_info: ProjectInfo = resp.json()?;
 
-    if project_info.server_side == "unsupported" {
-        return Err(anyhow!("project {id} does not support server-side"));
-    }
+    let force_client = check_sides(lockfile, id, &project_info, options)?;
 
     if project_info.server_side == "unknown" {
         warn!("project {id} may not support server-side");
     }
 
-    if !project_info.loaders.contains(&lockfile.loader.name) {
-        return Err(anyhow!(
-            "project {id} does not support {}",
-            lockfile.loader.name
-        ));
-    }
+    let mut compat = super::Compatibility::new(options);
 
-    if !project_info
-        .game_versions
-        .contains(&lockfile.loader.minecraft_version)
-    {
-        return Err(anyhow!(
-            "project does not support Minecraft version {}",
-            lockfile.loader.minecraft_version
-        ));
-    }
+    let loader = options.loader.as_deref().unwrap_or(&lockfile.loader.name);
+
+    compat.check(supports_loader(lockfile, options, &project_info.loaders), || {
+        if loader == "folia" && options.loader.is_none() {
+            format!("project {id} does not declare Folia support, most Paper plugins do not run on Folia")
+        } else {
+            format!("project {id} does not support {loader}")
+        }
+    })?;
+
+    compat.check(
+        super::supports_minecraft(&lockfile.loader, &project_info.game_versions, options),
+        || {
+            format!(
+                "project does not support Minecraft version {}",
+                lockfile.loader.minecraft_version
+            )
+        },
+    )?;
 
     if version != "latest" && !project_info.versions.contains(&version.to_string()) {
         return Err(anyhow!("project version {version} does not exist"));
     }
 
     let mut version_info = if version == "latest" {
-        get_latest_version(lockfile, &project_info.slug)?
+        get_latest_version(lockfile, &project_info.slug, options, &mut compat)?
     } else {
-        get_specific_version(lockfile, &project_info.slug, version)?
+        get_specific_version(lockfile, &project_info.slug, version, options, &mut compat)?
     };
 
+    // Hybrid servers load Forge mods and Bukkit plugins from different directories
+    let location = lockfile.loader.is_hybrid().then(|| {
+        let is_mod = version_info
+            .loaders
+            .iter()
+            .any(|l| l == "forge" || l == "neoforge");
+
+        String::from(if is_mod { "mods" } else { "plugins" })
+    });
+
     let project_file = version_info
         .files
         .iter()
@@ -131,38 +194,362 @@ pub fn fetch(lockfile: &Lockfile, id: &str, version: &str) -> Result<super::Info
             method: String::from("sha512"),
             hash: project_file.hashes.sha512.clone(),
         }),
+        size: Some(project_file.size),
         dependencies,
+        forced: compat.into_bypassed(),
+        location,
+        force_client,
+        ..Default::default()
     };
 
     Ok(info)
 }
 
+pub fn search(
+    lockfile: Option<&Lockfile>,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<super::SearchResult>> {
+    info!("searching modrinth for {query}");
+
+    let mut facets = vec![vec![
+        String::from("server_side:required"),
+        String::from("server_side:optional"),
+    ]];
+
+    if let Some(lockfile) = lockfile {
+        let loader = &lockfile.loader;
+
+        facets.push(
+            loader
+                .plugin_loaders()
+                .iter()
+                .map(|l| format!("categories:{l}"))
+                .collect(),
+        );
+
+        if !super::is_relaxed(loader, &super::ResolveOptions::default()) {
+            facets.push(vec![format!("versions:{}", loader.minecraft_version)]);
+        }
+    }
+
+    let formatted_url = mup::with_query(
+        &format!("{BASE_URL}/search"),
+        &[
+            ("query", query),
+            ("limit", &limit.to_string()),
+            ("facets", &serde_json::to_string(&facets)?),
+        ],
+    );
+    let results: SearchResults = mup::get_json(&formatted_url)?;
+
+    Ok(results
+        .hits
+        .into_iter()
+        .map(|hit| super::SearchResult {
+            slug: hit.slug,
+            downloads: hit.downloads,
+            description: hit.description,
+        })
+        .collect())
+}
+
+pub fn details(lockfile: Option<&Lockfile>, id: &str) -> Result<super::ProjectDetails> {
+    info!("fetching project info for {id}");
+
+    let resp = mup::get(&format!("{BASE_URL}/project/{id}"))?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("project {id} does not exist"));
+    }
+
+    let project_info: ProjectInfo = resp.json()?;
+
+    // Resolved the same way `plugin add` would, so this is what would get installed
+    let latest = lockfile.and_then(|lockfile| {
+        let options = super::ResolveOptions::default();
+        let mut compat = super::Compatibility::new(&options);
+
+        get_latest_version(lockfile, &project_info.slug, &options, &mut compat)
+            .ok()
+            .map(|v| format!("{} ({})", v.number, v.id))
+    });
+
+    Ok(super::ProjectDetails {
+        name: project_info.slug,
+        description: project_info.description,
+        license: project_info.license.map(|l| l.id),
+        downloads: project_info.downloads,
+        loaders: project_info.loaders,
+        game_versions: project_info.game_versions,
+        latest,
+    })
+}
+
+pub fn license(id: &str) -> Result<super::licenses::License> {
+    info!("fetching license of {id}");
+
+    let resp = mup::get(&format!("{BASE_URL}/project/{id}"))?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("project {id} does not exist"));
+    }
+
+    let project_info: ProjectInfo = resp.json()?;
+
+    // Modrinth license IDs are SPDX identifiers, or LicenseRef-* for custom ones
+    Ok(project_info
+        .license
+        .map(|l| super::licenses::License {
+            name: Some(if l.name.is_empty() {
+                l.id.clone()
+            } else {
+                l.name
+            }),
+            spdx: Some(l.id),
+            url: l.url,
+        })
+        .unwrap_or_default())
+}
+
+// Changelogs of every version for this loader published after `from`, up to and including `to`
+pub fn changelogs(
+    lockfile: &Lockfile,
+    id: &str,
+    from: &str,
+    to: &str,
+) -> Result<Vec<super::changelog::Changelog>> {
+    info!("fetching changelogs of {id}");
+
+    let loaders = lockfile
+        .loader
+        .plugin_loaders()
+        .iter()
+        .map(|l| format!("\"{l}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    let formatted_url = mup::with_query(
+        &format!("{BASE_URL}/project/{id}/version"),
+        &[("loaders", &format!("[{loaders}]"))],
+    );
+    let versions: Vec<Version> = mup::get_json(&formatted_url)?;
+
+    let published = |version: &str| -> Result<String> {
+        if let Some(v) = versions.iter().find(|v| v.id == version) {
+            return Ok(v.date_published.clone());
+        }
+
+        let v: Version = mup::get_json(&format!("{BASE_URL}/version/{version}"))?;
+
+        Ok(v.date_published)
+    };
+
+    let (start, end) = (published(from)?, published(to)?);
+
+    let mut between: Vec<Version> = versions
+        .into_iter()
+        .filter(|v| v.date_published > start && v.date_published <= end)
+        .collect();
+    between.sort_by(|a, b| a.date_published.cmp(&b.date_published));
+
+    Ok(between
+        .into_iter()
+        .map(|v| super::changelog::Changelog {
+            version: v.number,
+            text: v.changelog.unwrap_or_default(),
+        })
+        .collect())
+}
+
+// Returns whether a client-side mod is installed anyway because of --force-client
+fn check_sides(
+    lockfile: &Lockfile,
+    id: &str,
+    project_info: &ProjectInfo,
+    options: &super::ResolveOptions,
+) -> Result<bool> {
+    // Mod loaders happily load client-only mods, which then crash the server or do nothing
+    let client_only = project_info.client_side == "required"
+        && matches!(
+            project_info.server_side.as_str(),
+            "optional" | "unsupported"
+        );
+    let modded = !lockfile.loader.is_hybrid() && lockfile.loader.mod_location() == "mods";
+
+    if client_only && modded {
+        if !options.force_client {
+            return Err(anyhow!(
+                "project {id} is a client-side mod (use --force-client to install it anyway)"
+            ));
+        }
+
+        warn!("project {id} is a client-side mod, continuing anyway");
+    } else if project_info.server_side == "unsupported" {
+        return Err(anyhow!("project {id} does not support server-side"));
+    }
+
+    Ok(client_only && modded)
+}
+
+fn supports_loader(
+    lockfile: &Lockfile,
+    options: &super::ResolveOptions,
+    loaders: &[String],
+) -> bool {
+    let accepted = super::accepted_loaders(&lockfile.loader, options);
+
+    loaders.iter().any(|l| accepted.contains(&l.as_str()))
+}
+
+// Resource packs are published for the "minecraft" loader, returns the zip of the newest version
+// supporting the given Minecraft version
+pub fn resource_pack(id: &str, minecraft_version: &str) -> Result<ProjectFile> {
+    info!("fetching latest resource pack version of {id}");
+
+    let formatted_url = mup::with_query(
+        &format!("{BASE_URL}/project/{id}/version"),
+        &[
+            ("game_versions", &format!("[\"{minecraft_version}\"]")),
+            ("loaders", "[\"minecraft\"]"),
+        ],
+    );
+    let resp = mup::get(&formatted_url)?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("project {id} does not exist"));
+    }
+
+    let versions: Vec<Version> = resp.json()?;
+
+    let version = super::select_latest(versions, &super::ResolveOptions::default(), |v| {
+        (
+            v.number.as_str(),
+            v.date_published.as_str(),
+            v.channel.as_str(),
+        )
+    })
+    .ok_or_else(|| anyhow!("{id} has no resource pack for Minecraft {minecraft_version}"))?;
+
+    version
+        .files
+        .into_iter()
+        .find(|f| f.filename.ends_with(".zip"))
+        .ok_or_else(|| anyhow!("{id} version {} has no resource pack", version.number))
+}
+
+pub fn collection(id: &str) -> Result<Collection> {
+    info!("fetching collection {id}");
+
+    let resp = mup::get(&format!("{V3_URL}/collection/{id}"))?;
+
+    if resp.status == 404 {
+        return Err(anyhow!("collection {id} does not exist"));
+    }
+
+    resp.json()
+}
+
+// Looks up the slugs of many projects in a single request
+pub fn project_slugs(ids: &[&str]) -> Result<HashMap<String, String>> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    info!("fetching names of {} projects", ids.len());
+
+    let formatted_url = mup::with_query(
+        &format!("{BASE_URL}/projects"),
+        &[("ids", &serde_json::to_string(ids)?)],
+    );
+    let projects: Vec<ProjectInfo> = mup::get_json(&formatted_url)?;
+
+    Ok(projects.into_iter().map(|p| (p.id, p.slug)).collect())
+}
+
+// Looks up the version a jar belongs to by its SHA-512, for jars that were installed by hand.
+// `file_name` is kept when it differs from the name in the download url.
+pub fn from_hash(hash: &str, file_name: &str) -> Result<Option<super::Info>> {
+    let formatted_url = mup::with_query(
+        &format!("{BASE_URL}/version_file/{hash}"),
+        &[("algorithm", "sha512")],
+    );
+    let resp = mup::get(&formatted_url)?;
+
+    if resp.status == 404 {
+        return Ok(None);
+    }
+
+    let version_info: Version = resp.json()?;
+
+    let project_file = version_info
+        .files
+        .iter()
+        .find(|f| f.hashes.sha512 == hash)
+        .ok_or_else(|| anyhow!("version {} has no file with hash {hash}", version_info.id))?;
+
+    let mut dependencies = version_info.dependencies.clone();
+    for dep in &mut dependencies {
+        dep.slug = get_project_name(&dep.project_id)?;
+    }
+
+    Ok(Some(super::Info {
+        name: get_project_name(&version_info.project_id)?,
+        id: version_info.project_id.clone(),
+        version: version_info.id.clone(),
+        source: String::from("modrinth"),
+        download_url: project_file.url.clone(),
+        checksum: Some(super::Checksum {
+            method: String::from("sha512"),
+            hash: hash.to_string(),
+        }),
+        size: Some(project_file.size),
+        dependencies: (!dependencies.is_empty()).then(|| {
+            dependencies
+                .into_iter()
+                .map(super::Dependency::from)
+                .collect()
+        }),
+        file_name: project_file
+            .url
+            .rsplit_once('/')
+            .is_none_or(|(_, name)| name != file_name)
+            .then(|| file_name.to_string()),
+        ..Default::default()
+    }))
+}
+
 fn get_project_name(project_id: &str) -> Result<String> {
     info!("fetching project name for project id {project_id}");
 
     let formatted_url = format!("{BASE_URL}/project/{project_id}");
-    let mut resp = mup::get(&formatted_url).call()?;
+    let resp = mup::get(&formatted_url)?;
 
-    if resp.status() == 404 {
+    if resp.status == 404 {
         return Err(anyhow!("project {project_id} does not exist"));
     }
 
-    let resp: ProjectInfo = resp.body_mut().read_json()?;
+    let resp: ProjectInfo = resp.json()?;
 
     Ok(resp.slug)
 }
 
-fn get_specific_version(lockfile: &Lockfile, slug: &str, version: &str) -> Result<Version> {
+fn get_specific_version(
+    lockfile: &Lockfile,
+    slug: &str,
+    version: &str,
+    options: &super::ResolveOptions,
+    compat: &mut super::Compatibility,
+) -> Result<Version> {
     info!("fetching version {version} of {slug}");
 
     let formatted_url = format!("{BASE_URL}/version/{version}");
-    let mut resp = mup::get(&formatted_url).call()?;
+    let resp = mup::get(&formatted_url)?;
 
-    if resp.status() == 404 {
+    if resp.status == 404 {
         return Err(anyhow!("version {version} does not exist"));
     }
 
-    let resp: Version = resp.body_mut().read_json()?;
+    let resp: Version = resp.json()?;
 
     if slug != get_project_name(&resp.project_id)? {
         return Err(anyhow!(
@@ -170,50 +557,121 @@ fn get_specific_version(lockfile: &Lockfile, slug: &str, version: &str) -> Resul
         ));
     }
 
-    if !resp
-        .game_versions
-        .contains(&lockfile.loader.minecraft_version)
-    {
-        return Err(anyhow!(
-            "version {version} does not support Minecraft {}",
-            lockfile.loader.minecraft_version
-        ));
-    }
-
-    if !resp.loaders.contains(&lockfile.loader.name) {
-        return Err(anyhow!(
-            "version {version} does not support {}",
-            lockfile.loader.name
-        ));
-    }
+    compat.check(
+        super::supports_minecraft(&lockfile.loader, &resp.game_versions, options),
+        || {
+            format!(
+                "version {version} does not support Minecraft {}",
+                lockfile.loader.minecraft_version
+            )
+        },
+    )?;
+
+    let loader = options.loader.as_deref().unwrap_or(&lockfile.loader.name);
+    compat.check(supports_loader(lockfile, options, &resp.loaders), || {
+        format!("version {version} does not support {loader}")
+    })?;
 
     Ok(resp)
 }
 
-fn get_latest_version(lockfile: &Lockfile, slug: &str) -> Result<Version> {
+fn get_latest_version(
+    lockfile: &Lockfile,
+    slug: &str,
+    options: &super::ResolveOptions,
+    compat: &mut super::Compatibility,
+) -> Result<Version> {
     info!("fetching latest version of {slug}");
 
-    let loader = &lockfile.loader.name;
+    let loader = options.loader.as_deref().unwrap_or(&lockfile.loader.name);
     let version = &lockfile.loader.minecraft_version;
 
     let formatted_url = format!("{BASE_URL}/project/{slug}/version");
-    let mut resp = mup::get(&formatted_url)
-        .query("game_versions", format!("[\"{version}\"]").as_str())
-        .query("loaders", format!("[\"{loader}\"]").as_str())
-        .call()?;
 
-    if resp.status() == 404 {
+    // A forced resolution has to see every version to be able to fall back to a mismatched one,
+    // and relaxed matching needs versions that target other Minecraft releases
+    let game_versions = format!("[\"{version}\"]");
+    let loaders = super::accepted_loaders(&lockfile.loader, options)
+        .iter()
+        .map(|l| format!("\"{l}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    let loaders = format!("[{loaders}]");
+
+    let formatted_url = if compat.is_forced() {
+        formatted_url
+    } else if super::is_relaxed(&lockfile.loader, options) {
+        mup::with_query(&formatted_url, &[("loaders", &loaders)])
+    } else {
+        mup::with_query(
+            &formatted_url,
+            &[("game_versions", &game_versions), ("loaders", &loaders)],
+        )
+    };
+    let resp = mup::get(&formatted_url)?;
+
+    if resp.status == 404 {
         return Err(anyhow!("{slug} has no valid versions"));
     }
 
-    let versions: Vec<Version> = resp.body_mut().read_json()?;
+    let versions: Vec<Version> = resp.json()?;
 
-    let version = versions
-        .into_iter()
-        .find(|p| p.game_versions.contains(version) && p.loaders.contains(loader))
-        .ok_or_else(|| {
-            anyhow!("{slug} for {loader} has no version that supports Minecraft {version}")
+    let (compatible, incompatible): (Vec<_>, Vec<_>) = versions.into_iter().partition(|p| {
+        super::supports_minecraft(&lockfile.loader, &p.game_versions, options)
+            && supports_loader(lockfile, options, &p.loaders)
+    });
+
+    let candidates = if compatible.is_empty() && !incompatible.is_empty() {
+        compat.check(false, || {
+            format!("{slug} for {loader} has no version that supports Minecraft {version}")
         })?;
 
+        incompatible
+    } else {
+        compatible
+    };
+
+    let version = super::select_latest(candidates, options, |v| {
+        (
+            v.number.as_str(),
+            v.date_published.as_str(),
+            v.channel.as_str(),
+        )
+    })
+    .ok_or_else(|| {
+        let matching = super::constraint_note(options);
+        anyhow!("{slug} for {loader} has no version{matching} that supports Minecraft {version}")
+    })?;
+
     Ok(version)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{loader::Loader, plugin::ResolveOptions};
+
+    #[test]
+    fn test_fetch_latest_release() {
+        let cassette = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cassettes/modrinth-fetch");
+        let lockfile = Lockfile {
+            loader: Loader::new("fabric", "1.21.4", "latest", false),
+            ..Default::default()
+        };
+
+        let info = mup::cassette::replaying(&cassette, || {
+            fetch(&lockfile, "lithium", "latest", &ResolveOptions::default())
+        })
+        .unwrap()
+        .unwrap();
+
+        // The newer beta is skipped for the latest release
+        assert_eq!(info.name, "lithium");
+        assert_eq!(info.version, "Yp6BbNjU");
+        assert_eq!(info.size, Some(733_000));
+        assert_eq!(info.checksum.unwrap().hash, "aa11");
+        assert!(info.dependencies.is_none());
+    }
+}
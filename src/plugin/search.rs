@@ -0,0 +1,44 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{hangar, modrinth};
+use crate::server::lockfile::Lockfile;
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub slug: String,
+    pub downloads: u64,
+    pub description: String,
+}
+
+// Results are narrowed down to the server's loader and Minecraft version when run inside one
+pub fn search(query: &str, provider: &str, limit: usize, json: bool) -> Result<()> {
+    let lockfile = Lockfile::existing()?;
+
+    let results = match provider {
+        "hangar" => hangar::search(lockfile.as_ref(), query, limit)?,
+        _ => modrinth::search(lockfile.as_ref(), query, limit)?,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("no projects found for {query}");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = results
+        .into_iter()
+        .map(|r| vec![r.slug, r.downloads.to_string(), r.description])
+        .collect();
+
+    println!(
+        "{}",
+        mup::format_table(&["SLUG", "DOWNLOADS", "DESCRIPTION"], &rows)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use log::{info, warn};
+
+use super::Info;
+use crate::server::lockfile::Lockfile;
+
+const STAGING_DIR: &str = ".mup/staging";
+
+// Changes to the lockfile and jars made while adding projects. The lockfile is only saved on
+// commit, and jars in the way are moved aside instead of being deleted, so a failure partway
+// through a dependency chain can put the mod directories back as they were
+pub struct Transaction {
+    pub lockfile: Lockfile,
+    written: Vec<PathBuf>,
+    /// Where each jar was and where it was moved to
+    set_aside: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Transaction {
+    pub const fn new(lockfile: Lockfile) -> Self {
+        Self {
+            lockfile,
+            written: vec![],
+            set_aside: vec![],
+        }
+    }
+
+    pub fn set_aside(&mut self, path: &Path) -> Result<()> {
+        if !path.exists() || self.set_aside.iter().any(|(p, _)| p == path) {
+            return Ok(());
+        }
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let staged = Path::new(STAGING_DIR).join(format!("{}-{file_name}", self.set_aside.len()));
+
+        info!("moving {} to {}", path.display(), staged.display());
+
+        fs::create_dir_all(STAGING_DIR)?;
+        fs::rename(path, &staged)?;
+
+        self.set_aside.push((path.to_path_buf(), staged));
+
+        Ok(())
+    }
+
+    pub fn download(&mut self, info: &Info) -> Result<()> {
+        let path = info.get_file_path(&self.lockfile.loader);
+
+        self.set_aside(&path)?;
+        self.written.push(path);
+
+        super::download_plugin(&self.lockfile, info)
+    }
+
+    // Saves the lockfile and drops the jars that were replaced
+    pub fn commit(self) -> Result<()> {
+        if let Err(e) = self.lockfile.save() {
+            self.rollback();
+            return Err(e);
+        }
+
+        for (_, staged) in self.set_aside {
+            if let Err(e) = fs::remove_file(&staged) {
+                warn!("failed to remove {}: {e}", staged.display());
+            }
+        }
+
+        remove_staging_dir();
+
+        Ok(())
+    }
+
+    // Deletes everything that was downloaded and moves replaced jars back
+    pub fn rollback(self) {
+        for path in self.written.iter().filter(|p| p.exists()) {
+            info!("removing {}", path.display());
+
+            if let Err(e) = fs::remove_file(path) {
+                warn!("failed to remove {}: {e}", path.display());
+            }
+        }
+
+        for (path, staged) in self.set_aside.iter().rev() {
+            info!("restoring {}", path.display());
+
+            if let Err(e) = fs::rename(staged, path) {
+                warn!(
+                    "failed to restore {}, it was kept at {}: {e}",
+                    path.display(),
+                    staged.display()
+                );
+            }
+        }
+
+        remove_staging_dir();
+    }
+}
+
+// Only once it is empty, jars that could not be restored are left in it
+fn remove_staging_dir() {
+    let _ = fs::remove_dir(STAGING_DIR);
+}
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Result};
+
+use super::{Info, Override};
+use crate::server::lockfile::Lockfile;
+
+pub fn tree() -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+
+    if lockfile.mods.is_empty() {
+        println!("no projects installed");
+        return Ok(());
+    }
+
+    // Dependencies are listed under whatever needs them, unless nothing installed does
+    let roots = lockfile.mods.iter().filter(|p| {
+        !p.dependency
+            || !lockfile.mods.iter().any(|other| {
+                other
+                    .dependencies
+                    .iter()
+                    .flatten()
+                    .any(|d| d.name == p.name)
+            })
+    });
+
+    let mut lines = vec![];
+    for root in roots {
+        lines.push(format!("{} {}", root.name, root.version));
+        render(
+            &lockfile,
+            root,
+            "",
+            &mut vec![root.name.as_str()],
+            &mut lines,
+        );
+    }
+
+    println!("{}", lines.join("\n"));
+
+    Ok(())
+}
+
+fn render<'a>(
+    lockfile: &'a Lockfile,
+    parent: &'a Info,
+    prefix: &str,
+    path: &mut Vec<&'a str>,
+    lines: &mut Vec<String>,
+) {
+    let deps: Vec<_> = parent
+        .dependencies
+        .iter()
+        .flatten()
+        .filter(|d| !d.incompatible && !d.embedded)
+        .filter(|d| d.required || lockfile.get(&d.name).is_ok())
+        .collect();
+
+    for (i, dep) in deps.iter().enumerate() {
+        let last = i == deps.len() - 1;
+        let branch = if last { "└── " } else { "├── " };
+
+        let (name, note) = match lockfile.overrides.get(&dep.name) {
+            Some(Override::Ignore) => (dep.name.as_str(), Some(String::from("ignored"))),
+            Some(Override::Replace(with)) => {
+                (with.as_str(), Some(format!("replaces {}", dep.name)))
+            }
+            None => match lockfile.get_provider(dep) {
+                Some(p) if lockfile.get(&dep.name).is_err() => {
+                    (p.name.as_str(), Some(format!("provides {}", dep.name)))
+                }
+                _ => (
+                    dep.name.as_str(),
+                    (!dep.required).then(|| String::from("optional")),
+                ),
+            },
+        };
+
+        let installed = lockfile
+            .get(name)
+            .ok()
+            .filter(|_| note.as_deref() != Some("ignored"));
+
+        let mut parts = vec![format!("{prefix}{branch}{name}")];
+        if let Some(info) = installed {
+            parts.push(info.version.clone());
+        } else if note.is_none() {
+            parts.push(String::from("(not installed)"));
+        }
+        if let Some(note) = note {
+            parts.push(format!("({note})"));
+        }
+        let line = parts.join(" ");
+
+        // Cycles are marked instead of followed
+        let Some(info) = installed else {
+            lines.push(line);
+            continue;
+        };
+        if path.contains(&info.name.as_str()) {
+            lines.push(line + " (*)");
+            continue;
+        }
+
+        lines.push(line);
+
+        let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+        path.push(&info.name);
+        render(lockfile, info, &prefix, path, lines);
+        path.pop();
+    }
+}
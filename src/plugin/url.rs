@@ -0,0 +1,35 @@
+#![allow(clippy::case_sensitive_file_extension_comparisons)]
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+// Direct downloads have no metadata, so the name comes from the jar's filename and the
+// checksum is computed once the file has been downloaded
+pub fn fetch(url: &str, version: &str) -> Result<super::Info> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(anyhow!("{url} is not an http(s) url"));
+    }
+
+    let filename = url
+        .rsplit_once('/')
+        .map(|(_, f)| f)
+        .filter(|f| f.ends_with(".jar"))
+        .ok_or_else(|| anyhow!("{url} does not point to a jar file"))?;
+
+    info!("using {filename} from {url}");
+
+    let version = if version == "latest" {
+        "direct"
+    } else {
+        version
+    };
+
+    Ok(super::Info {
+        name: filename.trim_end_matches(".jar").to_lowercase(),
+        id: url.to_string(),
+        version: version.to_string(),
+        source: String::from("url"),
+        download_url: url.to_string(),
+        ..Default::default()
+    })
+}
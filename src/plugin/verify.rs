@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use sha2::{Sha256, Sha512};
+
+use super::{gc, Info};
+use crate::server::lockfile::Lockfile;
+
+pub enum Status {
+    Ok,
+    Missing,
+    NoChecksum,
+    Mismatch { expected: String, actual: String },
+}
+
+pub fn verify_entry(lockfile: &Lockfile, info: &Info) -> Result<Status> {
+    let path = info.get_file_path(&lockfile.loader);
+    if !path.exists() {
+        return Ok(Status::Missing);
+    }
+
+    let Some(checksum) = &info.checksum else {
+        return Ok(Status::NoChecksum);
+    };
+
+    let actual = hash(&path, &checksum.method)?;
+
+    if actual == checksum.hash {
+        Ok(Status::Ok)
+    } else {
+        Ok(Status::Mismatch {
+            expected: checksum.hash.clone(),
+            actual,
+        })
+    }
+}
+
+fn hash(path: &Path, method: &str) -> Result<String> {
+    match method {
+        "sha256" => mup::hash_file::<Sha256>(path),
+        "sha512" => mup::hash_file::<Sha512>(path),
+        _ => Err(anyhow!("unsupported checksum method {method}")),
+    }
+}
+
+// Hashes every lockfile entry in parallel
+pub fn verify_all(lockfile: &Lockfile, jobs: usize) -> Vec<(&Info, Result<Status>)> {
+    let results = mup::parallel_map(&lockfile.mods, jobs, |info| verify_entry(lockfile, info));
+
+    lockfile.mods.iter().zip(results).collect()
+}
+
+// How the jars on disk differ from the lockfile
+pub struct Report<'a> {
+    pub missing: Vec<&'a Info>,
+    pub modified: Vec<&'a Info>,
+    pub unchecked: Vec<&'a Info>,
+    /// Jars in the mod directories that no entry points to
+    pub unknown: Vec<PathBuf>,
+}
+
+pub fn compare(lockfile: &Lockfile, jobs: usize) -> Result<Report<'_>> {
+    let mut report = Report {
+        missing: vec![],
+        modified: vec![],
+        unchecked: vec![],
+        unknown: gc::find_unmanaged(lockfile)?,
+    };
+
+    for (info, status) in verify_all(lockfile, jobs) {
+        match status? {
+            Status::Ok => {}
+            Status::Missing => report.missing.push(info),
+            Status::NoChecksum => report.unchecked.push(info),
+            Status::Mismatch { .. } => report.modified.push(info),
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn verify(jobs: Option<usize>) -> Result<()> {
+    let lockfile = Lockfile::init()?;
+    if !lockfile.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    let mut failures = 0;
+
+    for (info, status) in verify_all(&lockfile, jobs.unwrap_or_else(mup::jobs)) {
+        match status? {
+            Status::Ok => continue,
+            Status::Missing => println!("{}: missing", info.name),
+            Status::NoChecksum => {
+                println!("{}: no checksum recorded", info.name);
+                continue;
+            }
+            Status::Mismatch { expected, actual } => {
+                println!("{}: expected {expected} but got {actual}", info.name);
+            }
+        }
+
+        failures += 1;
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("{failures} projects failed verification"));
+    }
+
+    println!("all {} projects verified", lockfile.mods.len());
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anyhow::{anyhow, Result};
+
+use super::Override;
+use crate::server::lockfile::Lockfile;
+
+pub fn why(id: &str) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let entry = lockfile.get(id)?;
+
+    println!("{} {}", entry.name, entry.version);
+
+    if !entry.dependency {
+        println!("explicitly requested");
+    } else if entry.required_by.is_empty() {
+        println!("installed as a dependency");
+    } else {
+        println!(
+            "installed as a dependency of {}",
+            entry.required_by.join(", ")
+        );
+    }
+
+    // Overrides and provides declarations make other projects depend on this one under a
+    // different name
+    let replaced: Vec<&str> = lockfile
+        .overrides
+        .iter()
+        .filter(|(_, o)| matches!(o, Override::Replace(with) if *with == entry.name || *with == entry.id))
+        .map(|(name, _)| name.as_str())
+        .chain(lockfile.provides.get(&entry.name).into_iter().flatten().map(String::as_str))
+        .collect();
+
+    let dependents: Vec<String> = lockfile
+        .mods
+        .iter()
+        .filter_map(|p| {
+            let dep = p
+                .dependencies
+                .iter()
+                .flatten()
+                .filter(|d| !d.incompatible)
+                .find(|d| d.name == entry.name || replaced.contains(&d.name.as_str()))?;
+
+            Some(if dep.required {
+                p.name.clone()
+            } else {
+                format!("{} (optional)", p.name)
+            })
+        })
+        .collect();
+
+    if dependents.is_empty() {
+        println!("not required by any installed project");
+    } else {
+        println!("required by {}", dependents.join(", "));
+    }
+
+    Ok(())
+}
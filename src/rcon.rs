@@ -0,0 +1,140 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+// Packet types of the Source RCON protocol, which Minecraft implements
+const LOGIN: i32 = 3;
+const COMMAND: i32 = 2;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A logged in RCON connection to a server.
+pub struct Client {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl Client {
+    pub fn connect(address: &str, password: &str) -> Result<Self> {
+        info!("connecting to rcon at {address}");
+
+        let stream = TcpStream::connect(address)
+            .map_err(|e| anyhow!("failed to connect to rcon at {address}: {e}"))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+
+        let mut client = Self { stream, next_id: 1 };
+
+        // The server answers a failed login with an ID of -1
+        let id = client.send(LOGIN, password)?;
+        let (reply_id, _) = client.receive()?;
+        if reply_id != id {
+            return Err(anyhow!("rcon login failed, check rcon.password"));
+        }
+
+        Ok(client)
+    }
+
+    /// Runs a console command and returns its output.
+    pub fn command(&mut self, command: &str) -> Result<String> {
+        info!("rcon: {command}");
+
+        let id = self.send(COMMAND, command)?;
+        let (reply_id, body) = self.receive()?;
+        if reply_id != id {
+            return Err(anyhow!("unexpected rcon response to {command}"));
+        }
+
+        Ok(body)
+    }
+
+    fn send(&mut self, kind: i32, body: &str) -> Result<i32> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.stream.write_all(&encode(id, kind, body)?)?;
+
+        Ok(id)
+    }
+
+    fn receive(&mut self) -> Result<(i32, String)> {
+        let mut length = [0; 4];
+        self.stream.read_exact(&mut length)?;
+
+        let length = usize::try_from(i32::from_le_bytes(length))?;
+        if !(10..=4096 + 10).contains(&length) {
+            return Err(anyhow!("invalid rcon packet length {length}"));
+        }
+
+        let mut packet = vec![0; length];
+        self.stream.read_exact(&mut packet)?;
+
+        decode(&packet)
+    }
+}
+
+// Length, request ID and type are little-endian, the body is followed by two NUL bytes
+fn encode(id: i32, kind: i32, body: &str) -> Result<Vec<u8>> {
+    let length = i32::try_from(body.len() + 10)?;
+
+    let mut packet = Vec::with_capacity(body.len() + 14);
+    packet.extend_from_slice(&length.to_le_bytes());
+    packet.extend_from_slice(&id.to_le_bytes());
+    packet.extend_from_slice(&kind.to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet.extend_from_slice(&[0, 0]);
+
+    Ok(packet)
+}
+
+// Takes a packet without its length prefix
+fn decode(packet: &[u8]) -> Result<(i32, String)> {
+    if packet.len() < 10 {
+        return Err(anyhow!("truncated rcon packet"));
+    }
+
+    let id = i32::from_le_bytes(packet[0..4].try_into()?);
+    let body = String::from_utf8_lossy(&packet[8..packet.len() - 2]).into_owned();
+
+    Ok((id, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_command() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // Accepts the login and echoes commands back
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            for _ in 0..2 {
+                let mut length = [0; 4];
+                stream.read_exact(&mut length).unwrap();
+                let mut packet = vec![0; i32::from_le_bytes(length) as usize];
+                stream.read_exact(&mut packet).unwrap();
+
+                let (id, body) = decode(&packet).unwrap();
+                let reply = if body == "secret" {
+                    String::new()
+                } else {
+                    format!("ran {body}")
+                };
+                stream.write_all(&encode(id, 0, &reply).unwrap()).unwrap();
+            }
+        });
+
+        let mut client = Client::connect(&address, "secret").unwrap();
+        assert_eq!(client.command("save-all").unwrap(), "ran save-all");
+
+        server.join().unwrap();
+    }
+}
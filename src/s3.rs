@@ -0,0 +1,198 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use sha2::{Digest, Sha256};
+
+use crate::AGENT;
+
+// Hash of an empty body, sent with requests that have none
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// An S3-compatible bucket, addressed path-style so it works with MinIO, R2, B2 and others.
+pub struct Bucket<'a> {
+    /// Base URL of the service, e.g. `https://s3.eu-central-1.amazonaws.com`
+    pub endpoint: &'a str,
+    pub name: &'a str,
+    pub region: &'a str,
+    pub access_key_id: &'a str,
+    pub secret_access_key: &'a str,
+}
+
+impl Bucket<'_> {
+    pub fn put(&self, key: &str, path: &Path) -> Result<()> {
+        info!("uploading {} to {}", path.display(), self.url(key));
+
+        let payload_hash = crate::hash_file::<Sha256>(path)?;
+        let headers = self.sign("PUT", key, &payload_hash, SystemTime::now())?;
+
+        let mut request = AGENT.put(self.url(key));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let status = request.send(File::open(path)?)?.status().as_u16();
+        if !(200..300).contains(&status) {
+            return Err(anyhow!("uploading {key} failed with status {status}"));
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str, path: &Path) -> Result<()> {
+        info!("downloading {} to {}", self.url(key), path.display());
+
+        let headers = self.sign("GET", key, EMPTY_SHA256, SystemTime::now())?;
+
+        let mut request = AGENT.get(self.url(key));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let resp = request.call()?;
+        let status = resp.status().as_u16();
+        if !(200..300).contains(&status) {
+            return Err(anyhow!("downloading {key} failed with status {status}"));
+        }
+
+        if let Some(prefix) = path.parent() {
+            fs::create_dir_all(prefix)?;
+        }
+
+        io::copy(
+            &mut resp.into_body().into_reader(),
+            &mut File::create(path)?,
+        )?;
+
+        Ok(())
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}{}", self.endpoint.trim_end_matches('/'), self.path(key))
+    }
+
+    fn path(&self, key: &str) -> String {
+        format!("/{}/{}", encode(self.name), encode(key))
+    }
+
+    // AWS Signature Version 4, see
+    // https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html
+    fn sign(
+        &self,
+        method: &str,
+        key: &str,
+        payload_hash: &str,
+        now: SystemTime,
+    ) -> Result<Vec<(&'static str, String)>> {
+        let host = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint, |(_, rest)| rest)
+            .trim_end_matches('/');
+
+        let timestamp = amz_date(now.duration_since(UNIX_EPOCH)?.as_secs());
+        let date = &timestamp[..8];
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{method}\n{}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{timestamp}\n\n{signed_headers}\n{payload_hash}",
+            self.path(key)
+        );
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{timestamp}\n{scope}\n{}",
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut signing_key = hmac(
+            format!("AWS4{}", self.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region, "s3", "aws4_request"] {
+            signing_key = hmac(&signing_key, part.as_bytes());
+        }
+
+        let signature = hex(&hmac(&signing_key, string_to_sign.as_bytes()));
+
+        Ok(vec![
+            (
+                "Authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                    self.access_key_id
+                ),
+            ),
+            ("x-amz-content-sha256", payload_hash.to_string()),
+            ("x-amz-date", timestamp),
+        ])
+    }
+}
+
+// Percent-encodes everything but unreserved characters, keeping `/` between key segments
+fn encode(s: &str) -> String {
+    s.bytes().fold(String::new(), |mut acc, b| {
+        if b.is_ascii_alphanumeric() || b"-_.~/".contains(&b) {
+            acc.push(b as char);
+        } else {
+            acc.push_str(&format!("%{b:02X}"));
+        }
+        acc
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner = Sha256::new()
+        .chain_update(block.map(|b| b ^ 0x36))
+        .chain_update(data)
+        .finalize();
+
+    Sha256::new()
+        .chain_update(block.map(|b| b ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+// Formats seconds since the epoch as `YYYYMMDDTHHMMSSZ`
+fn amz_date(secs: u64) -> String {
+    crate::format_timestamp(secs).replace(['-', ':'], "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hex(&hmac(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_amz_date() {
+        assert_eq!(amz_date(0), "19700101T000000Z");
+        assert_eq!(amz_date(1_709_210_096), "20240229T123456Z");
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use regex::Regex;
+use sha2::Sha512;
+
+use super::lockfile::Lockfile;
+use crate::{
+    loader::Loader,
+    plugin::{self, modrinth},
+};
+
+// What the server in a directory runs, as far as its files tell
+#[derive(Debug, PartialEq, Eq)]
+struct Detected {
+    loader: String,
+    minecraft_version: Option<String>,
+    version: String,
+}
+
+// Writes a lockfile for the server in the current directory from the files already there,
+// without downloading anything. The loader and Minecraft version are detected unless given.
+pub fn adopt(minecraft_version: Option<&str>, loader: Option<&str>) -> Result<()> {
+    if Lockfile::existing()?.is_some() {
+        return Err(anyhow!("a server is already initialized in this directory"));
+    }
+
+    let detected = detect(Path::new("."))?;
+    if let Some(detected) = &detected {
+        println!(
+            "found {} {} (loader version {})",
+            detected.loader,
+            detected
+                .minecraft_version
+                .as_deref()
+                .unwrap_or("for any Minecraft version"),
+            detected.version
+        );
+    }
+
+    let loader_name = loader
+        .or_else(|| detected.as_ref().map(|d| d.loader.as_str()))
+        .ok_or_else(|| anyhow!("could not identify the server jar, pass --loader"))?;
+    let minecraft_version = minecraft_version
+        .or_else(|| detected.as_ref()?.minecraft_version.as_deref())
+        .ok_or_else(|| anyhow!("could not tell the Minecraft version, pass --minecraft-version"))?;
+
+    // A detected loader version only applies to the loader it was detected for
+    let version = detected
+        .as_ref()
+        .filter(|d| d.loader == loader_name)
+        .map_or("latest", |d| d.version.as_str());
+
+    let candidate = Loader::new(loader_name, minecraft_version, version, false);
+    if !candidate.has_valid_minecraft_version() {
+        return Err(anyhow!("minecraft version {minecraft_version} is invalid"));
+    }
+
+    let default_location = candidate.mod_location().to_string();
+    let jars: Vec<(PathBuf, String)> = candidate
+        .mod_locations()
+        .into_iter()
+        .flat_map(|dir| {
+            jars_in(Path::new(dir))
+                .into_iter()
+                .map(move |p| (p, dir.to_string()))
+        })
+        .collect();
+
+    info!("looking up {} jars", jars.len());
+
+    let found = mup::parallel_map(&jars, mup::jobs(), |(path, dir)| {
+        identify(path).map(|info| plugin::Info {
+            location: (*dir != default_location).then(|| dir.clone()),
+            ..info
+        })
+    });
+
+    let mut lockfile = Lockfile::with_params(minecraft_version, loader_name, false)?;
+    lockfile.loader = candidate;
+
+    // One jar failing should not stop the rest, they are reported together at the end
+    let mut local = 0;
+    let mut failed = vec![];
+    for (info, (path, _)) in found.into_iter().zip(&jars) {
+        match info {
+            Ok(info) => {
+                if info.source == "local" {
+                    local += 1;
+                }
+                lockfile.add(info);
+            }
+            Err(e) => {
+                warn!("failed to identify {}: {e}", path.display());
+                failed.push(path.display().to_string());
+            }
+        }
+    }
+
+    lockfile.save()?;
+
+    if !failed.is_empty() {
+        return Err(anyhow!(
+            "failed to identify {}, add them with mup plugin add",
+            failed.join(", ")
+        ));
+    }
+
+    println!(
+        "adopted {} {} with {} projects, {local} of them not found on Modrinth and tracked as local jars",
+        lockfile.loader.name,
+        lockfile.loader.minecraft_version,
+        lockfile.mods.len()
+    );
+
+    Ok(())
+}
+
+// Modrinth knows most jars by their hash, anything else is tracked as a local jar
+fn identify(path: &Path) -> Result<plugin::Info> {
+    let hash = mup::hash_file::<Sha512>(path)?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow!("invalid path {}", path.display()))?;
+
+    if let Some(info) = modrinth::from_hash(&hash, file_name)? {
+        info!("{} is {} on Modrinth", path.display(), info.name);
+        return Ok(info);
+    }
+
+    plugin::local::fetch(&path.to_string_lossy(), "latest")
+}
+
+fn jars_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut jars: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("jar")))
+        .collect();
+    jars.sort();
+
+    jars
+}
+
+// Forge and NeoForge run from their libraries, every other server from a jar whose contents
+// tell what it is
+fn detect(dir: &Path) -> Result<Option<Detected>> {
+    if let Some(detected) = detect_libraries(dir) {
+        return Ok(Some(detected));
+    }
+
+    for path in jars_in(dir) {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if let Some(detected) = inspect_jar(name, &fs::read(&path)?)? {
+            return Ok(Some(detected));
+        }
+    }
+
+    Ok(None)
+}
+
+fn detect_libraries(dir: &Path) -> Option<Detected> {
+    let newest = |path: PathBuf| {
+        fs::read_dir(path)
+            .ok()?
+            .filter_map(Result::ok)
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .max_by(|a, b| mup::version::compare_versions(a, b))
+    };
+
+    let libraries = dir.join("libraries").join("net");
+
+    if let Some(version) = newest(libraries.join("neoforged").join("neoforge")) {
+        return Some(Detected {
+            loader: String::from("neoforge"),
+            minecraft_version: neoforge_minecraft_version(&version),
+            version,
+        });
+    }
+
+    let full = newest(libraries.join("minecraftforge").join("forge"))?;
+    let (minecraft_version, version) = full.split_once('-')?;
+
+    Some(Detected {
+        loader: String::from("forge"),
+        minecraft_version: Some(minecraft_version.to_string()),
+        version: version.to_string(),
+    })
+}
+
+// NeoForge versions drop the leading "1." of the Minecraft version, and the patch version of
+// Minecraft is 0 when the game has none, e.g. 21.0.167 is for 1.21
+fn neoforge_minecraft_version(version: &str) -> Option<String> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse::<u32>().ok()?;
+    let minor = parts.next()?.parse::<u32>().ok()?;
+
+    if major == 0 {
+        return None;
+    }
+
+    Some(if minor == 0 {
+        format!("1.{major}")
+    } else {
+        format!("1.{major}.{minor}")
+    })
+}
+
+fn inspect_jar(name: &str, jar: &[u8]) -> Result<Option<Detected>> {
+    let Ok(manifest) = mup::zip::read(jar, "META-INF/MANIFEST.MF") else {
+        return Ok(None);
+    };
+    let main_class = manifest
+        .as_deref()
+        .map(String::from_utf8_lossy)
+        .and_then(|m| {
+            m.lines()
+                .find_map(|l| l.strip_prefix("Main-Class:").map(|c| c.trim().to_string()))
+        })
+        .unwrap_or_default();
+
+    // Paperclip lists the server it patches together, e.g. 1.21.4/paper-1.21.4.jar
+    if let Some(list) = mup::zip::read(jar, "META-INF/versions.list")? {
+        let pattern = Regex::new(r"([a-z]+)-(\d+\.\d+(?:\.\d+)?)\.jar")?;
+        let list = String::from_utf8_lossy(&list);
+        let Some(captures) = pattern.captures(&list) else {
+            return Ok(None);
+        };
+
+        let project = &captures[1];
+        let loader = if Loader::parse_name(project).is_ok() {
+            project
+        } else {
+            "paper"
+        };
+
+        return Ok(Some(Detected {
+            loader: loader.to_string(),
+            minecraft_version: Some(captures[2].to_string()),
+            version: build_number(name).unwrap_or_else(|| String::from("latest")),
+        }));
+    }
+
+    if let Some(properties) = mup::zip::read(jar, "install.properties")? {
+        let properties = String::from_utf8_lossy(&properties);
+        let value = |key: &str| {
+            properties.lines().find_map(|l| {
+                l.strip_prefix(key)
+                    .and_then(|v| v.strip_prefix('='))
+                    .map(|v| v.trim().to_string())
+            })
+        };
+
+        return Ok(Some(Detected {
+            loader: String::from("fabric"),
+            minecraft_version: value("game-version"),
+            version: value("fabric-loader-version").unwrap_or_else(|| String::from("latest")),
+        }));
+    }
+
+    // Proxies run every Minecraft version, so only the build can be told from the name
+    let proxy = match main_class.as_str() {
+        "com.velocitypowered.proxy.Velocity" => Some("velocity"),
+        "net.md_5.bungee.Bootstrap" if name.starts_with("waterfall") => Some("waterfall"),
+        "net.md_5.bungee.Bootstrap" => Some("bungeecord"),
+        _ => None,
+    };
+    if let Some(loader) = proxy {
+        return Ok(Some(Detected {
+            loader: loader.to_string(),
+            minecraft_version: None,
+            version: build_number(name).unwrap_or_else(|| String::from("latest")),
+        }));
+    }
+
+    if let Some(version) = mup::zip::read(jar, "version.json")? {
+        let version: serde_json::Value = serde_json::from_slice(&version)?;
+
+        return Ok(version["id"].as_str().map(|id| Detected {
+            loader: String::from("vanilla"),
+            minecraft_version: Some(id.to_string()),
+            version: String::from("latest"),
+        }));
+    }
+
+    Ok(None)
+}
+
+// Builds from the PaperMC API and BungeeCord's CI end their file names with the build number,
+// e.g. paper-1.21.4-100.jar
+fn build_number(name: &str) -> Option<String> {
+    let stem = name.strip_suffix(".jar")?;
+    let (_, build) = stem.rsplit_once('-')?;
+
+    build
+        .chars()
+        .all(|c| c.is_ascii_digit())
+        .then(|| build.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_jar() {
+        let paperclip = mup::zip::write(&[
+            (
+                String::from("META-INF/MANIFEST.MF"),
+                b"Main-Class: io.papermc.paperclip.Main\n".to_vec(),
+            ),
+            (
+                String::from("META-INF/versions.list"),
+                b"abc\t1.21.4\t1.21.4/paper-1.21.4.jar\n".to_vec(),
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            inspect_jar("paper-1.21.4-100.jar", &paperclip).unwrap(),
+            Some(Detected {
+                loader: String::from("paper"),
+                minecraft_version: Some(String::from("1.21.4")),
+                version: String::from("100"),
+            })
+        );
+
+        let fabric = mup::zip::write(&[(
+            String::from("install.properties"),
+            b"fabric-loader-version=0.16.9\ngame-version=1.21.1\n".to_vec(),
+        )])
+        .unwrap();
+
+        assert_eq!(
+            inspect_jar("server.jar", &fabric).unwrap(),
+            Some(Detected {
+                loader: String::from("fabric"),
+                minecraft_version: Some(String::from("1.21.1")),
+                version: String::from("0.16.9"),
+            })
+        );
+
+        assert_eq!(
+            neoforge_minecraft_version("21.0.167").as_deref(),
+            Some("1.21")
+        );
+        assert_eq!(
+            neoforge_minecraft_version("21.1.77").as_deref(),
+            Some("1.21.1")
+        );
+        assert_eq!(build_number("server.jar"), None);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use log::{info, warn};
+
+use super::properties::{self, Properties, PROPERTIES_PATH};
+
+const BACKUP_DIR: &str = ".mup/config-backups";
+
+#[derive(Debug, Subcommand)]
+pub enum Config {
+    /// Print a value from server.properties
+    Get {
+        /// Key to read, such as view-distance
+        key: String,
+    },
+
+    /// Change a value in server.properties, keeping comments and the order of keys
+    Set {
+        /// Key to change, such as motd
+        key: String,
+
+        /// New value, checked against what the server accepts for known keys
+        value: String,
+    },
+
+    /// Restore the most recent config snapshot
+    Undo,
+}
+
+pub fn action(config: &Config) -> Result<()> {
+    match config {
+        Config::Get { key } => get(key),
+        Config::Set { key, value } => set(key, value),
+        Config::Undo => undo(),
+    }
+}
+
+fn get(key: &str) -> Result<()> {
+    let properties = Properties::load()?;
+    let value = properties
+        .get(key)
+        .ok_or_else(|| anyhow!("{key} is not set in {PROPERTIES_PATH}"))?;
+
+    println!("{value}");
+
+    Ok(())
+}
+
+fn set(key: &str, value: &str) -> Result<()> {
+    properties::validate(key, value)?;
+
+    if !properties::is_known(key) {
+        warn!("{key} is not a vanilla property, setting it anyway");
+    }
+
+    let mut properties = Properties::load()?;
+    properties.set(key, value);
+    properties.save()?;
+
+    println!("set {key} to {value}");
+
+    Ok(())
+}
+
+// Copies `path` into a new timestamped snapshot directory before it is modified
+pub fn snapshot(path: &Path) -> Result<()> {
+    snapshot_all(&[path])
+}
+
+// Files changed together share a snapshot, so undoing restores all of them
+pub fn snapshot_all(paths: &[&Path]) -> Result<()> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+
+    for path in paths.iter().filter(|p| p.exists()) {
+        let destination = Path::new(BACKUP_DIR).join(timestamp.to_string()).join(path);
+
+        info!(
+            "saving snapshot of {} to {}",
+            path.to_string_lossy(),
+            destination.to_string_lossy()
+        );
+
+        if let Some(prefix) = destination.parent() {
+            fs::create_dir_all(prefix)?;
+        }
+
+        fs::copy(path, destination)?;
+    }
+
+    Ok(())
+}
+
+fn undo() -> Result<()> {
+    let latest = latest_snapshot()?.ok_or_else(|| anyhow!("no config snapshots to restore"))?;
+
+    info!("restoring snapshot {}", latest.to_string_lossy());
+
+    restore(&latest, &latest)?;
+
+    fs::remove_dir_all(latest)?;
+
+    Ok(())
+}
+
+fn latest_snapshot() -> Result<Option<PathBuf>> {
+    if !Path::new(BACKUP_DIR).exists() {
+        return Ok(None);
+    }
+
+    let latest = fs::read_dir(BACKUP_DIR)?
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let timestamp: u128 = e.file_name().to_str()?.parse().ok()?;
+            Some((timestamp, e.path()))
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map(|(_, path)| path);
+
+    Ok(latest)
+}
+
+fn restore(root: &Path, dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            restore(root, &path)?;
+            continue;
+        }
+
+        let original = path.strip_prefix(root)?;
+        info!("restoring {}", original.to_string_lossy());
+
+        if let Some(prefix) = original.parent() {
+            if !prefix.as_os_str().is_empty() {
+                fs::create_dir_all(prefix)?;
+            }
+        }
+
+        fs::copy(&path, original)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,432 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use super::lockfile::Lockfile;
+
+const CRASH_DIR: &str = "crash-reports";
+
+// Packages of Java, the game and the loaders, which show up in every stack trace
+const PLATFORM_PACKAGES: [&str; 22] = [
+    "java.",
+    "javax.",
+    "jdk.",
+    "sun.",
+    "com.sun.",
+    "net.minecraft.",
+    "com.mojang.",
+    "net.fabricmc.",
+    "net.minecraftforge.",
+    "net.neoforged.",
+    "cpw.mods.",
+    "org.bukkit.",
+    "org.spigotmc.",
+    "io.papermc.",
+    "com.destroystokyo.",
+    "org.spongepowered.",
+    "com.google.",
+    "org.apache.",
+    "io.netty.",
+    "it.unimi.",
+    "org.slf4j.",
+    "org.objectweb.",
+];
+
+// Package segments too generic to identify a project by
+const GENERIC_SEGMENTS: [&str; 16] = [
+    "com", "org", "net", "io", "me", "dev", "de", "github", "api", "common", "core", "util",
+    "mixin", "impl", "plugin", "internal",
+];
+
+#[derive(Debug, Args)]
+pub struct Crashes {
+    /// Crash report to analyze, the newest one in crash-reports by default
+    report: Option<String>,
+
+    /// Analyze every crash report instead of only the newest
+    #[arg(long, action, conflicts_with = "report")]
+    all: bool,
+}
+
+#[derive(Debug, Default)]
+struct Report {
+    description: Option<String>,
+    exception: Option<String>,
+    /// Stack frames of the error, outermost exception first
+    frames: Vec<String>,
+    /// Mod IDs the loader itself blamed, newer versions of Forge list these
+    suspected: Vec<String>,
+}
+
+struct Project {
+    name: String,
+    version: String,
+    /// Normalized names a stack frame may refer to the project by
+    keys: Vec<String>,
+}
+
+struct Suspect<'a> {
+    project: &'a Project,
+    /// Whether the loader named it in the report
+    blamed: bool,
+    frames: usize,
+    first: usize,
+}
+
+pub fn action(crashes: &Crashes) -> Result<()> {
+    let reports = match &crashes.report {
+        Some(path) => vec![PathBuf::from(path)],
+        None => find_reports(crashes.all)?,
+    };
+
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let projects: Vec<Project> = lockfile
+        .mods
+        .iter()
+        .map(|info| {
+            let path = info.get_file_path(&lockfile.loader);
+            let file = path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .unwrap_or_default();
+
+            let mut keys = vec![normalize(&info.name), normalize(jar_stem(file))];
+            keys.dedup();
+
+            Project {
+                name: info.name.clone(),
+                version: info.version.clone(),
+                keys,
+            }
+        })
+        .collect();
+
+    let mut sections = vec![];
+    for path in reports {
+        let report = parse(&fs::read_to_string(&path)?);
+        sections.push(describe(&path, &report, &projects));
+    }
+
+    println!("{}", sections.join("\n\n"));
+
+    Ok(())
+}
+
+fn find_reports(all: bool) -> Result<Vec<PathBuf>> {
+    if !Path::new(CRASH_DIR).is_dir() {
+        return Err(anyhow!("no crash reports, {CRASH_DIR} does not exist"));
+    }
+
+    // Reports are named after the time of the crash, so they sort chronologically
+    let mut reports: Vec<PathBuf> = fs::read_dir(CRASH_DIR)?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "txt"))
+        .collect();
+    reports.sort();
+
+    if reports.is_empty() {
+        return Err(anyhow!("no crash reports in {CRASH_DIR}"));
+    }
+
+    if !all {
+        reports.drain(..reports.len() - 1);
+    }
+
+    Ok(reports)
+}
+
+fn describe(path: &Path, report: &Report, projects: &[Project]) -> String {
+    let mut lines = vec![path.display().to_string()];
+
+    lines.extend(report.description.iter().map(|d| format!("  {d}")));
+    lines.extend(report.exception.iter().map(|e| format!("  {e}")));
+
+    let ranked = suspects(report, projects);
+    match ranked.split_first() {
+        Some((likely, others)) => {
+            lines.push(format!(
+                "  likely caused by {} {} ({})",
+                likely.project.name,
+                likely.project.version,
+                evidence(likely)
+            ));
+
+            for other in others {
+                lines.push(format!(
+                    "  also involved: {} {} ({})",
+                    other.project.name,
+                    other.project.version,
+                    evidence(other)
+                ));
+            }
+        }
+        None => lines.push(String::from(
+            "  no project from the lockfile appears in the stack trace, the cause may be the \
+             server itself, Java or a jar mup does not manage",
+        )),
+    }
+
+    let unmanaged = unmanaged_jars(report, projects);
+    if !unmanaged.is_empty() {
+        lines.push(format!(
+            "  jars in the stack trace that are not in the lockfile: {}",
+            unmanaged.join(", ")
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn evidence(suspect: &Suspect) -> String {
+    let mut parts = vec![];
+
+    if suspect.blamed {
+        parts.push(String::from("suspected by the loader"));
+    }
+
+    if suspect.frames > 0 {
+        parts.push(format!(
+            "{} stack frame{}, first at frame {}",
+            suspect.frames,
+            if suspect.frames == 1 { "" } else { "s" },
+            suspect.first + 1
+        ));
+    }
+
+    parts.join(", ")
+}
+
+fn parse(contents: &str) -> Report {
+    let mut report = Report::default();
+    let mut lines = contents.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if let Some(description) = trimmed.strip_prefix("Description: ") {
+            report
+                .description
+                .get_or_insert_with(|| description.to_string());
+        } else if trimmed.starts_with("Suspected Mod") {
+            // Followed by one `Name (modid), Version: x` line per mod and details indented below
+            while let Some(next) = lines.next_if(|l| l.starts_with('\t') || l.starts_with(' ')) {
+                if let Some(id) = next
+                    .split_once('(')
+                    .and_then(|(_, rest)| rest.split_once(')'))
+                    .map(|(id, _)| id)
+                {
+                    report.suspected.push(normalize(id));
+                }
+            }
+        } else if let Some(frame) = trimmed.strip_prefix("at ") {
+            report.frames.push(frame.to_string());
+        } else if report.exception.is_none()
+            && report.description.is_some()
+            && (trimmed.contains("Exception") || trimmed.contains("Error"))
+            && !trimmed.starts_with("Description")
+        {
+            report.exception = Some(trimmed.to_string());
+        } else if trimmed.starts_with("A detailed walkthrough of the error") {
+            // What follows lists every loaded mod, which would implicate all of them
+            break;
+        }
+    }
+
+    report
+}
+
+// Projects referenced by the stack trace, the one closest to the top first
+fn suspects<'a>(report: &Report, projects: &'a [Project]) -> Vec<Suspect<'a>> {
+    let mut found: Vec<Suspect> = vec![];
+
+    for project in projects {
+        if report.suspected.iter().any(|s| project.keys.contains(s)) {
+            found.push(Suspect {
+                project,
+                blamed: true,
+                frames: 0,
+                first: usize::MAX,
+            });
+        }
+    }
+
+    for (i, frame) in report.frames.iter().enumerate() {
+        let tokens = frame_tokens(frame);
+
+        for project in projects {
+            if !project.keys.iter().any(|k| tokens.contains(k)) {
+                continue;
+            }
+
+            match found.iter_mut().find(|s| s.project.name == project.name) {
+                Some(suspect) => {
+                    suspect.first = suspect.first.min(i);
+                    suspect.frames += 1;
+                }
+                None => found.push(Suspect {
+                    project,
+                    blamed: false,
+                    frames: 1,
+                    first: i,
+                }),
+            }
+        }
+    }
+
+    found.sort_by_key(|s| (!s.blamed, s.first, std::cmp::Reverse(s.frames)));
+
+    found
+}
+
+// Names a stack frame may refer to a project by: the jar it was loaded from, the mod ID
+// in Forge's `TRANSFORMER/modid@version/` prefix and the segments of its package
+fn frame_tokens(frame: &str) -> Vec<String> {
+    let mut tokens = vec![];
+
+    for jar in frame_jars(frame) {
+        tokens.push(normalize(jar_stem(jar)));
+    }
+
+    let class = match frame.split_once('/') {
+        Some((prefix, rest)) if frame.starts_with("TRANSFORMER/") || prefix.contains('@') => {
+            if let Some((module, _)) = rest.split_once('@') {
+                tokens.push(normalize(module));
+            }
+            rest.rsplit('/').next().unwrap_or(rest)
+        }
+        _ => frame,
+    };
+
+    let class = class.split('(').next().unwrap_or(class);
+    if !PLATFORM_PACKAGES.iter().any(|p| class.starts_with(p)) {
+        let segments: Vec<&str> = class.split('.').collect();
+
+        // The last two segments are the class and the method
+        tokens.extend(
+            segments[..segments.len().saturating_sub(2)]
+                .iter()
+                .filter(|s| !GENERIC_SEGMENTS.contains(s))
+                .map(|s| normalize(s)),
+        );
+    }
+
+    tokens.retain(|t| !t.is_empty());
+
+    tokens
+}
+
+// Jar names in frames such as `... ~[sodium-0.5.8.jar%23123!/:?]` or `[server.jar:?]`
+fn frame_jars(frame: &str) -> Vec<&str> {
+    frame
+        .split(['[', '/', ' ', '~'])
+        .filter_map(|part| part.find(".jar").map(|end| &part[..end + 4]))
+        .collect()
+}
+
+fn unmanaged_jars(report: &Report, projects: &[Project]) -> Vec<String> {
+    let mut jars: Vec<String> = report
+        .frames
+        .iter()
+        .flat_map(|f| frame_jars(f))
+        .filter(|jar| {
+            let key = normalize(jar_stem(jar));
+            !projects.iter().any(|p| p.keys.contains(&key))
+        })
+        .filter(|jar| {
+            // The game and loaders themselves
+            ![
+                "server",
+                "minecraft",
+                "paper",
+                "fabric",
+                "forge",
+                "neoforge",
+                "java",
+            ]
+            .iter()
+            .any(|platform| jar.starts_with(platform))
+        })
+        .map(String::from)
+        .collect();
+
+    jars.sort();
+    jars.dedup();
+
+    jars
+}
+
+// `sodium-fabric-0.5.8+mc1.20.4.jar` becomes `sodium-fabric`
+fn jar_stem(file: &str) -> &str {
+    let name = file.strip_suffix(".jar").unwrap_or(file);
+
+    name.char_indices()
+        .find(|(i, c)| {
+            matches!(c, '-' | '_' | '+')
+                && name[i + 1..].starts_with(|n: char| n.is_ascii_digit() || n == 'v')
+        })
+        .map_or(name, |(i, _)| &name[..i])
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = "---- Minecraft Crash Report ----
+// Why did you do that?
+
+Time: 2024-01-05 12:00:00
+Description: Exception in server tick loop
+
+java.lang.NullPointerException: Cannot invoke \"Object.hashCode()\"
+\tat java.util.HashMap.hash(HashMap.java:338) ~[?:?]
+\tat TRANSFORMER/create@0.5.1.f/com.simibubi.create.content.Foo.tick(Foo.java:10) ~[create-1.20.1-0.5.1.f.jar%23123!/:0.5.1.f]
+\tat net.minecraft.server.MinecraftServer.tick(MinecraftServer.java:900) ~[server-1.20.1.jar:?]
+\tat dev.lambdaurora.lambdynlights.Thing.run(Thing.java:5) ~[lambdynamiclights-2.3.jar:?]
+\tat org.example.mystery.Boom.go(Boom.java:1) ~[mystery-1.0.jar:?]
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+\tat dev.sodium.Unrelated.run(Unrelated.java:1)
+";
+
+    #[test]
+    fn test_suspects() {
+        let project = |name: &str, file: &str| Project {
+            name: name.to_string(),
+            version: String::from("1.0"),
+            keys: vec![normalize(name), normalize(jar_stem(file))],
+        };
+        let projects = [
+            project("sodium", "sodium-fabric-0.5.8+mc1.20.4.jar"),
+            project("lambdynamiclights", "lambdynamiclights-2.3.jar"),
+            project("create", "create-1.20.1-0.5.1.f.jar"),
+        ];
+
+        let report = parse(REPORT);
+        assert_eq!(
+            report.description.as_deref(),
+            Some("Exception in server tick loop")
+        );
+        assert_eq!(report.frames.len(), 5);
+
+        let ranked: Vec<&str> = suspects(&report, &projects)
+            .iter()
+            .map(|s| s.project.name.as_str())
+            .collect();
+        assert_eq!(ranked, ["create", "lambdynamiclights"]);
+
+        assert_eq!(unmanaged_jars(&report, &projects), ["mystery-1.0.jar"]);
+    }
+}
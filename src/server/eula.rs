@@ -1,48 +1,123 @@
 use std::{
-    fs::{self, File},
-    io::Write,
+    env, fs,
+    io::{self, IsTerminal},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
+use dialoguer::Confirm;
 use log::info;
 
+const EULA_PATH: &str = "eula.txt";
+const EULA_URL: &str = "https://aka.ms/MinecraftEULA";
+const SIGNATURE: &str = "# Signed by mup";
+
+// Set to true to accept the eula without being asked, e.g. in containers and CI
+const ACCEPT_VAR: &str = "MUP_ACCEPT_EULA";
+
 pub fn sign() -> Result<()> {
     info!("signing eula");
 
-    let mut file = if fs::metadata("eula.txt").is_err() {
-        File::create("eula.txt")?
-    } else {
-        fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open("eula.txt")?
+    let path = Path::new(EULA_PATH);
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    super::config::snapshot(path)?;
+    fs::write(path, signed(&existing, &mup::format_timestamp(now)))?;
+
+    Ok(())
+}
+
+pub fn is_signed() -> bool {
+    fs::read_to_string(EULA_PATH).is_ok_and(|eula| eula.lines().any(|l| l.trim() == "eula=true"))
+}
+
+// Signs the eula if it is accepted through $MUP_ACCEPT_EULA or, in a terminal, a prompt
+pub fn accept() -> Result<()> {
+    if is_signed() {
+        return Ok(());
+    }
+
+    let accepted = match env::var(ACCEPT_VAR) {
+        Ok(value) => value.eq_ignore_ascii_case("true"),
+        Err(_) if io::stdin().is_terminal() => prompt()?,
+        Err(_) => false,
     };
 
-    file.write_all(b"# Signed by mup\neula=true")?;
+    if accepted {
+        return sign();
+    }
+
+    println!(
+        "the eula was not accepted, run `mup server sign` or set {ACCEPT_VAR}=true once you do"
+    );
 
     Ok(())
 }
 
+pub fn prompt() -> Result<bool> {
+    Ok(Confirm::new()
+        .with_prompt(format!("Do you accept the Minecraft EULA ({EULA_URL})?"))
+        .default(false)
+        .interact()?)
+}
+
+// Everything but the eula line is kept as the server wrote it, an earlier signature is replaced
+fn signed(existing: &str, timestamp: &str) -> String {
+    let signature = format!("{SIGNATURE} on {timestamp}\neula=true");
+
+    let mut lines: Vec<&str> = existing
+        .lines()
+        .filter(|l| !l.starts_with(SIGNATURE))
+        .collect();
+
+    match lines
+        .iter()
+        .position(|l| l.trim_start().starts_with("eula="))
+    {
+        Some(idx) => lines[idx] = &signature,
+        None => lines.push(&signature),
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+
+    contents
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env;
-    use tempfile::tempdir;
+    use crate::testing;
 
     #[test]
     fn test_sign_eula() -> Result<()> {
-        let dir = tempdir()?;
-        let original_dir = env::current_dir()?;
-        env::set_current_dir(&dir)?;
+        testing::in_temp_dir(|| {
+            sign()?;
 
-        sign()?;
+            let content = fs::read_to_string("eula.txt")?;
+            assert!(content.starts_with("# Signed by mup on "));
+            assert!(content.ends_with("\neula=true\n"));
+            assert!(is_signed());
 
-        let content = fs::read_to_string("eula.txt")?;
-        assert_eq!(content, "# Signed by mup\neula=true");
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_signed_keeps_contents() {
+        let vanilla = "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\n#Wed Oct 16 12:00:00 UTC 2024\neula=false\n";
 
-        env::set_current_dir(original_dir)?;
-        dir.close()?;
+        assert_eq!(
+            signed(vanilla, "2024-10-16T12:00:00Z"),
+            "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\n#Wed Oct 16 12:00:00 UTC 2024\n# Signed by mup on 2024-10-16T12:00:00Z\neula=true\n"
+        );
 
-        Ok(())
+        let resigned = signed("# Signed by mup\neula=true", "2024-10-17T00:00:00Z");
+        assert_eq!(
+            resigned,
+            "# Signed by mup on 2024-10-17T00:00:00Z\neula=true\n"
+        );
     }
 }
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::{de::IgnoredAny, Deserialize};
+
+use super::{Imported, ImportedProject};
+
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    active_profile: usize,
+    profiles: Vec<Profile>,
+}
+
+#[derive(Deserialize)]
+struct Profile {
+    name: String,
+    game_version: Option<String>,
+    mod_loader: Option<String>,
+    #[serde(default)]
+    mods: Vec<Mod>,
+}
+
+#[derive(Deserialize)]
+struct Mod {
+    name: String,
+    identifier: Identifier,
+}
+
+#[derive(Deserialize)]
+enum Identifier {
+    ModrinthProject(String),
+    PinnedModrinthProject(String, String),
+    CurseForgeProject(IgnoredAny),
+    PinnedCurseForgeProject(IgnoredAny),
+    GitHubRepository(IgnoredAny),
+    PinnedGitHubRepository(IgnoredAny),
+}
+
+// Reads a profile from ferium's config.json, the active one unless a name is given
+pub fn read(path: &str, profile: Option<&str>) -> Result<Imported> {
+    let config: Config = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+    let profile = match profile {
+        Some(name) => config
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| anyhow!("ferium profile {name} does not exist"))?,
+        None => config
+            .profiles
+            .get(config.active_profile)
+            .ok_or_else(|| anyhow!("{path} has no active ferium profile"))?,
+    };
+
+    let minecraft_version = profile
+        .game_version
+        .clone()
+        .ok_or_else(|| anyhow!("ferium profile {} has no game version", profile.name))?;
+    let loader = match profile.mod_loader.as_deref() {
+        Some("Fabric") => "fabric",
+        Some("Forge") => "forge",
+        Some("NeoForge") => "neoforge",
+        Some(other) => return Err(anyhow!("{other} servers are not supported")),
+        None => return Err(anyhow!("ferium profile {} has no mod loader", profile.name)),
+    };
+
+    let mut projects = vec![];
+    for m in &profile.mods {
+        let (id, version) = match &m.identifier {
+            Identifier::ModrinthProject(id) => (id, "latest"),
+            Identifier::PinnedModrinthProject(id, version) => (id, version.as_str()),
+            Identifier::CurseForgeProject(_) | Identifier::PinnedCurseForgeProject(_) => {
+                warn!("skipping {}, CurseForge is not supported yet", m.name);
+                continue;
+            }
+            Identifier::GitHubRepository(_) | Identifier::PinnedGitHubRepository(_) => {
+                warn!("skipping {}, GitHub releases are not supported", m.name);
+                continue;
+            }
+        };
+
+        projects.push(ImportedProject {
+            provider: "modrinth",
+            id: id.clone(),
+            version: version.to_string(),
+            artifact: None,
+        });
+    }
+
+    Ok(Imported {
+        name: profile.name.clone(),
+        minecraft_version,
+        loader: loader.to_string(),
+        loader_version: None,
+        projects,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "active_profile": 0,
+                "profiles": [{
+                    "name": "server",
+                    "game_version": "1.20.1",
+                    "mod_loader": "Fabric",
+                    "mods": [
+                        {"name": "Sodium", "identifier": {"ModrinthProject": "AANobbMI"}},
+                        {"name": "Lithium", "identifier": {"PinnedModrinthProject": ["gvQqBUqZ", "ZSNsJrPI"]}},
+                        {"name": "JEI", "identifier": {"CurseForgeProject": 238222}},
+                        {"name": "Mod", "identifier": {"GitHubRepository": ["owner", "repo"]}}
+                    ]
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let mods = &config.profiles[0].mods;
+        assert!(matches!(&mods[0].identifier, Identifier::ModrinthProject(id) if id == "AANobbMI"));
+        assert!(
+            matches!(&mods[1].identifier, Identifier::PinnedModrinthProject(_, v) if v == "ZSNsJrPI")
+        );
+        assert!(matches!(
+            mods[2].identifier,
+            Identifier::CurseForgeProject(_)
+        ));
+        assert!(matches!(
+            mods[3].identifier,
+            Identifier::GitHubRepository(_)
+        ));
+    }
+}
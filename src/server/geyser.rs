@@ -0,0 +1,93 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use log::info;
+
+use super::lockfile::Lockfile;
+use crate::plugin::{self, ResolveOptions};
+
+#[derive(Debug, Args)]
+pub struct Geyser {
+    /// Port Bedrock players connect to
+    #[arg(short, long, default_value_t = 19132)]
+    port: u16,
+
+    /// Also install Floodgate, so Bedrock players can join without a Java account
+    #[arg(long, action)]
+    floodgate: bool,
+}
+
+pub fn action(geyser: &Geyser) -> Result<()> {
+    let options = ResolveOptions::default();
+
+    for project in ["geyser", "floodgate"] {
+        if project == "floodgate" && !geyser.floodgate {
+            continue;
+        }
+
+        if Lockfile::init()?.get(project).is_ok() {
+            println!("{project} is already installed");
+            continue;
+        }
+
+        plugin::add(
+            "geysermc",
+            &[project],
+            "latest",
+            true,
+            plugin::Reason::Requested,
+            &options,
+        )?;
+    }
+
+    write_config(geyser)
+}
+
+// Geyser generates its full config on first start, this only sets what it can't guess
+fn write_config(geyser: &Geyser) -> Result<()> {
+    let lockfile = Lockfile::init()?;
+    let entry = lockfile.get("geyser")?;
+
+    let jar = entry.get_file_path(&lockfile.loader);
+    let name = jar
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow!("invalid geyser jar {}", jar.display()))?;
+
+    // Mods keep their configuration in config/, plugins next to their jar
+    let dir = match lockfile.loader.name.as_str() {
+        "fabric" | "neoforge" => PathBuf::from("config").join(name),
+        _ => jar.with_file_name(name),
+    };
+    let path = dir.join("config.yml");
+
+    if path.exists() {
+        println!("{} already exists, leaving it untouched", path.display());
+        return Ok(());
+    }
+
+    let auth_type = if geyser.floodgate {
+        "floodgate"
+    } else {
+        "online"
+    };
+
+    info!("writing {}", path.display());
+
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        &path,
+        format!(
+            "bedrock:\n  address: 0.0.0.0\n  port: {}\nremote:\n  address: auto\n  auth-type: {auth_type}\n",
+            geyser.port
+        ),
+    )?;
+
+    println!(
+        "Geyser will listen on UDP port {}, make sure it is reachable",
+        geyser.port
+    );
+
+    Ok(())
+}
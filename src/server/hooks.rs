@@ -0,0 +1,134 @@
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use super::lockfile::Lockfile;
+
+#[derive(Clone, Copy)]
+pub enum Event {
+    Install,
+    Update,
+    Remove,
+}
+
+/// Commands run around changes to the server's projects, e.g. to warn players before an update.
+/// Commands prefixed with `rcon:` are sent to the running server instead of a shell.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Hooks {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pre_install: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_install: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pre_update: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_update: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pre_remove: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_remove: Vec<String>,
+}
+
+impl Hooks {
+    pub const fn is_empty(&self) -> bool {
+        self.pre_install.is_empty()
+            && self.post_install.is_empty()
+            && self.pre_update.is_empty()
+            && self.post_update.is_empty()
+            && self.pre_remove.is_empty()
+            && self.post_remove.is_empty()
+    }
+
+    fn commands(&self, event: Event) -> (&[String], &[String]) {
+        match event {
+            Event::Install => (&self.pre_install, &self.post_install),
+            Event::Update => (&self.pre_update, &self.post_update),
+            Event::Remove => (&self.pre_remove, &self.post_remove),
+        }
+    }
+}
+
+impl Event {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Install => "install",
+            Self::Update => "update",
+            Self::Remove => "remove",
+        }
+    }
+}
+
+// Runs the pre hook of an event, then the change itself and finally the post hook, which is
+// skipped when the change fails
+pub fn around<T>(
+    event: Event,
+    projects: &[String],
+    change: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let hooks = Lockfile::existing()?.map(|l| l.hooks).unwrap_or_default();
+    let (pre, post) = hooks.commands(event);
+
+    run(&format!("pre-{}", event.name()), pre, projects)?;
+    let result = change()?;
+    run(&format!("post-{}", event.name()), post, projects)?;
+
+    Ok(result)
+}
+
+// Runs each command of a hook in order, stopping at the first one that fails. The projects
+// involved are passed in $MUP_PROJECTS, separated by spaces
+fn run(name: &str, commands: &[String], projects: &[String]) -> Result<()> {
+    for command in commands {
+        println!("running {name} hook: {command}");
+
+        if let Some(rcon) = command.strip_prefix("rcon:") {
+            send(rcon.trim())?;
+            continue;
+        }
+
+        let status = shell(command)
+            .env("MUP_HOOK", name)
+            .env("MUP_PROJECTS", projects.join(" "))
+            .status()
+            .map_err(|e| anyhow!("failed to run {name} hook {command}: {e}"))?;
+
+        if !status.success() {
+            return Err(anyhow!("{name} hook {command} failed with {status}"));
+        }
+    }
+
+    Ok(())
+}
+
+// A server that is not running has nobody to tell, so it does not stop the change
+fn send(command: &str) -> Result<()> {
+    let mut rcon = match super::restart::connect() {
+        Ok(rcon) => rcon,
+        Err(e) => {
+            println!("skipping rcon command, the server is not reachable: {e}");
+            return Ok(());
+        }
+    };
+
+    let response = rcon.command(command)?;
+    info!("rcon responded with {response}");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
@@ -0,0 +1,79 @@
+use std::{fs, io::Cursor, path::Path};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use image::{imageops::FilterType, ImageFormat};
+
+const ICON_PATH: &str = "server-icon.png";
+const ICON_SIZE: u32 = 64;
+
+#[derive(Debug, Args)]
+pub struct Icon {
+    /// PNG or JPEG image to use, images that are not square are cropped to their center
+    image: String,
+}
+
+pub fn action(icon: &Icon) -> Result<()> {
+    let encoded = to_icon(&fs::read(&icon.image)?)
+        .map_err(|e| anyhow!("failed to convert {}: {e}", icon.image))?;
+
+    super::config::snapshot(Path::new(ICON_PATH))?;
+    fs::write(ICON_PATH, encoded)?;
+
+    println!("wrote {ICON_PATH} from {}", icon.image);
+
+    Ok(())
+}
+
+// The client silently shows the default icon for anything but a 64x64 PNG
+fn to_icon(data: &[u8]) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(data)?;
+
+    let side = image.width().min(image.height());
+    let resized = image
+        .crop_imm(
+            (image.width() - side) / 2,
+            (image.height() - side) / 2,
+            side,
+            side,
+        )
+        .resize_exact(ICON_SIZE, ICON_SIZE, FilterType::Lanczos3);
+
+    let mut encoded = Cursor::new(vec![]);
+    resized.write_to(&mut encoded, ImageFormat::Png)?;
+
+    Ok(encoded.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView, Rgb, RgbImage};
+
+    use super::*;
+
+    #[test]
+    fn test_to_icon() {
+        // Red on the left and right edges, which cropping to the center drops
+        let wide = RgbImage::from_fn(300, 200, |x, _| {
+            if (50..250).contains(&x) {
+                Rgb([0, 0, 255])
+            } else {
+                Rgb([255, 0, 0])
+            }
+        });
+
+        let mut jpeg = Cursor::new(vec![]);
+        wide.write_to(&mut jpeg, ImageFormat::Jpeg).unwrap();
+
+        let icon = to_icon(jpeg.get_ref()).unwrap();
+        assert_eq!(image::guess_format(&icon).unwrap(), ImageFormat::Png);
+
+        let decoded = image::load_from_memory(&icon).unwrap();
+        assert_eq!(decoded.dimensions(), (ICON_SIZE, ICON_SIZE));
+
+        let [r, _, b, _] = decoded.get_pixel(0, 32).0;
+        assert!(b > 200 && r < 50);
+
+        assert!(to_icon(b"not an image").is_err());
+    }
+}
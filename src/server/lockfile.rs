@@ -1,64 +1,121 @@
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
-    path::PathBuf,
+    io::Write,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Result};
 use log::{info, warn};
-use serde::{Deserialize, Serialize};
-use versions::Versioning;
+use serde::{Deserialize, Serialize, Serializer};
 
+use super::hooks::Hooks;
 use crate::{loader, plugin};
 
-const LOCKFILE_PATH: &str = "mup.lock.json";
+pub const LOCKFILE_PATH: &str = "mup.lock.json";
+pub const TOML_LOCKFILE_PATH: &str = "mup.lock.toml";
+
+// A server set up with a TOML lockfile keeps using it, every other server uses JSON
+pub fn path() -> &'static str {
+    if Path::new(TOML_LOCKFILE_PATH).exists() {
+        TOML_LOCKFILE_PATH
+    } else {
+        LOCKFILE_PATH
+    }
+}
+
+pub fn exists_in(dir: &Path) -> bool {
+    dir.join(LOCKFILE_PATH).exists() || dir.join(TOML_LOCKFILE_PATH).exists()
+}
+
+pub fn is_lockfile(name: &str) -> bool {
+    name == LOCKFILE_PATH || name == TOML_LOCKFILE_PATH
+}
 
 #[derive(Deserialize, Default, Serialize)]
 pub struct Lockfile {
     pub loader: loader::Loader,
+    #[serde(serialize_with = "sorted_by_name")]
     pub mods: Vec<plugin::Info>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, plugin::Override>,
+    /// Dependencies each project satisfies in place of another, like a Vault replacement
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub provides: BTreeMap<String, Vec<String>>,
+    /// Java executable installed by `mup java install` for this server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub java: Option<String>,
+    /// JVM flags for this server, used by `mup server run` after the ones in the config
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jvm_args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+}
+
+// Entries are written sorted by name, so the order they were installed in does not show up in
+// diffs of the lockfile
+fn sorted_by_name<S: Serializer>(mods: &[plugin::Info], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut sorted: Vec<&plugin::Info> = mods.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+
+    sorted.serialize(serializer)
 }
 
 impl Lockfile {
     pub fn init() -> Result<Self> {
         info!("initializing lockfile");
 
-        if PathBuf::from(LOCKFILE_PATH).exists() {
+        if PathBuf::from(path()).exists() {
             info!("using existing lockfile");
 
-            let current_lockfile = File::open(LOCKFILE_PATH)?;
-
-            return Ok(serde_json::from_reader(&current_lockfile)?);
+            return Self::read();
         }
 
         info!("creating new lockfile");
 
-        File::create(LOCKFILE_PATH)?;
+        File::create(path())?;
 
         Ok(Self {
             loader: loader::Loader::default(),
             mods: vec![],
+            overrides: BTreeMap::new(),
+            provides: BTreeMap::new(),
+            java: None,
+            jvm_args: vec![],
+            hooks: Hooks::default(),
         })
     }
 
-    pub fn with_params(minecraft_version: &str, loader_name: &str) -> Result<Self> {
+    // Reads the lockfile without creating one, for commands that work outside of a server too
+    pub fn existing() -> Result<Option<Self>> {
+        if !PathBuf::from(path()).exists() {
+            return Ok(None);
+        }
+
+        let lockfile = Self::read()?;
+
+        Ok(lockfile.is_initialized().then_some(lockfile))
+    }
+
+    pub fn with_params(minecraft_version: &str, loader_name: &str, snapshot: bool) -> Result<Self> {
         info!("initializing lockfile with Minecraft version {minecraft_version} and loader {loader_name}");
 
-        let mv = Versioning::new(minecraft_version)
-            .ok_or_else(|| anyhow!("invalid minecraft version: {minecraft_version}"))?;
-        if mv.is_complex() {
-            return Err(anyhow!(
-                "minecraft version {} is invalid",
-                minecraft_version
-            ));
-        }
+        let loader = loader::Loader::new(loader_name, minecraft_version, "latest", snapshot);
 
-        let loader = loader::Loader::new(loader_name, minecraft_version, "latest", false);
+        if !loader.has_valid_minecraft_version() {
+            return Err(anyhow!("minecraft version {minecraft_version} is invalid"));
+        }
 
-        File::create(LOCKFILE_PATH)?;
+        File::create(path())?;
 
         let lf = Self {
             loader,
             mods: vec![],
+            overrides: BTreeMap::new(),
+            provides: BTreeMap::new(),
+            java: None,
+            jvm_args: vec![],
+            hooks: Hooks::default(),
         };
 
         lf.save()?;
@@ -66,6 +123,33 @@ impl Lockfile {
         Ok(lf)
     }
 
+    fn read() -> Result<Self> {
+        let path = path();
+
+        Self::parse(path, &fs::read_to_string(path)?)
+    }
+
+    // Reads the contents of a lockfile, in the format its file name says it is in
+    pub fn parse(path: &str, contents: &str) -> Result<Self> {
+        if path == TOML_LOCKFILE_PATH {
+            toml::from_str(contents).map_err(|e| anyhow!("invalid {path}: {e}"))
+        } else {
+            Ok(serde_json::from_str(contents)?)
+        }
+    }
+
+    // Moves the lockfile to TOML, which is easier to review in diffs than JSON
+    pub fn use_toml(&self) -> Result<()> {
+        File::create(TOML_LOCKFILE_PATH)?;
+        self.save()?;
+
+        if Path::new(LOCKFILE_PATH).exists() {
+            fs::remove_file(LOCKFILE_PATH)?;
+        }
+
+        Ok(())
+    }
+
     pub fn get(&self, project_id: &str) -> Result<&plugin::Info> {
         self.mods
             .iter()
@@ -73,7 +157,15 @@ impl Lockfile {
             .ok_or_else(|| anyhow!("key {project_id} not found"))
     }
 
-    pub fn add(&mut self, info: plugin::Info) -> Result<()> {
+    pub fn get_mut(&mut self, project_id: &str) -> Result<&mut plugin::Info> {
+        self.mods
+            .iter_mut()
+            .find(|p| p.name == project_id || p.id == project_id)
+            .ok_or_else(|| anyhow!("key {project_id} not found"))
+    }
+
+    // Callers save once they are done, so several projects can be added in one write
+    pub fn add(&mut self, info: plugin::Info) {
         if let Some(idx) = self
             .mods
             .iter()
@@ -83,13 +175,22 @@ impl Lockfile {
         } else {
             self.mods.push(info);
         }
+    }
 
-        self.save()?;
+    // Removes every entry before saving, so the lockfile is only written once
+    pub fn remove_all(&mut self, slugs: &[String], keep_jarfile: bool) -> Result<()> {
+        for slug in slugs {
+            self.remove_entry(slug, keep_jarfile)?;
+        }
 
-        Ok(())
+        for entry in &mut self.mods {
+            entry.required_by.retain(|p| !slugs.contains(p));
+        }
+
+        self.save()
     }
 
-    pub fn remove(&mut self, slug: &str, keep_jarfile: bool) -> Result<()> {
+    pub fn remove_entry(&mut self, slug: &str, keep_jarfile: bool) -> Result<()> {
         info!("removing {slug} from lockfile");
 
         let entry = self.get(slug)?;
@@ -111,26 +212,170 @@ impl Lockfile {
 
         self.mods.remove(entry_idx);
 
-        self.save()?;
-
         Ok(())
     }
 
+    // Looks up an override for a dependency by either its name or project ID
+    pub fn get_override(&self, dep: &plugin::Dependency) -> Option<&plugin::Override> {
+        self.overrides
+            .get(&dep.name)
+            .or_else(|| self.overrides.get(&dep.id))
+    }
+
+    // Finds an installed project declared to provide a dependency, by the dependency's name or ID
+    pub fn get_provider(&self, dep: &plugin::Dependency) -> Option<&plugin::Info> {
+        self.provides
+            .iter()
+            .filter(|(_, provided)| provided.iter().any(|p| *p == dep.name || *p == dep.id))
+            .find_map(|(project, _)| self.get(project).ok())
+    }
+
     pub fn is_initialized(&self) -> bool {
-        let version = Versioning::new(&self.loader.minecraft_version).unwrap();
+        self.loader.has_valid_minecraft_version() && self.loader.name != "none"
+    }
 
-        !version.is_complex() && self.loader.name != "none"
+    // Downloads the server jar and saves the record of what was installed
+    pub fn fetch_loader(&mut self) -> Result<()> {
+        self.loader.fetch()?;
+        self.save()
     }
 
     pub fn save(&self) -> Result<()> {
         info!("saving transaction to lockfile");
 
-        let mut output = fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(LOCKFILE_PATH)?;
+        let path = path();
+
+        mup::timings::time("lockfile", path, || {
+            let contents = if path == TOML_LOCKFILE_PATH {
+                toml::to_string_pretty(self)?
+            } else {
+                serde_json::to_string_pretty(self)?
+            };
+
+            let mut output = fs::OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .open(path)?;
+
+            output.write_all(contents.as_bytes())?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{lockfile_with, project};
+
+    #[test]
+    fn test_stable_order() -> Result<()> {
+        let installed = lockfile_with(vec![
+            project("sodium", &[], &[]),
+            project("fabric-api", &[], &["sodium", "lithium"]),
+            project("lithium", &["fabric-api", "cloth-config"], &[]),
+        ]);
+        let shuffled = lockfile_with(vec![
+            project("lithium", &["cloth-config", "fabric-api"], &[]),
+            project("fabric-api", &[], &["lithium", "sodium"]),
+            project("sodium", &[], &[]),
+        ]);
+
+        let json = serde_json::to_string_pretty(&installed)?;
+        assert_eq!(json, serde_json::to_string_pretty(&shuffled)?);
+        assert_eq!(toml::to_string(&installed)?, toml::to_string(&shuffled)?);
+
+        let written: Lockfile = serde_json::from_str(&json)?;
+        let names: Vec<&str> = written.mods.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["fabric-api", "lithium", "sodium"]);
+        assert_eq!(written.mods[0].required_by, ["lithium", "sodium"]);
+
+        let dependencies: Vec<&str> = written.mods[1]
+            .dependencies
+            .iter()
+            .flatten()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(dependencies, ["cloth-config", "fabric-api"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_toml_round_trip() -> Result<()> {
+        let populated: plugin::Info = serde_json::from_value(serde_json::json!({
+            "name": "essentialsx",
+            "id": "hXiIvTyT",
+            "version": "2.21.0",
+            "source": "modrinth",
+            "download_url": "https://cdn.modrinth.com/data/hXiIvTyT/versions/2.21.0/download",
+            "dependencies": [
+                { "name": "vault", "required": false },
+                { "name": "essentials-legacy", "required": false, "incompatible": true },
+                { "name": "paperlib", "required": true, "embedded": true },
+            ],
+            "checksum": { "method": "sha512", "hash": "abc123" },
+            "size": 1024,
+            "forced": ["minecraft version"],
+            "artifact": "jars/EssentialsX-*.jar",
+            "location": "plugins",
+            "file_name": "EssentialsX.jar",
+            "dependency": true,
+            "required_by": ["essentialsx-chat"],
+            "pinned": true,
+            "constraint": "^2.21",
+            "channel": "beta",
+            "loader": "spigot",
+            "disabled": true,
+            "groups": ["admin"],
+            "force_client": true,
+        }))?;
+
+        let lockfile = Lockfile {
+            loader: serde_json::from_value(serde_json::json!({
+                "name": "paper",
+                "minecraft_version": "1.21.4",
+                "version": "latest",
+                "snapshot": false,
+                "channel": "experimental",
+                "installer": "1.0.0",
+                "output": "server.jar",
+                "installed": {
+                    "version": "232",
+                    "file": "server.jar",
+                    "url": "https://example.com/paper-1.21.4-232.jar",
+                    "checksum": { "method": "sha512", "hash": "def456" },
+                },
+            }))?,
+            mods: vec![populated, project("vault", &[], &["essentialsx"])],
+            overrides: BTreeMap::from([
+                (
+                    String::from("vault"),
+                    plugin::Override::Replace(String::from("vaultunlocked")),
+                ),
+                (String::from("worldedit"), plugin::Override::Ignore),
+            ]),
+            provides: BTreeMap::from([(
+                String::from("vaultunlocked"),
+                vec![String::from("vault")],
+            )]),
+            java: Some(String::from(".mup/java/bin/java")),
+            jvm_args: vec![String::from("-XX:+UseG1GC")],
+            hooks: serde_json::from_value(serde_json::json!({
+                "pre_install": ["./backup.sh"],
+                "post_update": ["./notify.sh"],
+            }))?,
+        };
+
+        let written = toml::to_string_pretty(&lockfile)?;
+        let read: Lockfile = toml::from_str(&written)?;
 
-        serde_json::to_writer_pretty(&mut output, &self)?;
+        assert_eq!(toml::to_string_pretty(&read)?, written);
+        assert_eq!(
+            serde_json::to_value(&read)?,
+            serde_json::to_value(&lockfile)?
+        );
 
         Ok(())
     }
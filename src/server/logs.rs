@@ -0,0 +1,374 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::info;
+use regex::Regex;
+
+const LOG_DIRS: [&str; 2] = ["logs", "crash-reports"];
+const LATEST_LOG: &str = "logs/latest.log";
+const DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Logs {
+    #[command(subcommand)]
+    command: Option<LogsCommand>,
+
+    /// Keep printing lines as they are written to latest.log
+    #[arg(short, long, action)]
+    follow: bool,
+
+    /// Only print lines matching this regular expression
+    #[arg(short, long)]
+    grep: Option<String>,
+
+    /// Also search rotated logs, printing lines newer than this, e.g. 30m or 2d
+    #[arg(long, value_parser = mup::parse_duration)]
+    since: Option<Duration>,
+}
+
+#[derive(Debug, Subcommand)]
+enum LogsCommand {
+    /// Delete old logs and crash reports, optionally compressing the rest
+    Prune {
+        /// Delete files older than this many days, defaults to the configured retention
+        #[arg(long)]
+        keep_days: Option<u64>,
+
+        /// Gzip uncompressed logs and crash reports that are kept
+        #[arg(long, action)]
+        compress: bool,
+    },
+}
+
+pub fn action(logs: &Logs) -> Result<()> {
+    match &logs.command {
+        Some(LogsCommand::Prune {
+            keep_days,
+            compress,
+        }) => {
+            let retention = &mup::config::get().logs;
+
+            prune(
+                keep_days.unwrap_or(retention.keep_days),
+                *compress || retention.compress,
+            )
+        }
+        None => show(logs),
+    }
+}
+
+fn show(logs: &Logs) -> Result<()> {
+    let pattern = logs
+        .grep
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| anyhow!("invalid --grep pattern: {e}"))?;
+    let matches = |line: &str| pattern.as_ref().is_none_or(|p| p.is_match(line));
+
+    let offset = utc_offset();
+    let now = unix_time(SystemTime::now())?;
+    let cutoff = logs
+        .since
+        .map(|since| i64::try_from(since.as_secs()).map(|since| now - since))
+        .transpose()?;
+
+    let files = match cutoff {
+        Some(cutoff) => log_files(cutoff)?,
+        None => vec![PathBuf::from(LATEST_LOG)],
+    };
+
+    for path in files.iter().filter(|p| p.is_file()) {
+        let modified = unix_time(fs::metadata(path)?.modified()?)? + offset;
+
+        // Lines are stamped with the local time of day, files tell which day it was
+        let day = file_day(path).unwrap_or_else(|| modified.div_euclid(DAY));
+        let last = modified.rem_euclid(DAY);
+        let mut keep = cutoff.is_none();
+
+        for line in read_lines(path)? {
+            if let (Some(cutoff), Some(time)) = (cutoff, line_time(&line)) {
+                // latest.log may have been started the day before it was last written to
+                let day = if path.ends_with("latest.log") && time > last + 60 {
+                    day - 1
+                } else {
+                    day
+                };
+                keep = day * DAY + time >= cutoff + offset;
+            }
+
+            if keep && matches(&line) {
+                println!("{line}");
+            }
+        }
+    }
+
+    if logs.follow {
+        follow(&matches)?;
+    }
+
+    Ok(())
+}
+
+// Rotated logs written to after the cutoff, oldest first, followed by latest.log
+fn log_files(cutoff: i64) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    if Path::new("logs").is_dir() {
+        for entry in fs::read_dir("logs")? {
+            let path = entry?.path();
+            let modified = unix_time(fs::metadata(&path)?.modified()?)?;
+
+            if path.is_file() && file_day(&path).is_some() && modified >= cutoff {
+                files.push(path);
+            }
+        }
+    }
+
+    // Names sort by date, then by the number of the log within that day
+    files.sort_by_key(|p| {
+        let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let number: u32 = name
+            .get(11..)
+            .and_then(|rest| rest.split('.').next())
+            .and_then(|n| n.parse().ok())
+            .unwrap_or_default();
+
+        (name.get(..10).unwrap_or_default().to_string(), number)
+    });
+    files.push(PathBuf::from(LATEST_LOG));
+
+    Ok(files)
+}
+
+fn read_lines(path: &Path) -> Result<Vec<String>> {
+    let file = File::open(path)?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|e| e == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    let mut lines = vec![];
+    let mut reader = BufReader::new(reader);
+    let mut buf = vec![];
+
+    // Logs are not always valid UTF-8, e.g. when a plugin prints raw bytes
+    while reader.read_until(b'\n', &mut buf)? > 0 {
+        lines.push(
+            String::from_utf8_lossy(&buf)
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+        );
+        buf.clear();
+    }
+
+    Ok(lines)
+}
+
+// Polls latest.log for new lines, starting over when the server rotates it
+fn follow(matches: &dyn Fn(&str) -> bool) -> Result<()> {
+    let mut position = fs::metadata(LATEST_LOG).map_or(0, |m| m.len());
+    let mut partial = String::new();
+
+    loop {
+        thread::sleep(Duration::from_millis(500));
+
+        let Ok(mut file) = File::open(LATEST_LOG) else {
+            continue;
+        };
+
+        let len = file.metadata()?.len();
+        if len < position {
+            position = 0;
+            partial.clear();
+        }
+        if len == position {
+            continue;
+        }
+
+        file.seek(SeekFrom::Start(position))?;
+
+        let mut buf = vec![];
+        file.read_to_end(&mut buf)?;
+        position += buf.len() as u64;
+        partial.push_str(&String::from_utf8_lossy(&buf));
+
+        // The last line may still be being written
+        while let Some(end) = partial.find('\n') {
+            let line: String = partial.drain(..=end).collect();
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            if matches(line) {
+                println!("{line}");
+            }
+        }
+    }
+}
+
+// Seconds into the day of lines starting with `[HH:MM:SS`, as vanilla and Paper write them
+fn line_time(line: &str) -> Option<i64> {
+    let time = line.strip_prefix('[')?.get(..8)?;
+    let mut parts = time.split(':').map(str::parse::<i64>);
+
+    let (Some(Ok(h)), Some(Ok(m)), Some(Ok(s)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+
+    Some(h * 3600 + m * 60 + s)
+}
+
+// Days since the epoch of rotated logs, which are named like `2024-01-31-1.log.gz`
+fn file_day(path: &Path) -> Option<i64> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.get(..10)?.split('-').map(str::parse::<i64>);
+
+    let (Some(Ok(y)), Some(Ok(m)), Some(Ok(d))) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+
+    Some(days_from_civil(y, m, d))
+}
+
+// See http://howardhinnant.github.io/date_algorithms.html
+const fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year % 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+fn unix_time(time: SystemTime) -> Result<i64> {
+    Ok(i64::try_from(time.duration_since(UNIX_EPOCH)?.as_secs())?)
+}
+
+// Seconds the local time zone is ahead of UTC, log lines are stamped in local time
+#[cfg(unix)]
+fn utc_offset() -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    // SAFETY: localtime_r only writes to the tm it is given
+    let offset = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        let time = libc::time_t::try_from(now).unwrap_or_default();
+        libc::localtime_r(&raw const time, &raw mut tm);
+        tm.tm_gmtoff
+    };
+
+    // c_long is only 32 bits wide on some targets
+    #[allow(clippy::useless_conversion)]
+    i64::from(offset)
+}
+
+#[cfg(not(unix))]
+const fn utc_offset() -> i64 {
+    0
+}
+
+pub fn prune(keep_days: u64, compress: bool) -> Result<()> {
+    let cutoff = cutoff(SystemTime::now(), keep_days);
+
+    let (mut deleted, mut compressed) = (0, 0);
+
+    for dir in LOG_DIRS {
+        if !Path::new(dir).is_dir() {
+            continue;
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            // The server is still writing to this one
+            if !path.is_file() || path.ends_with("latest.log") {
+                continue;
+            }
+
+            if fs::metadata(&path)?.modified()? < cutoff {
+                info!("deleting {}", path.to_string_lossy());
+                fs::remove_file(&path)?;
+
+                deleted += 1;
+                continue;
+            }
+
+            let uncompressed = path.extension().is_some_and(|e| e == "log" || e == "txt");
+
+            if compress && uncompressed {
+                gzip(&path)?;
+                compressed += 1;
+            }
+        }
+    }
+
+    println!("deleted {deleted} files, compressed {compressed} files");
+
+    Ok(())
+}
+
+fn gzip(path: &Path) -> Result<()> {
+    let mut output = path.as_os_str().to_owned();
+    output.push(".gz");
+
+    info!("compressing {}", path.to_string_lossy());
+
+    let mut encoder = GzEncoder::new(File::create(&output)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+// Logs older than this are deleted. Keeping them for longer than the clock can go back keeps
+// every log
+fn cutoff(now: SystemTime, keep_days: u64) -> SystemTime {
+    keep_days
+        .checked_mul(24 * 60 * 60)
+        .and_then(|secs| now.checked_sub(Duration::from_secs(secs)))
+        .unwrap_or(UNIX_EPOCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff() {
+        let now = UNIX_EPOCH + Duration::from_hours(30 * 24);
+
+        assert_eq!(cutoff(now, 7), UNIX_EPOCH + Duration::from_hours(23 * 24));
+        assert_eq!(cutoff(now, u64::MAX / 2), UNIX_EPOCH);
+        assert_eq!(cutoff(now, u64::MAX), UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(
+            line_time("[12:34:56] [Server thread/INFO]: Done"),
+            Some(45296)
+        );
+        assert_eq!(line_time("[12:34:56 INFO]: Done"), Some(45296));
+        assert_eq!(line_time("\tat java.lang.Thread.run"), None);
+
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(file_day(Path::new("logs/2024-02-29-3.log.gz")), Some(19782));
+        assert_eq!(file_day(Path::new("logs/latest.log")), None);
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::Deserialize;
+
+use super::{Imported, ImportedProject};
+
+#[derive(Deserialize)]
+struct ServerToml {
+    name: String,
+    mc_version: String,
+    jar: Downloadable,
+    #[serde(default)]
+    plugins: Vec<Downloadable>,
+    #[serde(default)]
+    mods: Vec<Downloadable>,
+}
+
+// mcman tags every source with a type, only the fields mup can use are read
+#[derive(Deserialize)]
+struct Downloadable {
+    #[serde(rename = "type")]
+    kind: String,
+    id: Option<String>,
+    version: Option<String>,
+    url: Option<String>,
+    job: Option<String>,
+    build: Option<String>,
+    artifact: Option<String>,
+    loader: Option<String>,
+}
+
+// Reads the server, plugins and mods of an mcman server.toml
+pub fn read(path: &str) -> Result<Imported> {
+    let server: ServerToml = toml::from_str(&std::fs::read_to_string(path)?)?;
+
+    let loader = match server.jar.kind.as_str() {
+        "buildtools" => "spigot",
+        kind @ ("vanilla" | "paper" | "folia" | "purpur" | "fabric" | "forge" | "neoforge"
+        | "waterfall" | "bungeecord" | "velocity") => kind,
+        other => return Err(anyhow!("{other} servers are not supported")),
+    };
+
+    let projects = server
+        .plugins
+        .iter()
+        .chain(&server.mods)
+        .filter_map(|d| {
+            let project = to_project(d);
+            if project.is_none() {
+                warn!("skipping {} source, it is not supported", d.kind);
+            }
+
+            project
+        })
+        .collect();
+
+    Ok(Imported {
+        name: server.name,
+        minecraft_version: server.mc_version,
+        loader: loader.to_string(),
+        loader_version: server.jar.loader.filter(|v| v != "latest"),
+        projects,
+    })
+}
+
+fn to_project(source: &Downloadable) -> Option<ImportedProject> {
+    let version = source
+        .version
+        .clone()
+        .unwrap_or_else(|| String::from("latest"));
+
+    let (provider, id, version) = match source.kind.as_str() {
+        "modrinth" | "mr" => ("modrinth", source.id.clone()?, version),
+        "hangar" => ("hangar", source.id.clone()?, version),
+        "url" => ("url", source.url.clone()?, String::from("latest")),
+        "jenkins" => {
+            // Jobs inside folders are written as paths, e.g. EssentialsX/2.x
+            let job = source.job.as_ref()?.split('/').collect::<Vec<_>>();
+            let url = format!(
+                "{}/job/{}/",
+                source.url.as_ref()?.trim_end_matches('/'),
+                job.join("/job/")
+            );
+
+            (
+                "jenkins",
+                url,
+                source
+                    .build
+                    .clone()
+                    .unwrap_or_else(|| String::from("latest")),
+            )
+        }
+        _ => return None,
+    };
+
+    Some(ImportedProject {
+        provider,
+        id,
+        version,
+        // mcman picks the first artifact by default, which is what mup does without a filter
+        artifact: source.artifact.clone().filter(|a| a != "first"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_project() {
+        let server: ServerToml = toml::from_str(
+            r#"
+            name = "test"
+            mc_version = "1.20.4"
+
+            [jar]
+            type = "paper"
+
+            [[plugins]]
+            type = "modrinth"
+            id = "luckperms"
+            version = "latest"
+
+            [[plugins]]
+            type = "jenkins"
+            url = "https://ci.ender.zone/"
+            job = "EssentialsX/2.x"
+            artifact = "EssentialsX-*.jar"
+
+            [[plugins]]
+            type = "spigot"
+            id = "1234"
+            "#,
+        )
+        .unwrap();
+
+        let modrinth = to_project(&server.plugins[0]).unwrap();
+        assert_eq!(modrinth.provider, "modrinth");
+        assert_eq!(modrinth.id, "luckperms");
+
+        let jenkins = to_project(&server.plugins[1]).unwrap();
+        assert_eq!(jenkins.id, "https://ci.ender.zone/job/EssentialsX/job/2.x/");
+        assert_eq!(jenkins.version, "latest");
+        assert_eq!(jenkins.artifact.as_deref(), Some("EssentialsX-*.jar"));
+
+        assert!(to_project(&server.plugins[2]).is_none());
+    }
+}
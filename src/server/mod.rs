@@ -1,51 +1,425 @@
+use std::io::{self, IsTerminal};
+
 use anyhow::{anyhow, Result};
 use clap::Subcommand;
+use log::{info, warn};
 
-mod eula;
+mod adopt;
+mod config;
+mod crashes;
+pub mod eula;
+mod ferium;
+mod geyser;
+pub mod hooks;
+mod icon;
 pub mod lockfile;
+mod logs;
+mod mcman;
+mod mrpack;
+mod packwiz;
+pub mod properties;
+mod reset;
+mod resourcepack;
+mod restart;
+pub mod run;
+mod status;
+mod template;
+mod tune;
+pub mod upgrade;
+mod verify;
+mod wizard;
+pub mod world;
 
 use lockfile::Lockfile;
 
-use crate::{loader, plugin};
+use crate::{history, loader, plugin};
 
 #[derive(Debug, Subcommand)]
 pub enum Server {
     /// Initialize a server in the current directory
     Init {
-        /// Minecraft version of the server
-        #[arg(short, long, required = true)]
-        minecraft_version: String,
+        /// Minecraft version of the server, detected from the server jar with --adopt
+        #[arg(short, long, conflicts_with = "interactive")]
+        minecraft_version: Option<String>,
+
+        /// Which loader to use, detected from the server jar with --adopt
+        #[arg(short, long, conflicts_with = "interactive", value_parser = loader::Loader::parse_name)]
+        loader: Option<String>,
+
+        /// Pick the loader, Minecraft version, memory and starter plugins from prompts, the
+        /// default when no flags are given in a terminal
+        #[arg(short, long, action, conflicts_with = "adopt")]
+        interactive: bool,
+
+        /// Write a lockfile for the server already in this directory, identifying its jar and
+        /// installed projects without downloading anything
+        #[arg(long, action, conflicts_with_all = ["snapshot", "channel", "output"])]
+        adopt: bool,
 
-        /// Which loader to use
-        #[arg(short, long, required = true, value_parser = loader::Loader::parse_name)]
-        loader: String,
+        /// Git repository or .zip/.tar.gz archive holding a lockfile and config files to start
+        /// from, everything in the lockfile is installed afterwards
+        #[arg(long, conflicts_with_all = ["minecraft_version", "loader", "interactive", "adopt", "snapshot", "channel", "output"])]
+        template: Option<String>,
 
-        /// Do not sign the eula automatically
+        /// Do not ask for confirmation before downloading, for --template
+        #[arg(short, long, action, requires = "template")]
+        yes: bool,
+
+        /// Leave the eula unsigned instead of asking to accept it
         #[arg(long, action)]
         no_sign: bool,
+
+        /// Allow snapshot versions for vanilla, fabric and neoforge
+        #[arg(long, action)]
+        snapshot: bool,
+
+        /// Release channel to take loader builds from, e.g. recommended for forge
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Directory or file to write the server jar to, e.g. server.jar
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Format of the lockfile, toml reads better in reviews of servers kept in git
+        #[arg(long, default_value = "json", value_parser = ["json", "toml"])]
+        lockfile_format: String,
+
+        #[command(flatten)]
+        settings: Box<properties::Settings>,
     },
 
     /// Sign the eula.txt
     Sign,
 
     /// Install all mods from the current lockfile
-    Install,
+    Install {
+        /// Do not ask for confirmation before downloading
+        #[arg(short, long, action)]
+        yes: bool,
+
+        /// Skip projects in this group, can be given multiple times
+        #[arg(long)]
+        without_group: Vec<String>,
+
+        /// Only download the exact files recorded in the lockfile, failing if a project has no
+        /// checksum, the loader version is not pinned or no server jar is recorded. The lockfile
+        /// is left unchanged
+        #[arg(long, action)]
+        frozen: bool,
+    },
+
+    /// Create a server from a Modrinth modpack, a packwiz pack, a ferium profile or an mcman
+    /// server.toml
+    Import {
+        /// Path to the .mrpack, packwiz pack.toml or the directory holding it, ferium
+        /// config.json or mcman server.toml
+        path: String,
+
+        /// Format of the file, detected from its name by default
+        #[arg(long, value_parser = ["mrpack", "packwiz", "ferium", "mcman"])]
+        from: Option<String>,
+
+        /// Ferium profile to import, defaults to the active one
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Leave the eula unsigned instead of asking to accept it
+        #[arg(long, action)]
+        no_sign: bool,
+
+        /// Do not ask for confirmation before downloading
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+
+    /// Export the server so it can be shared or imported elsewhere
+    Export {
+        /// Format to export to
+        #[arg(long, default_value = "mrpack", value_parser = ["mrpack", "packwiz"])]
+        format: String,
+
+        /// Where to write to, defaults to the name and version of the pack for mrpack and the
+        /// current directory for packwiz
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Name of the pack, defaults to the name of the current directory
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Version of the pack
+        #[arg(long, default_value = "1.0.0")]
+        pack_version: String,
+    },
+
+    /// Install Geyser, and optionally Floodgate, to let Bedrock players join
+    Geyser(geyser::Geyser),
+
+    /// Delete worlds or plugins and rebuild them from the lockfile
+    Reset(reset::Reset),
+
+    /// Move the server to another Minecraft version, along with its loader and projects
+    Upgrade(upgrade::Upgrade),
+
+    /// Launch the server, Ctrl-C stops it gracefully
+    Run(run::Run),
+
+    /// Warn players, then stop the server and have `mup server run` start it again
+    Restart(restart::Restart),
+
+    /// Set the icon shown in the server list, resizing the image to 64x64
+    Icon(icon::Icon),
+
+    /// Serve a resource pack to players, setting its URL and SHA-1 in server.properties
+    Resourcepack(resourcepack::Resourcepack),
+
+    /// Ping a server and print its MOTD, version and player count
+    Status(status::Status),
+
+    /// Find which project most likely caused a crash from the crash reports
+    Crashes(crashes::Crashes),
+
+    /// Print, search and follow the server logs, or prune old ones
+    Logs(logs::Logs),
+
+    /// Apply a preset of tuned values to the Paper, Spigot and Bukkit configs
+    Tune(tune::Tune),
+
+    /// Check every installed jar against the lockfile, reporting missing, modified and unknown
+    /// files
+    Verify(verify::Verify),
+
+    /// Manage worlds
+    #[command(subcommand)]
+    World(world::World),
+
+    /// Manage server configuration files
+    #[command(subcommand)]
+    Config(config::Config),
 }
 
 pub fn action(server: &Server) -> Result<()> {
     match server {
+        // Settings are written once the server is set up, so a template's own server.properties
+        // is overridden rather than the other way around
+        Server::Init {
+            lockfile_format,
+            settings,
+            ..
+        } => {
+            start(server)?;
+
+            if lockfile_format == "toml" {
+                Lockfile::existing()?
+                    .ok_or_else(|| anyhow!("failed to read lockfile"))?
+                    .use_toml()?;
+            }
+
+            settings.apply()
+        }
+        Server::Sign => eula::sign(),
+        Server::Install {
+            yes,
+            without_group,
+            frozen,
+        } => install(*yes, without_group, *frozen),
+        Server::Import {
+            path,
+            from,
+            profile,
+            no_sign,
+            yes,
+        } => match from.as_deref().unwrap_or_else(|| import_format(path)) {
+            "packwiz" => packwiz::import(path, *no_sign, *yes),
+            "ferium" => import(ferium::read(path, profile.as_deref())?, *no_sign, *yes),
+            "mcman" => import(mcman::read(path)?, *no_sign, *yes),
+            _ => mrpack::import(path, *no_sign, *yes),
+        },
+        Server::Export {
+            format,
+            output,
+            name,
+            pack_version,
+        } => match format.as_str() {
+            "packwiz" => packwiz::export(output.as_deref(), name.as_deref(), pack_version),
+            _ => mrpack::export(output.as_deref(), name.as_deref(), pack_version),
+        },
+        Server::Geyser(g) => geyser::action(g),
+        Server::Reset(r) => reset::action(r),
+        Server::Upgrade(u) => history::record(|| upgrade::action(u)),
+        Server::Run(r) => run::action(r),
+        Server::Restart(r) => restart::action(r),
+        Server::Icon(i) => icon::action(i),
+        Server::Resourcepack(r) => resourcepack::action(r),
+        Server::Status(s) => status::action(s),
+        Server::Crashes(c) => crashes::action(c),
+        Server::Logs(l) => logs::action(l),
+        Server::Tune(t) => tune::action(t),
+        Server::Verify(v) => verify::action(v),
+        Server::World(w) => world::action(w),
+        Server::Config(c) => config::action(c),
+    }
+}
+
+// Sets up the server in the current directory in whichever way the flags ask for
+fn start(server: &Server) -> Result<()> {
+    match server {
+        Server::Init {
+            template: Some(url),
+            yes,
+            ..
+        } => template::init(url, *yes),
         Server::Init {
             minecraft_version,
             loader,
+            adopt: true,
+            ..
+        } => adopt::adopt(minecraft_version.as_deref(), loader.as_deref()),
+        Server::Init {
+            minecraft_version: Some(minecraft_version),
+            loader: Some(loader),
             no_sign,
-        } => init(minecraft_version, loader, *no_sign),
-        Server::Sign => eula::sign(),
-        Server::Install => install(),
+            snapshot,
+            channel,
+            output,
+            ..
+        } => init(
+            minecraft_version,
+            loader,
+            *no_sign,
+            *snapshot,
+            channel.as_deref(),
+            output.as_deref(),
+        ),
+        Server::Init {
+            minecraft_version: None,
+            loader: None,
+            interactive,
+            ..
+        } if *interactive || io::stdin().is_terminal() => wizard::wizard(),
+        _ => Err(anyhow!(
+            "pass --minecraft-version and --loader, --interactive, --adopt or --template"
+        )),
+    }
+}
+
+// A server described by another tool, whose projects are installed through mup's providers
+struct Imported {
+    name: String,
+    minecraft_version: String,
+    loader: String,
+    loader_version: Option<String>,
+    projects: Vec<ImportedProject>,
+}
+
+struct ImportedProject {
+    provider: &'static str,
+    id: String,
+    version: String,
+    artifact: Option<String>,
+}
+
+fn import_format(path: &str) -> &'static str {
+    if packwiz::is_pack(path) {
+        "packwiz"
+    } else if path.ends_with("server.toml") {
+        "mcman"
+    } else if std::path::Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+    {
+        "ferium"
+    } else {
+        "mrpack"
+    }
+}
+
+fn import(imported: Imported, no_sign: bool, yes: bool) -> Result<()> {
+    if Lockfile::existing()?.is_some() {
+        return Err(anyhow!("a server is already initialized in this directory"));
+    }
+
+    info!("importing {}", imported.name);
+    println!("{} projects to install", imported.projects.len());
+
+    if !yes && !mup::confirm("Proceed?")? {
+        return Ok(());
+    }
+
+    let mut lockfile = Lockfile::with_params(&imported.minecraft_version, &imported.loader, false)?;
+    if let Some(version) = imported.loader_version {
+        lockfile.loader.version = version;
+        lockfile.save()?;
+    }
+
+    lockfile.fetch_loader()?;
+
+    // One project failing should not stop the rest, they are reported together at the end
+    let mut failed = vec![];
+    for project in &imported.projects {
+        // Dependencies of earlier projects may already be installed
+        let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+        if lockfile.get(&project.id).is_ok() {
+            continue;
+        }
+
+        let options = plugin::ResolveOptions {
+            artifact: project.artifact.clone(),
+            ..Default::default()
+        };
+
+        if let Err(e) = plugin::add(
+            project.provider,
+            &[&project.id],
+            &project.version,
+            false,
+            plugin::Reason::Requested,
+            &options,
+        ) {
+            warn!("failed to import {}: {e}", project.id);
+            failed.push(project.id.as_str());
+        }
+    }
+
+    if !no_sign && !lockfile.loader.is_bedrock() {
+        eula::accept()?;
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow!("failed to import {}", failed.join(", ")));
+    }
+
+    println!(
+        "imported {} with {} projects",
+        imported.name,
+        imported.projects.len()
+    );
+
+    Ok(())
+}
+
+// Name of an exported pack, the server directory's unless one is given
+fn pack_name(name: Option<&str>) -> Result<String> {
+    if let Some(name) = name {
+        return Ok(name.to_string());
     }
+
+    Ok(std::env::current_dir()?.file_name().map_or_else(
+        || String::from("server"),
+        |n| n.to_string_lossy().into_owned(),
+    ))
 }
 
-fn init(minecraft_version: &str, loader: &str, no_sign: bool) -> Result<()> {
-    let lf = Lockfile::with_params(minecraft_version, loader)?;
+fn init(
+    minecraft_version: &str,
+    loader: &str,
+    no_sign: bool,
+    snapshot: bool,
+    channel: Option<&str>,
+    output: Option<&str>,
+) -> Result<()> {
+    let mut lf = Lockfile::with_params(minecraft_version, loader, snapshot)?;
 
     if !lf.is_initialized() {
         return Err(anyhow!(
@@ -53,28 +427,110 @@ fn init(minecraft_version: &str, loader: &str, no_sign: bool) -> Result<()> {
         ));
     }
 
-    lf.loader.fetch()?;
+    lf.loader.channel = channel.map(String::from);
+    lf.loader.output = output.map(String::from);
+
+    lf.loader.pin()?;
+    lf.fetch_loader()?;
 
-    if !no_sign {
-        eula::sign()?;
+    // Bedrock has no eula.txt to sign
+    if !no_sign && !lf.loader.is_bedrock() {
+        eula::accept()?;
     }
 
     Ok(())
 }
 
-fn install() -> Result<()> {
-    let lf = Lockfile::init()?;
+fn install(yes: bool, without_groups: &[String], frozen: bool) -> Result<()> {
+    let mut lf = Lockfile::init()?;
     if !lf.is_initialized() {
         return Err(anyhow!("failed to read lockfile"));
     }
 
-    lf.loader.fetch()?;
+    let wanted = |p: &&plugin::Info| !p.groups.iter().any(|g| without_groups.contains(g));
+    let entries: Vec<_> = lf.mods.iter().filter(wanted).collect();
+
+    if frozen {
+        check_frozen(&lf, &entries)?;
+    }
+
+    let confirmed = if frozen {
+        plugin::confirm_recorded_downloads(&entries, yes)?
+    } else {
+        plugin::confirm_downloads(&entries, yes)?
+    };
+    if !confirmed {
+        return Ok(());
+    }
+
+    let names: Vec<String> = entries.iter().map(|p| p.name.clone()).collect();
+    hooks::around(hooks::Event::Install, &names, || {
+        install_server(&mut lf, frozen)?;
+
+        for entry in lf.mods.iter().filter(wanted) {
+            plugin::download_plugin(&lf, entry)?;
+        }
+
+        if !lf.loader.is_bedrock() {
+            eula::accept()?;
+        }
+
+        Ok(())
+    })
+}
+
+// The server jar is only downloaded again when it is missing or has changed since it was fetched.
+// Frozen installs download the recorded jar and leave the lockfile as it is
+fn install_server(lockfile: &mut Lockfile, frozen: bool) -> Result<()> {
+    if lockfile.loader.is_installed()? {
+        info!("the server jar is already installed");
+        return Ok(());
+    }
+
+    if let Some(installed) = &lockfile.loader.installed {
+        println!(
+            "{} is missing or modified, downloading it again",
+            installed.file
+        );
+    }
 
-    for entry in &lf.mods {
-        plugin::download_plugin(&lf, entry)?;
+    if frozen {
+        return lockfile.loader.fetch_installed();
     }
 
-    eula::sign()?;
+    lockfile.fetch_loader()
+}
+
+// A frozen install must give the same files on every machine, so nothing may be left for the
+// network to decide
+fn check_frozen(lockfile: &Lockfile, entries: &[&plugin::Info]) -> Result<()> {
+    if !lockfile.loader.is_pinned() {
+        return Err(anyhow!(
+            "the loader is not pinned, set the {} {} version in the lockfile instead of latest",
+            lockfile.loader.name,
+            lockfile.loader.minecraft_version
+        ));
+    }
+
+    if lockfile.loader.installed.is_none() {
+        return Err(anyhow!(
+            "no server jar is recorded in the lockfile, run `mup server install` once without \
+             --frozen to record it"
+        ));
+    }
+
+    let unchecked: Vec<&str> = entries
+        .iter()
+        .filter(|p| p.checksum.is_none())
+        .map(|p| p.name.as_str())
+        .collect();
+
+    if !unchecked.is_empty() {
+        return Err(anyhow!(
+            "no checksum recorded for {}, frozen installs need one for every project",
+            unchecked.join(", ")
+        ));
+    }
 
     Ok(())
 }
@@ -0,0 +1,329 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Component, Path},
+};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::Sha512;
+
+use super::{eula, lockfile::Lockfile};
+use crate::plugin::{self, modrinth};
+
+const INDEX_PATH: &str = "modrinth.index.json";
+
+// Hosts Modrinth allows modpack files to be downloaded from
+const ALLOWED_HOSTS: [&str; 4] = [
+    "https://cdn.modrinth.com/",
+    "https://github.com/",
+    "https://raw.githubusercontent.com/",
+    "https://gitlab.com/",
+];
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Index {
+    format_version: u32,
+    game: String,
+    version_id: String,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    files: Vec<PackFile>,
+    dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PackFile {
+    path: String,
+    hashes: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    env: Option<Env>,
+    downloads: Vec<String>,
+    file_size: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Env {
+    client: String,
+    server: String,
+}
+
+pub fn import(path: &str, no_sign: bool, yes: bool) -> Result<()> {
+    if Lockfile::existing()?.is_some() {
+        return Err(anyhow!("a server is already initialized in this directory"));
+    }
+
+    let archive = fs::read(path)?;
+    let index = mup::zip::read(&archive, INDEX_PATH)?
+        .ok_or_else(|| anyhow!("{path} is not a Modrinth modpack"))?;
+    let index: Index = serde_json::from_slice(&index)?;
+
+    if index.game != "minecraft" {
+        return Err(anyhow!("modpacks for {} are not supported", index.game));
+    }
+
+    let minecraft_version = index
+        .dependencies
+        .get("minecraft")
+        .ok_or_else(|| anyhow!("modpack does not declare a Minecraft version"))?;
+    let (loader, loader_version) = pack_loader(&index.dependencies)?;
+
+    info!("importing {} {}", index.name, index.version_id);
+
+    let mut lockfile = Lockfile::with_params(minecraft_version, loader, false)?;
+    lockfile.loader.version = loader_version.to_string();
+
+    // Client-only files such as shaders and minimaps have no place on a server
+    let files: Vec<&PackFile> = index
+        .files
+        .iter()
+        .filter(|f| f.env.as_ref().is_none_or(|env| env.server != "unsupported"))
+        .collect();
+
+    let project_ids: Vec<&str> = files
+        .iter()
+        .filter_map(|f| cdn_ids(f.downloads.first()?))
+        .map(|(project, _)| project)
+        .collect();
+    let slugs = modrinth::project_slugs(&project_ids)?;
+
+    for file in &files {
+        lockfile.add(to_info(file, &slugs)?);
+    }
+
+    lockfile.save()?;
+
+    if !plugin::confirm_downloads(&lockfile.mods, yes)? {
+        return Ok(());
+    }
+
+    lockfile.fetch_loader()?;
+
+    for entry in &lockfile.mods {
+        plugin::download_plugin(&lockfile, entry)?;
+    }
+
+    // Server overrides are applied last so they win over the shared ones
+    mup::zip::extract_dir(&archive, "overrides", Path::new("."))?;
+    mup::zip::extract_dir(&archive, "server-overrides", Path::new("."))?;
+
+    if !no_sign {
+        eula::accept()?;
+    }
+
+    println!(
+        "imported {} {} with {} files",
+        index.name,
+        index.version_id,
+        files.len()
+    );
+
+    Ok(())
+}
+
+pub fn export(output: Option<&str>, name: Option<&str>, version: &str) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let loader = &lockfile.loader;
+
+    let mut dependencies =
+        BTreeMap::from([(String::from("minecraft"), loader.minecraft_version.clone())]);
+
+    let dependency = match loader.name.as_str() {
+        "fabric" => Some("fabric-loader"),
+        "forge" => Some("forge"),
+        "neoforge" => Some("neoforge"),
+        "vanilla" => None,
+        other => return Err(anyhow!("modpacks cannot contain {other} servers")),
+    };
+    if let Some(dependency) = dependency {
+        dependencies.insert(dependency.to_string(), loader.resolve_version()?);
+    }
+
+    let name = super::pack_name(name)?;
+
+    let mut files = vec![];
+    let mut overrides = vec![];
+
+    for info in lockfile.mods.iter().filter(|i| !i.disabled) {
+        let path = info.get_file_path(loader);
+        let pack_path = path.to_string_lossy().replace('\\', "/");
+
+        if !path.exists() {
+            return Err(anyhow!(
+                "{} is missing, run mup server install first",
+                path.display()
+            ));
+        }
+
+        // Local jars have nowhere to be downloaded from, so they ship inside the pack
+        if info.source == "local" {
+            overrides.push((format!("overrides/{pack_path}"), fs::read(&path)?));
+            continue;
+        }
+
+        if !ALLOWED_HOSTS
+            .iter()
+            .any(|host| info.download_url.starts_with(host))
+        {
+            warn!(
+                "{} is downloaded from {}, Modrinth does not accept it in published modpacks",
+                info.name, info.download_url
+            );
+        }
+
+        // Plugins mean nothing to a client, mods may or may not be needed there
+        let client = if info.force_client {
+            "required"
+        } else if loader.mod_location() == "plugins" {
+            "unsupported"
+        } else {
+            "optional"
+        };
+
+        files.push(PackFile {
+            path: pack_path,
+            hashes: BTreeMap::from([
+                (String::from("sha1"), mup::hash_file::<Sha1>(&path)?),
+                (String::from("sha512"), mup::hash_file::<Sha512>(&path)?),
+            ]),
+            env: Some(Env {
+                client: client.to_string(),
+                server: String::from("required"),
+            }),
+            downloads: vec![info.download_url.clone()],
+            file_size: fs::metadata(&path)?.len(),
+        });
+    }
+
+    let count = files.len() + overrides.len();
+    let index = Index {
+        format_version: 1,
+        game: String::from("minecraft"),
+        version_id: version.to_string(),
+        name: name.clone(),
+        summary: None,
+        files,
+        dependencies,
+    };
+
+    let mut entries = vec![(INDEX_PATH.to_string(), serde_json::to_vec_pretty(&index)?)];
+    entries.extend(overrides);
+
+    let output = output.map_or_else(|| format!("{name}-{version}.mrpack"), String::from);
+    fs::write(&output, mup::zip::write(&entries)?)?;
+
+    println!("exported {count} files to {output}");
+
+    Ok(())
+}
+
+// Modpacks name loaders after their Maven artifacts
+fn pack_loader(dependencies: &BTreeMap<String, String>) -> Result<(&str, &str)> {
+    for (dependency, loader) in [
+        ("fabric-loader", "fabric"),
+        ("forge", "forge"),
+        ("neoforge", "neoforge"),
+    ] {
+        if let Some(version) = dependencies.get(dependency) {
+            return Ok((loader, version));
+        }
+    }
+
+    if let Some(other) = dependencies.keys().find(|k| *k != "minecraft") {
+        return Err(anyhow!("modpacks for {other} are not supported"));
+    }
+
+    Ok(("vanilla", "latest"))
+}
+
+// Files hosted on Modrinth are tracked as Modrinth projects so they can be updated later
+fn to_info(file: &PackFile, slugs: &HashMap<String, String>) -> Result<plugin::Info> {
+    let relative = Path::new(&file.path);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(anyhow!(
+            "refusing to install {} outside of the server",
+            file.path
+        ));
+    }
+
+    let (location, file_name) = file
+        .path
+        .rsplit_once('/')
+        .ok_or_else(|| anyhow!("{} is not inside a directory", file.path))?;
+    let url = file
+        .downloads
+        .first()
+        .ok_or_else(|| anyhow!("{} has no download", file.path))?;
+    let hash = file
+        .hashes
+        .get("sha512")
+        .ok_or_else(|| anyhow!("{} has no sha512 hash", file.path))?;
+
+    let project = cdn_ids(url).and_then(|(project, version)| {
+        slugs
+            .get(project)
+            .map(|slug| (slug.clone(), project.to_string(), version.to_string()))
+    });
+
+    let (name, id, version, source) = match project {
+        Some((slug, project, version)) => (slug, project, version, "modrinth"),
+        None => (
+            file_name.trim_end_matches(".jar").to_lowercase(),
+            url.clone(),
+            String::from("direct"),
+            "url",
+        ),
+    };
+
+    Ok(plugin::Info {
+        name,
+        id,
+        version,
+        source: source.to_string(),
+        download_url: url.clone(),
+        checksum: Some(plugin::Checksum {
+            method: String::from("sha512"),
+            hash: hash.clone(),
+        }),
+        size: Some(file.file_size),
+        location: Some(location.to_string()),
+        file_name: Some(file_name.to_string()),
+        ..Default::default()
+    })
+}
+
+// Project and version ID of a file on Modrinth's CDN
+fn cdn_ids(url: &str) -> Option<(&str, &str)> {
+    let mut parts = url
+        .strip_prefix("https://cdn.modrinth.com/data/")?
+        .split('/');
+
+    let project = parts.next()?;
+    if parts.next()? != "versions" {
+        return None;
+    }
+
+    Some((project, parts.next()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cdn_ids() {
+        assert_eq!(
+            cdn_ids("https://cdn.modrinth.com/data/AANobbMI/versions/4DdLmtyz/sodium.jar"),
+            Some(("AANobbMI", "4DdLmtyz"))
+        );
+        assert_eq!(cdn_ids("https://github.com/a/b/releases/x.jar"), None);
+    }
+}
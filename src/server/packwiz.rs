@@ -0,0 +1,421 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+
+use super::{eula, lockfile::Lockfile};
+use crate::plugin::{self, modrinth};
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Pack {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(rename = "pack-format")]
+    format: String,
+    index: IndexRef,
+    versions: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct IndexRef {
+    file: String,
+    hash_format: String,
+    hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Index {
+    hash_format: String,
+    #[serde(default)]
+    files: Vec<IndexFile>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct IndexFile {
+    file: String,
+    hash: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    metafile: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Metafile {
+    name: String,
+    filename: String,
+    #[serde(default = "default_side")]
+    side: String,
+    download: Download,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    update: Option<Update>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Download {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    hash_format: String,
+    hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Update {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modrinth: Option<ModrinthUpdate>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ModrinthUpdate {
+    mod_id: String,
+    version: String,
+}
+
+fn default_side() -> String {
+    String::from("both")
+}
+
+// Either a pack.toml or a directory holding one
+pub fn is_pack(path: &str) -> bool {
+    let path = Path::new(path);
+
+    path.ends_with("pack.toml") || path.join("pack.toml").is_file()
+}
+
+pub fn import(path: &str, no_sign: bool, yes: bool) -> Result<()> {
+    if Lockfile::existing()?.is_some() {
+        return Err(anyhow!("a server is already initialized in this directory"));
+    }
+
+    let pack_path = if Path::new(path).is_dir() {
+        Path::new(path).join("pack.toml")
+    } else {
+        PathBuf::from(path)
+    };
+    let root = pack_path.parent().unwrap_or_else(|| Path::new("."));
+
+    let pack: Pack = toml::from_str(&fs::read_to_string(&pack_path)?)?;
+    let minecraft_version = pack
+        .versions
+        .get("minecraft")
+        .ok_or_else(|| anyhow!("pack does not declare a Minecraft version"))?;
+    let (loader, loader_version) = pack_loader(&pack.versions)?;
+
+    // Paths in the index are relative to the index itself
+    let index_path = root.join(&pack.index.file);
+    let index_root = index_path.parent().unwrap_or(root);
+    let index: Index = toml::from_str(&fs::read_to_string(&index_path)?)?;
+
+    info!("importing {}", pack.name);
+
+    let mut metafiles = vec![];
+    let mut others = vec![];
+
+    for file in &index.files {
+        if !is_safe(&file.file) {
+            return Err(anyhow!(
+                "refusing to import {} outside of the pack",
+                file.file
+            ));
+        }
+
+        if !(file.metafile || file.file.ends_with(".pw.toml")) {
+            others.push(&file.file);
+            continue;
+        }
+
+        let meta: Metafile = toml::from_str(&fs::read_to_string(index_root.join(&file.file))?)?;
+
+        if meta.side == "client" {
+            info!("skipping client-only {}", meta.name);
+            continue;
+        }
+
+        if meta.download.url.is_none() {
+            warn!(
+                "skipping {}, it can only be downloaded through CurseForge",
+                meta.name
+            );
+            continue;
+        }
+
+        let location = Path::new(&file.file)
+            .parent()
+            .map_or_else(String::new, |p| p.to_string_lossy().replace('\\', "/"));
+        metafiles.push((location, meta));
+    }
+
+    let project_ids: Vec<&str> = metafiles
+        .iter()
+        .filter_map(|(_, meta)| Some(meta.update.as_ref()?.modrinth.as_ref()?.mod_id.as_str()))
+        .collect();
+    let slugs = modrinth::project_slugs(&project_ids)?;
+
+    let mut lockfile = Lockfile::with_params(minecraft_version, loader, false)?;
+    lockfile.loader.version = loader_version.to_string();
+
+    for (location, meta) in &metafiles {
+        lockfile.add(to_info(location, meta, &slugs));
+    }
+
+    lockfile.save()?;
+
+    if !plugin::confirm_downloads(&lockfile.mods, yes)? {
+        return Ok(());
+    }
+
+    lockfile.fetch_loader()?;
+
+    for entry in &lockfile.mods {
+        plugin::download_plugin(&lockfile, entry)?;
+    }
+
+    // Hashes packwiz uses but mup cannot verify are replaced once the files are downloaded
+    let mods = std::mem::take(&mut lockfile.mods);
+    for entry in mods {
+        let entry = plugin::record_checksum(&lockfile, entry)?;
+        lockfile.mods.push(entry);
+    }
+    lockfile.save()?;
+
+    // Configs and other files are part of the pack too, unless it already is this directory
+    let in_place = fs::canonicalize(index_root)? == std::env::current_dir()?;
+    if !in_place {
+        for file in others {
+            let dest = Path::new(file);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::copy(index_root.join(file), dest)?;
+        }
+    }
+
+    if !no_sign {
+        eula::accept()?;
+    }
+
+    println!(
+        "imported {} with {} projects",
+        pack.name,
+        lockfile.mods.len()
+    );
+
+    Ok(())
+}
+
+pub fn export(output: Option<&str>, name: Option<&str>, version: &str) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let loader = &lockfile.loader;
+
+    let mut versions =
+        BTreeMap::from([(String::from("minecraft"), loader.minecraft_version.clone())]);
+
+    match loader.name.as_str() {
+        "fabric" | "forge" | "neoforge" => {
+            versions.insert(loader.name.clone(), loader.resolve_version()?);
+        }
+        "vanilla" => {}
+        other => return Err(anyhow!("packwiz packs cannot contain {other} servers")),
+    }
+
+    let output = Path::new(output.unwrap_or("."));
+    let mut files = vec![];
+
+    for info in lockfile.mods.iter().filter(|i| !i.disabled) {
+        let path = info.get_file_path(loader);
+        let pack_path = path.to_string_lossy().replace('\\', "/");
+
+        // Local jars have nowhere to be downloaded from, so they ship inside the pack
+        if info.source == "local" {
+            let dest = output.join(&path);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if fs::canonicalize(&path)? != fs::canonicalize(&dest).unwrap_or_default() {
+                fs::copy(&path, &dest)?;
+            }
+
+            files.push(IndexFile {
+                hash: mup::hash_file::<Sha256>(&dest)?,
+                file: pack_path,
+                metafile: false,
+            });
+            continue;
+        }
+
+        let checksum = match &info.checksum {
+            Some(checksum) => (checksum.method.clone(), checksum.hash.clone()),
+            None => (String::from("sha512"), mup::hash_file::<Sha512>(&path)?),
+        };
+
+        let filename = path
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("{} has no file name", info.name))?;
+        let location = pack_path.trim_end_matches(&filename).trim_end_matches('/');
+
+        let meta = Metafile {
+            name: info.name.clone(),
+            filename,
+            side: String::from(if loader.mod_location() == "plugins" {
+                "server"
+            } else {
+                "both"
+            }),
+            download: Download {
+                url: Some(info.download_url.clone()),
+                hash_format: checksum.0,
+                hash: checksum.1,
+                mode: None,
+            },
+            update: (info.source == "modrinth").then(|| Update {
+                modrinth: Some(ModrinthUpdate {
+                    mod_id: info.id.clone(),
+                    version: info.version.clone(),
+                }),
+            }),
+        };
+
+        let meta_path = format!("{location}/{}.pw.toml", info.name);
+        let dest = output.join(&meta_path);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&dest, toml::to_string(&meta)?)?;
+
+        files.push(IndexFile {
+            hash: mup::hash_file::<Sha256>(&dest)?,
+            file: meta_path,
+            metafile: true,
+        });
+    }
+
+    let count = files.len();
+    let index = Index {
+        hash_format: String::from("sha256"),
+        files,
+    };
+    let index_path = output.join("index.toml");
+    fs::write(&index_path, toml::to_string(&index)?)?;
+
+    let pack = Pack {
+        name: super::pack_name(name)?,
+        version: Some(version.to_string()),
+        format: String::from("packwiz:1.1.0"),
+        index: IndexRef {
+            file: String::from("index.toml"),
+            hash_format: String::from("sha256"),
+            hash: mup::hash_file::<Sha256>(&index_path)?,
+        },
+        versions,
+    };
+    fs::write(output.join("pack.toml"), toml::to_string(&pack)?)?;
+
+    println!("exported {count} files to {}", output.display());
+
+    Ok(())
+}
+
+// packwiz names loaders the same way mup does, except for quilt which mup does not support
+fn pack_loader(versions: &BTreeMap<String, String>) -> Result<(&str, &str)> {
+    for loader in ["fabric", "forge", "neoforge"] {
+        if let Some(version) = versions.get(loader) {
+            return Ok((loader, version));
+        }
+    }
+
+    if let Some(other) = versions.keys().find(|k| *k != "minecraft") {
+        return Err(anyhow!("packs for {other} are not supported"));
+    }
+
+    Ok(("vanilla", "latest"))
+}
+
+fn is_safe(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+}
+
+// Projects with Modrinth update info are tracked as Modrinth projects so they can be updated
+fn to_info(location: &str, meta: &Metafile, slugs: &HashMap<String, String>) -> plugin::Info {
+    let url = meta.download.url.clone().unwrap_or_default();
+
+    let project = meta
+        .update
+        .as_ref()
+        .and_then(|u| u.modrinth.as_ref())
+        .and_then(|m| Some((slugs.get(&m.mod_id)?.clone(), m)));
+
+    let (name, id, version, source) = match project {
+        Some((slug, m)) => (slug, m.mod_id.clone(), m.version.clone(), "modrinth"),
+        None => (
+            meta.filename.trim_end_matches(".jar").to_lowercase(),
+            url.clone(),
+            String::from("direct"),
+            "url",
+        ),
+    };
+
+    let checksum = matches!(meta.download.hash_format.as_str(), "sha256" | "sha512").then(|| {
+        plugin::Checksum {
+            method: meta.download.hash_format.clone(),
+            hash: meta.download.hash.clone(),
+        }
+    });
+
+    plugin::Info {
+        name,
+        id,
+        version,
+        source: source.to_string(),
+        download_url: url,
+        checksum,
+        location: Some(location.to_string()),
+        file_name: Some(meta.filename.clone()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_loader() {
+        let versions = BTreeMap::from([
+            (String::from("minecraft"), String::from("1.21.1")),
+            (String::from("neoforge"), String::from("21.1.77")),
+        ]);
+        assert_eq!(pack_loader(&versions).unwrap(), ("neoforge", "21.1.77"));
+
+        let versions = BTreeMap::from([(String::from("minecraft"), String::from("1.21.1"))]);
+        assert_eq!(pack_loader(&versions).unwrap(), ("vanilla", "latest"));
+
+        let versions = BTreeMap::from([
+            (String::from("minecraft"), String::from("1.21.1")),
+            (String::from("quilt"), String::from("0.26.0")),
+        ]);
+        assert!(pack_loader(&versions).is_err());
+    }
+
+    #[test]
+    fn test_is_safe() {
+        assert!(is_safe("mods/sodium.pw.toml"));
+        assert!(!is_safe("../outside.toml"));
+        assert!(!is_safe("/etc/passwd"));
+    }
+}
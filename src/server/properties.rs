@@ -0,0 +1,304 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+pub const PROPERTIES_PATH: &str = "server.properties";
+
+const BOOLEAN_KEYS: [&str; 28] = [
+    "accepts-transfers",
+    "allow-flight",
+    "allow-nether",
+    "broadcast-console-to-ops",
+    "broadcast-rcon-to-ops",
+    "enable-code-of-conduct",
+    "enable-command-block",
+    "enable-jmx-monitoring",
+    "enable-query",
+    "enable-rcon",
+    "enable-status",
+    "enforce-secure-profile",
+    "enforce-whitelist",
+    "force-gamemode",
+    "generate-structures",
+    "hardcore",
+    "hide-online-players",
+    "log-ips",
+    "online-mode",
+    "prevent-proxy-connections",
+    "pvp",
+    "require-resource-pack",
+    "spawn-animals",
+    "spawn-monsters",
+    "spawn-npcs",
+    "sync-chunk-writes",
+    "use-native-transport",
+    "white-list",
+];
+
+// Inclusive bounds the server accepts
+const INTEGER_KEYS: [(&str, i64, i64); 15] = [
+    ("entity-broadcast-range-percentage", 10, 1000),
+    ("function-permission-level", 1, 4),
+    ("max-players", 0, i32::MAX as i64),
+    ("max-tick-time", -1, i64::MAX),
+    ("max-world-size", 1, 29_999_984),
+    ("network-compression-threshold", -1, i32::MAX as i64),
+    ("op-permission-level", 0, 4),
+    ("pause-when-empty-seconds", 0, i32::MAX as i64),
+    ("player-idle-timeout", 0, i32::MAX as i64),
+    ("query.port", 1, 65535),
+    ("rate-limit", 0, i32::MAX as i64),
+    ("rcon.port", 1, 65535),
+    ("server-port", 1, 65535),
+    ("simulation-distance", 3, 32),
+    ("view-distance", 3, 32),
+];
+
+const CHOICE_KEYS: [(&str, &[&str]); 2] = [
+    ("difficulty", &["peaceful", "easy", "normal", "hard"]),
+    (
+        "gamemode",
+        &["survival", "creative", "adventure", "spectator"],
+    ),
+];
+
+// Keys with free-form values
+const TEXT_KEYS: [&str; 16] = [
+    "bug-report-link",
+    "generator-settings",
+    "initial-disabled-packs",
+    "initial-enabled-packs",
+    "level-name",
+    "level-seed",
+    "level-type",
+    "motd",
+    "rcon.password",
+    "region-file-compression",
+    "resource-pack",
+    "resource-pack-id",
+    "resource-pack-prompt",
+    "resource-pack-sha1",
+    "server-ip",
+    "text-filtering-config",
+];
+
+// Whether vanilla knows about a key, mods and forks are free to add their own
+pub fn is_known(key: &str) -> bool {
+    BOOLEAN_KEYS.contains(&key)
+        || TEXT_KEYS.contains(&key)
+        || INTEGER_KEYS.iter().any(|(k, _, _)| *k == key)
+        || CHOICE_KEYS.iter().any(|(k, _)| *k == key)
+}
+
+pub fn validate(key: &str, value: &str) -> Result<()> {
+    if BOOLEAN_KEYS.contains(&key) && !matches!(value, "true" | "false") {
+        return Err(anyhow!("{key} must be true or false"));
+    }
+
+    if let Some((_, min, max)) = INTEGER_KEYS.iter().find(|(k, _, _)| *k == key) {
+        let valid = value
+            .parse::<i64>()
+            .is_ok_and(|v| (*min..=*max).contains(&v));
+        if !valid {
+            return Err(anyhow!("{key} must be a number from {min} to {max}"));
+        }
+    }
+
+    if let Some((_, choices)) = CHOICE_KEYS.iter().find(|(k, _)| *k == key) {
+        if !choices.contains(&value) {
+            return Err(anyhow!("{key} must be one of {}", choices.join(", ")));
+        }
+    }
+
+    Ok(())
+}
+
+// Checks a value for a key as it is parsed from the command line
+fn valid(key: &'static str) -> impl Fn(&str) -> Result<String> + Clone {
+    move |value| validate(key, value).map(|()| value.to_string())
+}
+
+// Settings that can be given to `mup server init` instead of editing server.properties after the
+// first start
+#[derive(Debug, Args)]
+pub struct Settings {
+    /// Port the server listens on
+    #[arg(long, value_parser = valid("server-port"))]
+    port: Option<String>,
+
+    /// Message shown in the server list
+    #[arg(long)]
+    motd: Option<String>,
+
+    /// How many players can be online at once
+    #[arg(long, value_parser = valid("max-players"))]
+    max_players: Option<String>,
+
+    /// One of peaceful, easy, normal or hard
+    #[arg(long, value_parser = valid("difficulty"))]
+    difficulty: Option<String>,
+
+    /// One of survival, creative, adventure or spectator
+    #[arg(long, value_parser = valid("gamemode"))]
+    gamemode: Option<String>,
+
+    /// View distance in chunks, from 3 to 32
+    #[arg(long, value_parser = valid("view-distance"))]
+    view_distance: Option<String>,
+
+    /// Simulation distance in chunks, from 3 to 32
+    #[arg(long, value_parser = valid("simulation-distance"))]
+    simulation_distance: Option<String>,
+
+    /// Seed of the world generated on the first start
+    #[arg(long)]
+    seed: Option<String>,
+
+    /// Only let whitelisted players join
+    #[arg(long, action)]
+    whitelist: bool,
+
+    /// Start the world in hardcore mode
+    #[arg(long, action)]
+    hardcore: bool,
+
+    /// Do not authenticate players with Mojang, e.g. behind a proxy
+    #[arg(long, action)]
+    offline: bool,
+}
+
+impl Settings {
+    fn pairs(&self) -> Vec<(&'static str, &str)> {
+        let values = [
+            ("server-port", self.port.as_deref()),
+            ("motd", self.motd.as_deref()),
+            ("max-players", self.max_players.as_deref()),
+            ("difficulty", self.difficulty.as_deref()),
+            ("gamemode", self.gamemode.as_deref()),
+            ("view-distance", self.view_distance.as_deref()),
+            ("simulation-distance", self.simulation_distance.as_deref()),
+            ("level-seed", self.seed.as_deref()),
+            ("white-list", self.whitelist.then_some("true")),
+            ("enforce-whitelist", self.whitelist.then_some("true")),
+            ("hardcore", self.hardcore.then_some("true")),
+            ("online-mode", self.offline.then_some("false")),
+        ];
+
+        values
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect()
+    }
+
+    // Writes the given settings over whatever server.properties already holds, leaving it
+    // untouched when there are none
+    pub fn apply(&self) -> Result<()> {
+        let pairs = self.pairs();
+        if pairs.is_empty() {
+            return Ok(());
+        }
+
+        let mut properties = Properties::load()?;
+        for (key, value) in &pairs {
+            properties.set(key, value);
+            println!("set {key}={value}");
+        }
+
+        properties.save()
+    }
+}
+
+// A server.properties file, kept line by line so comments survive a round trip
+#[derive(Default)]
+pub struct Properties {
+    lines: Vec<String>,
+}
+
+impl Properties {
+    pub fn load() -> Result<Self> {
+        let path = Path::new(PROPERTIES_PATH);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        Self {
+            lines: contents.lines().map(String::from).collect(),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .filter(|l| !l.trim_start().starts_with('#'))
+            .filter_map(|l| l.split_once('='))
+            .find(|(k, _)| k.trim() == key)
+            .map(|(_, v)| v.trim())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        let line = format!("{key}={value}");
+
+        let existing = self.lines.iter_mut().find(|l| {
+            !l.trim_start().starts_with('#')
+                && l.split_once('=').is_some_and(|(k, _)| k.trim() == key)
+        });
+
+        match existing {
+            Some(l) => *l = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Path::new(PROPERTIES_PATH);
+
+        super::config::snapshot(path)?;
+
+        let mut contents = self.lines.join("\n");
+        contents.push('\n');
+
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    pub fn level_name(&self) -> &str {
+        self.get("level-name").unwrap_or("world")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("server-port", "25565").is_ok());
+        assert!(validate("server-port", "70000").is_err());
+        assert!(validate("view-distance", "far").is_err());
+        assert!(validate("pvp", "yes").is_err());
+        assert!(validate("difficulty", "hard").is_ok());
+        assert!(validate("gamemode", "hardcore").is_err());
+        assert!(validate("motd", "A Minecraft Server").is_ok());
+        assert!(validate("some-mod-key", "anything").is_ok());
+    }
+
+    #[test]
+    fn test_set_keeps_comments() {
+        let mut properties = Properties::parse("#Minecraft server properties\npvp=true\n");
+        properties.set("pvp", "false");
+        properties.set("motd", "hello");
+
+        assert_eq!(
+            properties.lines,
+            ["#Minecraft server properties", "pvp=false", "motd=hello"]
+        );
+        assert_eq!(properties.get("motd"), Some("hello"));
+    }
+}
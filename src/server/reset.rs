@@ -0,0 +1,134 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use log::info;
+
+use super::lockfile::Lockfile;
+use crate::{network, plugin, workspace};
+
+// Files mup keeps its own state in, which a full reset must not lose along with the server
+const MUP_FILES: [&str; 3] = [".mup", workspace::WORKSPACE_PATH, network::NETWORK_PATH];
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Args)]
+pub struct Reset {
+    /// Delete the worlds so they are regenerated on the next start
+    #[arg(long, action)]
+    world: bool,
+
+    /// Delete all plugin jarfiles and reinstall them from the lockfile
+    #[arg(long, action)]
+    plugins: bool,
+
+    /// Delete everything except the lockfile and mup's own files, then reinstall the server
+    #[arg(long, action)]
+    full: bool,
+
+    /// Do not ask for confirmation
+    #[arg(short, long, action)]
+    yes: bool,
+}
+
+pub fn action(reset: &Reset) -> Result<()> {
+    if !(reset.world || reset.plugins || reset.full) {
+        return Err(anyhow!(
+            "specify at least one of --world, --plugins or --full"
+        ));
+    }
+
+    if reset.full {
+        return reset_full(reset.yes);
+    }
+
+    if reset.world {
+        reset_worlds(reset.yes)?;
+    }
+
+    if reset.plugins {
+        reset_plugins(reset.yes)?;
+    }
+
+    Ok(())
+}
+
+fn reset_worlds(yes: bool) -> Result<()> {
+    super::world::action(&super::world::World::Reset { name: None, yes })
+}
+
+fn reset_plugins(yes: bool) -> Result<()> {
+    let lockfile = Lockfile::init()?;
+    if !lockfile.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    // Disabled projects are reinstalled too, so their jars are cleared along with the rest
+    let locations: Vec<String> = lockfile
+        .loader
+        .mod_locations()
+        .into_iter()
+        .flat_map(|l| [l.to_string(), format!("{l}-disabled")])
+        .collect();
+
+    println!(
+        "this will delete every jarfile in {} and reinstall {} projects from the lockfile",
+        locations.join(", "),
+        lockfile.mods.len()
+    );
+
+    if !yes && !mup::confirm("Reset plugins?")? {
+        return Ok(());
+    }
+
+    for location in locations.iter().filter(|l| Path::new(l).is_dir()) {
+        for entry in fs::read_dir(location)? {
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|e| e == "jar") {
+                info!("deleting {}", path.to_string_lossy());
+                fs::remove_file(path)?;
+            }
+        }
+    }
+
+    for entry in &lockfile.mods {
+        plugin::download_plugin(&lockfile, entry)?;
+    }
+
+    Ok(())
+}
+
+fn reset_full(yes: bool) -> Result<()> {
+    let current = std::env::current_dir()?;
+
+    println!(
+        "this will delete everything in {} except the lockfile and mup's own files, then reinstall the server",
+        current.display()
+    );
+
+    if !yes && !mup::confirm("Reset the entire server?")? {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(".")? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+
+        if super::lockfile::is_lockfile(name) || MUP_FILES.contains(&name) {
+            continue;
+        }
+
+        info!("deleting {name}");
+
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+    }
+
+    super::install(true, &[], false)
+}
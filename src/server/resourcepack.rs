@@ -0,0 +1,57 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use sha1::Sha1;
+
+use super::{lockfile::Lockfile, properties::Properties};
+use crate::plugin::modrinth;
+
+#[derive(Debug, Args)]
+pub struct Resourcepack {
+    /// URL of the resource pack, or the ID or slug of a Modrinth resource pack
+    pack: String,
+
+    /// Kick players who decline the resource pack
+    #[arg(long, action)]
+    require: bool,
+
+    /// Message shown to players when they are asked to download the resource pack
+    #[arg(long)]
+    prompt: Option<String>,
+}
+
+pub fn action(pack: &Resourcepack) -> Result<()> {
+    let (url, sha1) = if pack.pack.starts_with("https://") || pack.pack.starts_with("http://") {
+        (pack.pack.clone(), hash_url(&pack.pack)?)
+    } else {
+        let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+        let file = modrinth::resource_pack(&pack.pack, &lockfile.loader.minecraft_version)?;
+
+        (file.url, file.hashes.sha1)
+    };
+
+    let mut properties = Properties::load()?;
+    properties.set("resource-pack", &url);
+    properties.set("resource-pack-sha1", &sha1);
+    properties.set("require-resource-pack", &pack.require.to_string());
+    if let Some(prompt) = &pack.prompt {
+        properties.set("resource-pack-prompt", prompt);
+    }
+    properties.save()?;
+
+    println!("set resource pack to {url} with SHA-1 {sha1}");
+
+    Ok(())
+}
+
+// Clients reject packs whose hash does not match, so it is computed from the file players will get
+fn hash_url(url: &str) -> Result<String> {
+    let path = std::env::temp_dir().join(format!("mup-resourcepack-{}.zip", std::process::id()));
+
+    mup::download(url, &path)?;
+    let hash = mup::hash_file::<Sha1>(&path);
+    fs::remove_file(&path)?;
+
+    hash
+}
@@ -0,0 +1,129 @@
+use std::{fs, path::Path, thread, time::Duration};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use log::warn;
+
+use super::{properties::Properties, run};
+
+// Points in the countdown at which players are warned
+const WARNINGS: [u64; 12] = [1800, 900, 600, 300, 60, 30, 10, 5, 4, 3, 2, 1];
+const DEFAULT_RCON_PORT: &str = "25575";
+
+#[derive(Debug, Args)]
+pub struct Restart {
+    /// How long to warn players for before restarting, e.g. 10m or 1h30m
+    #[arg(long = "in", value_parser = mup::parse_duration, default_value = "0s")]
+    delay: Duration,
+
+    /// Message broadcast with each warning, {time} is replaced by the time left
+    #[arg(long, default_value = "Server restarting in {time}")]
+    message: String,
+}
+
+pub fn action(restart: &Restart) -> Result<()> {
+    // Stopping a server nothing will start again is not a restart
+    if !Path::new(run::PID_FILE).exists() {
+        return Err(anyhow!(
+            "the server is not running under `mup server run`, it would not be started again"
+        ));
+    }
+
+    let mut rcon = connect()?;
+
+    let total = restart.delay.as_secs();
+    for (wait, left) in schedule(total) {
+        thread::sleep(Duration::from_secs(wait));
+
+        let message = restart.message.replace("{time}", &format_time(left));
+        println!("{message}");
+        rcon.command(&format!("say {message}"))?;
+    }
+
+    thread::sleep(Duration::from_secs(total.min(WARNINGS[WARNINGS.len() - 1])));
+
+    println!("saving the world");
+    rcon.command("save-all flush")?;
+
+    fs::write(run::RESTART_FILE, "")?;
+
+    // The server may close the connection before it answers
+    if let Err(e) = rcon.command("stop") {
+        warn!("no response to stop: {e}");
+    }
+
+    println!("server is restarting");
+
+    Ok(())
+}
+
+pub fn connect() -> Result<mup::rcon::Client> {
+    let properties = Properties::load()?;
+
+    if properties.get("enable-rcon") != Some("true") {
+        return Err(anyhow!(
+            "rcon is disabled, enable it with `mup server config set enable-rcon true` and set rcon.password"
+        ));
+    }
+
+    let password = properties
+        .get("rcon.password")
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| anyhow!("rcon.password is not set in server.properties"))?;
+
+    let host = properties
+        .get("server-ip")
+        .filter(|ip| !ip.is_empty())
+        .unwrap_or("127.0.0.1");
+    let port = properties.get("rcon.port").unwrap_or(DEFAULT_RCON_PORT);
+
+    mup::rcon::Client::connect(&format!("{host}:{port}"), password)
+}
+
+// Returns how long to wait before each warning, and the time left it announces. The first
+// warning is given right away, the last one a second before the restart
+fn schedule(total: u64) -> Vec<(u64, u64)> {
+    if total == 0 {
+        return vec![];
+    }
+
+    let mut steps = vec![(0, total)];
+    let mut left = total;
+
+    for mark in WARNINGS.into_iter().filter(|m| *m < total) {
+        steps.push((left - mark, mark));
+        left = mark;
+    }
+
+    steps
+}
+
+fn format_time(secs: u64) -> String {
+    let plural = |n: u64, unit: &str| format!("{n} {unit}{}", if n == 1 { "" } else { "s" });
+
+    [
+        (secs / 3600, "hour"),
+        (secs % 3600 / 60, "minute"),
+        (secs % 60, "second"),
+    ]
+    .into_iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, unit)| plural(n, unit))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule() {
+        assert_eq!(schedule(0), vec![]);
+        assert_eq!(schedule(3), vec![(0, 3), (1, 2), (1, 1)]);
+        assert_eq!(schedule(90)[..4], [(0, 90), (30, 60), (30, 30), (20, 10)]);
+
+        assert_eq!(format_time(90), "1 minute 30 seconds");
+        assert_eq!(format_time(3600), "1 hour");
+    }
+}
@@ -0,0 +1,416 @@
+#![allow(clippy::case_sensitive_file_extension_comparisons)]
+
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use log::{info, warn};
+
+use super::lockfile::Lockfile;
+use crate::loader::Loader;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// A server that stays up this long before crashing starts a new streak of restarts
+const STABLE_AFTER: Duration = Duration::from_mins(10);
+const MAX_BACKOFF: Duration = Duration::from_mins(5);
+
+// Written while the server is supervised, so `mup server restart` knows it will come back up
+pub const PID_FILE: &str = ".mup/run.pid";
+// Left by `mup server restart` before it stops the server
+pub const RESTART_FILE: &str = ".mup/restart";
+
+// Stdin of the running server, replaced whenever it is started again
+type Console = Arc<Mutex<Option<ChildStdin>>>;
+
+struct Exit {
+    status: ExitStatus,
+    /// Whether the server was asked to stop with Ctrl-C
+    stopped: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct Run {
+    /// Java executable to launch the server with, overrides the config
+    #[arg(long)]
+    java: Option<String>,
+
+    /// Flag passed to the JVM after the ones in the config and lockfile, can be given multiple
+    /// times
+    #[arg(short = 'J', long = "jvm-arg", allow_hyphen_values = true)]
+    jvm_args: Vec<String>,
+
+    /// Server jar to launch, the one downloaded for the lockfile's loader by default
+    #[arg(long)]
+    jar: Option<String>,
+
+    /// Start the server again when it exits with an error, waiting longer after each crash
+    #[arg(long, action)]
+    restart_on_crash: bool,
+
+    /// Give up after this many restarts in a row
+    #[arg(long, default_value_t = 5, requires = "restart_on_crash")]
+    max_restarts: u32,
+
+    /// URL to post a message to when the server crashes, such as a Discord or Slack webhook
+    #[arg(long, requires = "restart_on_crash")]
+    webhook: Option<String>,
+
+    /// Start the server even if Java looks too old for its Minecraft version
+    #[arg(long, action)]
+    skip_java_check: bool,
+}
+
+pub fn action(run: &Run) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let args = command(run, &lockfile)?;
+
+    if !run.skip_java_check && !lockfile.loader.is_bedrock() {
+        crate::java::check(&args[0], &lockfile.loader)
+            .map_err(|e| anyhow!("{e}, pass --skip-java-check to start it anyway"))?;
+    }
+
+    let console = Console::default();
+    forward_stdin(Arc::clone(&console));
+    watch_interrupts();
+
+    fs::create_dir_all(".mup")?;
+    fs::write(PID_FILE, std::process::id().to_string())?;
+
+    let result = supervise(run, &args, &console);
+    fs::remove_file(PID_FILE)?;
+
+    result
+}
+
+fn supervise(run: &Run, args: &[String], console: &Console) -> Result<()> {
+    let mut restarts = 0;
+
+    loop {
+        let started = Instant::now();
+        let exit = launch(args, console)?;
+
+        let requested = Path::new(RESTART_FILE).exists();
+        if requested {
+            fs::remove_file(RESTART_FILE)?;
+        }
+
+        if requested && !exit.stopped {
+            println!("restarting the server as requested");
+            restarts = 0;
+            continue;
+        }
+
+        if exit.status.success() || exit.stopped {
+            return Ok(());
+        }
+
+        if !run.restart_on_crash {
+            return Err(anyhow!("server exited with {}", exit.status));
+        }
+
+        if started.elapsed() >= STABLE_AFTER {
+            restarts = 0;
+        }
+
+        if restarts >= run.max_restarts {
+            notify(
+                run,
+                &format!(
+                    "server crashed with {}, giving up after {restarts} restarts",
+                    exit.status
+                ),
+            );
+            return Err(anyhow!(
+                "server kept crashing, last exit was {}",
+                exit.status
+            ));
+        }
+
+        let delay = backoff(restarts);
+        restarts += 1;
+
+        notify(
+            run,
+            &format!(
+                "server crashed with {}, restarting in {}s ({restarts}/{})",
+                exit.status,
+                delay.as_secs(),
+                run.max_restarts
+            ),
+        );
+
+        if !sleep_unless_interrupted(delay) {
+            println!("not restarting the server");
+            return Ok(());
+        }
+    }
+}
+
+fn backoff(restarts: u32) -> Duration {
+    let secs = 5u64.saturating_mul(1 << restarts.min(16));
+
+    Duration::from_secs(secs).min(MAX_BACKOFF)
+}
+
+fn sleep_unless_interrupted(duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+
+    while Instant::now() < until {
+        if INTERRUPTED.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    true
+}
+
+// Webhook failures are only logged, they should never keep the server down
+fn notify(run: &Run, message: &str) {
+    println!("{message}");
+
+    let Some(url) = &run.webhook else {
+        return;
+    };
+
+    // Discord reads `content` and Slack reads `text`, sending both covers the common cases
+    let body = serde_json::json!({ "content": message, "text": message });
+    if let Err(e) = mup::post_json(url, &body) {
+        warn!("failed to notify {url}: {e}");
+    }
+}
+
+// Builds the command once so it can be reused when the server is started again
+fn command(run: &Run, lockfile: &Lockfile) -> Result<Vec<String>> {
+    let loader = &lockfile.loader;
+    let dir = server_dir(loader);
+
+    // Bedrock ships a native binary that loads its libraries from the working directory
+    if loader.is_bedrock() {
+        let binary = dir.join(if cfg!(windows) {
+            "bedrock_server.exe"
+        } else {
+            "bedrock_server"
+        });
+
+        return Ok(vec![binary.to_string_lossy().into_owned()]);
+    }
+
+    let config = &mup::config::get().run;
+
+    let mut args = vec![crate::java::executable(run.java.as_deref())];
+    args.extend(config.jvm_args.iter().cloned());
+    args.extend(lockfile.jvm_args.iter().cloned());
+    args.extend(run.jvm_args.iter().cloned());
+
+    if let Some(jar) = &run.jar {
+        args.extend([String::from("-jar"), jar.clone()]);
+    } else if let Some(args_file) = installer_args(loader) {
+        // Modern Forge and NeoForge installs are launched through argument files, like run.sh does
+        if Path::new("user_jvm_args.txt").exists() {
+            args.push(String::from("@user_jvm_args.txt"));
+        }
+        args.push(format!("@{}", args_file.to_string_lossy()));
+    } else {
+        let jar = find_jar(loader, &dir)?;
+        args.extend([String::from("-jar"), jar.to_string_lossy().into_owned()]);
+    }
+
+    args.push(String::from("nogui"));
+
+    Ok(args)
+}
+
+fn server_dir(loader: &Loader) -> PathBuf {
+    match loader.output.as_deref() {
+        Some(output) if Path::new(output).is_dir() || output.ends_with('/') => {
+            PathBuf::from(output)
+        }
+        Some(output) => Path::new(output)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+        None => PathBuf::from("."),
+    }
+}
+
+// Forge and NeoForge only download an installer, running it leaves an argument file behind
+fn installer_args(loader: &Loader) -> Option<PathBuf> {
+    let dir = match loader.name.as_str() {
+        "forge" => "libraries/net/minecraftforge/forge",
+        "neoforge" => "libraries/net/neoforged/neoforge",
+        _ => return None,
+    };
+    let file = if cfg!(windows) {
+        "win_args.txt"
+    } else {
+        "unix_args.txt"
+    };
+
+    newest(fs::read_dir(dir).ok()?.filter_map(|e| {
+        let path = e.ok()?.path().join(file);
+        path.exists().then_some(path)
+    }))
+}
+
+// Jars are named after their loader, e.g. paper-1.21.4-100.jar, the newest one is picked when
+// several builds were downloaded
+fn find_jar(loader: &Loader, dir: &Path) -> Result<PathBuf> {
+    if let Some(output) = loader.output.as_deref().filter(|o| o.ends_with(".jar")) {
+        return Ok(PathBuf::from(output));
+    }
+
+    if matches!(loader.name.as_str(), "forge" | "neoforge") {
+        return Err(anyhow!(
+            "run the {} installer with --installServer first",
+            loader.name
+        ));
+    }
+
+    let prefix = format!("{}-", loader.name);
+    let jars = fs::read_dir(dir)?.filter_map(|e| {
+        let path = e.ok()?.path();
+        let name = path.file_name()?.to_str()?;
+
+        (name.starts_with(&prefix) && name.ends_with(".jar")).then_some(path)
+    });
+
+    newest(jars).ok_or_else(|| {
+        anyhow!(
+            "no {} server jar in {}, run mup server install first",
+            loader.name,
+            dir.display()
+        )
+    })
+}
+
+fn newest(paths: impl Iterator<Item = PathBuf>) -> Option<PathBuf> {
+    paths.max_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+}
+
+// Output goes straight to the terminal, input is forwarded line by line so a Ctrl-C can be turned
+// into a `stop` command instead of killing the server
+fn launch(args: &[String], console: &Console) -> Result<Exit> {
+    info!("launching {}", args.join(" "));
+
+    let mut command = Command::new(&args[0]);
+    command.args(&args[1..]).stdin(Stdio::piped());
+    detach_from_terminal(&mut command);
+
+    let mut child = command.spawn()?;
+    *lock(console)? = child.stdin.take();
+
+    let exit = wait(&mut child, console);
+    *lock(console)? = None;
+
+    exit
+}
+
+fn lock(console: &Console) -> Result<std::sync::MutexGuard<'_, Option<ChildStdin>>> {
+    console
+        .lock()
+        .map_err(|_| anyhow!("server console lock poisoned"))
+}
+
+fn wait(child: &mut Child, console: &Console) -> Result<Exit> {
+    let mut stopping = false;
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Exit {
+                status,
+                stopped: stopping,
+            });
+        }
+
+        if INTERRUPTED.swap(false, Ordering::SeqCst) {
+            // A second Ctrl-C gives up on a server that does not shut down
+            if stopping {
+                println!("killing the server");
+                child.kill()?;
+            } else {
+                println!("stopping the server, press Ctrl-C again to kill it");
+                stopping = true;
+
+                if let Some(stdin) = lock(console)?.as_mut() {
+                    writeln!(stdin, "stop")?;
+                    stdin.flush()?;
+                }
+            }
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// Started once, lines typed while the server is restarting are dropped
+fn forward_stdin(console: Console) {
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let Ok(mut stdin) = console.lock() else { break };
+
+            if let Some(stdin) = stdin.as_mut() {
+                // The server may have just exited, the next one gets a fresh pipe
+                let _ = writeln!(stdin, "{line}").and_then(|()| stdin.flush());
+            }
+        }
+    });
+}
+
+#[cfg(unix)]
+fn detach_from_terminal(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // The terminal sends Ctrl-C to its whole foreground process group, keeping the server out of
+    // it lets mup shut it down cleanly
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn detach_from_terminal(_: &mut Command) {}
+
+#[cfg(unix)]
+fn watch_interrupts() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    // Safety: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as *const () as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn watch_interrupts() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0), Duration::from_secs(5));
+        assert_eq!(backoff(1), Duration::from_secs(10));
+        assert_eq!(backoff(3), Duration::from_secs(40));
+        assert_eq!(backoff(10), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use super::properties::Properties;
+
+const DEFAULT_PORT: u16 = 25565;
+
+#[derive(Debug, Args)]
+pub struct Status {
+    /// Server to ping as host[:port], the server in the current directory by default
+    address: Option<String>,
+
+    /// Print the raw status response as JSON
+    #[arg(long, action)]
+    json: bool,
+}
+
+pub fn action(status: &Status) -> Result<()> {
+    let (host, port) = match &status.address {
+        Some(address) => parse_address(address)?,
+        None => local_address()?,
+    };
+
+    let (response, json) = mup::ping::ping(&host, port)?;
+
+    if status.json {
+        println!("{json}");
+        return Ok(());
+    }
+
+    let mut lines = vec![
+        format!("address: {host}:{port}"),
+        format!("motd: {}", response.motd()),
+        format!(
+            "version: {} (protocol {})",
+            response.version.name, response.version.protocol
+        ),
+    ];
+
+    if let Some(players) = &response.players {
+        let names: Vec<&str> = players.sample.iter().map(|p| p.name.as_str()).collect();
+
+        lines.push(if names.is_empty() {
+            format!("players: {}/{}", players.online, players.max)
+        } else {
+            format!(
+                "players: {}/{} ({})",
+                players.online,
+                players.max,
+                names.join(", ")
+            )
+        });
+    }
+
+    if !response.latency.is_zero() {
+        lines.push(format!("latency: {}ms", response.latency.as_millis()));
+    }
+
+    println!("{}", lines.join("\n"));
+
+    Ok(())
+}
+
+fn parse_address(address: &str) -> Result<(String, u16)> {
+    // IPv6 addresses are only split on a port after their closing bracket
+    let split = address
+        .rsplit_once(':')
+        .filter(|(host, _)| !host.contains(':') || host.ends_with(']'));
+
+    match split {
+        Some((host, port)) => Ok((
+            host.trim_matches(['[', ']']).to_string(),
+            port.parse()
+                .map_err(|_| anyhow!("invalid port in {address}"))?,
+        )),
+        None => Ok((address.trim_matches(['[', ']']).to_string(), DEFAULT_PORT)),
+    }
+}
+
+fn local_address() -> Result<(String, u16)> {
+    let properties = Properties::load()?;
+
+    let host = properties
+        .get("server-ip")
+        .filter(|ip| !ip.is_empty())
+        .unwrap_or("127.0.0.1")
+        .to_string();
+    let port = properties
+        .get("server-port")
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| anyhow!("invalid server-port in server.properties"))?
+        .unwrap_or(DEFAULT_PORT);
+
+    Ok((host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(
+            parse_address("play.example.com").unwrap(),
+            (String::from("play.example.com"), 25565)
+        );
+        assert_eq!(
+            parse_address("10.0.0.1:25570").unwrap(),
+            (String::from("10.0.0.1"), 25570)
+        );
+        assert_eq!(
+            parse_address("[::1]:25570").unwrap(),
+            (String::from("::1"), 25570)
+        );
+        assert_eq!(parse_address("::1").unwrap(), (String::from("::1"), 25565));
+        assert!(parse_address("host:port").is_err());
+    }
+}
@@ -0,0 +1,126 @@
+#![allow(clippy::case_sensitive_file_extension_comparisons)]
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use log::info;
+
+use super::lockfile::{self, Lockfile};
+
+const STAGING_DIR: &str = ".mup/template";
+
+// Sets up the current directory from a template holding a lockfile and any config files to go
+// with it, then installs everything the lockfile lists
+pub fn init(url: &str, yes: bool) -> Result<()> {
+    if Lockfile::existing()?.is_some() {
+        return Err(anyhow!("a server is already initialized in this directory"));
+    }
+
+    let staging = Path::new(STAGING_DIR);
+    if staging.exists() {
+        fs::remove_dir_all(staging)?;
+    }
+    fs::create_dir_all(staging)?;
+
+    let fetched = fetch(url, staging).and_then(|()| {
+        let root = template_root(staging)?;
+        copy_dir(&root, Path::new("."))
+    });
+    fs::remove_dir_all(staging)?;
+    let copied = fetched?;
+
+    println!("copied {copied} files from {url}");
+
+    super::install(yes, &[], false)
+}
+
+// Archives are downloaded and unpacked, anything else is cloned with git
+fn fetch(url: &str, dest: &Path) -> Result<()> {
+    let archive_name = url.rsplit('/').next().unwrap_or_default().to_lowercase();
+    let is_zip = archive_name.ends_with(".zip");
+    let is_tarball = archive_name.ends_with(".tar.gz") || archive_name.ends_with(".tgz");
+
+    if !is_zip && !is_tarball {
+        info!("cloning {url}");
+
+        let status = Command::new("git")
+            // A url starting with a dash must not be taken for an option
+            .args(["clone", "--depth", "1", "--", url])
+            .arg(dest.join("repo"))
+            .status()
+            .map_err(|e| anyhow!("git is required for templates from a repository: {e}"))?;
+
+        if !status.success() {
+            return Err(anyhow!("failed to clone {url}"));
+        }
+
+        return Ok(());
+    }
+
+    let archive = dest.join(&archive_name);
+    mup::download(url, &archive)?;
+
+    let unpacked = dest.join("repo");
+    if is_zip {
+        mup::zip::extract(&fs::read(&archive)?, &unpacked, |_| false)?;
+    } else {
+        mup::tar::extract(
+            GzDecoder::new(BufReader::new(File::open(&archive)?)),
+            &unpacked,
+        )?;
+    }
+
+    Ok(())
+}
+
+// Archives from code hosts wrap everything in a directory named after the repository
+fn template_root(staging: &Path) -> Result<PathBuf> {
+    let mut root = staging.join("repo");
+
+    loop {
+        if lockfile::exists_in(&root) {
+            return Ok(root);
+        }
+
+        let entries: Vec<PathBuf> = fs::read_dir(&root)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+
+        match entries.as_slice() {
+            [only] if only.is_dir() => root.clone_from(only),
+            _ => return Err(anyhow!("the template has no lockfile")),
+        }
+    }
+}
+
+// Returns how many files were copied, git metadata is left behind
+fn copy_dir(from: &Path, to: &Path) -> Result<usize> {
+    let mut copied = 0;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+
+        if entry.file_name() == ".git" {
+            continue;
+        }
+
+        if path.is_dir() {
+            fs::create_dir_all(&target)?;
+            copied += copy_dir(&path, &target)?;
+        } else {
+            info!("copying {}", target.display());
+            fs::copy(&path, &target)?;
+            copied += 1;
+        }
+    }
+
+    Ok(copied)
+}
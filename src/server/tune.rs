@@ -0,0 +1,419 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use super::{config, lockfile::Lockfile};
+
+const PAPER_GLOBAL: &str = "config/paper-global.yml";
+const PAPER_WORLD: &str = "config/paper-world-defaults.yml";
+const SPIGOT: &str = "spigot.yml";
+const BUKKIT: &str = "bukkit.yml";
+
+// File, dotted key and value of every setting a preset changes
+type Preset = &'static [(&'static str, &'static str, &'static str)];
+
+// Cuts entity, redstone and chunk work at the cost of small differences from vanilla
+const PERFORMANCE: Preset = &[
+    (PAPER_WORLD, "chunks.delay-chunk-unloads-by", "10s"),
+    (PAPER_WORLD, "chunks.max-auto-save-chunks-per-tick", "8"),
+    (
+        PAPER_WORLD,
+        "chunks.prevent-moving-into-unloaded-chunks",
+        "true",
+    ),
+    (PAPER_WORLD, "collisions.max-entity-collisions", "2"),
+    (
+        PAPER_WORLD,
+        "entities.armor-stands.do-collision-entity-lookups",
+        "false",
+    ),
+    (PAPER_WORLD, "entities.armor-stands.tick", "false"),
+    (PAPER_WORLD, "environment.optimize-explosions", "true"),
+    (PAPER_WORLD, "environment.treasure-maps.enabled", "false"),
+    (
+        PAPER_WORLD,
+        "misc.redstone-implementation",
+        "ALTERNATE_CURRENT",
+    ),
+    (PAPER_WORLD, "tick-rates.grass-spread", "4"),
+    (PAPER_WORLD, "tick-rates.mob-spawner", "2"),
+    (
+        SPIGOT,
+        "world-settings.default.entity-activation-range.animals",
+        "16",
+    ),
+    (
+        SPIGOT,
+        "world-settings.default.entity-activation-range.monsters",
+        "24",
+    ),
+    (
+        SPIGOT,
+        "world-settings.default.entity-activation-range.raiders",
+        "48",
+    ),
+    (
+        SPIGOT,
+        "world-settings.default.entity-activation-range.misc",
+        "8",
+    ),
+    (
+        SPIGOT,
+        "world-settings.default.entity-activation-range.water",
+        "8",
+    ),
+    (
+        SPIGOT,
+        "world-settings.default.entity-activation-range.villagers",
+        "16",
+    ),
+    (
+        SPIGOT,
+        "world-settings.default.entity-activation-range.flying-monsters",
+        "48",
+    ),
+    (SPIGOT, "world-settings.default.merge-radius.item", "3.5"),
+    (SPIGOT, "world-settings.default.merge-radius.exp", "4.0"),
+    (BUKKIT, "spawn-limits.monsters", "50"),
+    (BUKKIT, "spawn-limits.water-animals", "3"),
+    (BUKKIT, "spawn-limits.water-ambient", "10"),
+    (BUKKIT, "spawn-limits.ambient", "1"),
+    (BUKKIT, "ticks-per.monster-spawns", "10"),
+    (BUKKIT, "chunk-gc.period-in-ticks", "400"),
+];
+
+// Brings back vanilla mechanics that Paper and Spigot change, such as technical exploits
+const VANILLA_PARITY: Preset = &[
+    (
+        PAPER_GLOBAL,
+        "unsupported-settings.allow-headless-pistons",
+        "true",
+    ),
+    (
+        PAPER_GLOBAL,
+        "unsupported-settings.allow-permanent-block-break-exploits",
+        "true",
+    ),
+    (
+        PAPER_GLOBAL,
+        "unsupported-settings.allow-piston-duplication",
+        "true",
+    ),
+    (
+        PAPER_WORLD,
+        "unsupported-settings.fix-invulnerable-end-crystal-exploit",
+        "false",
+    ),
+    (
+        PAPER_WORLD,
+        "fixes.disable-unloaded-chunk-enderpearl-exploit",
+        "false",
+    ),
+    (
+        PAPER_WORLD,
+        "chunks.prevent-moving-into-unloaded-chunks",
+        "false",
+    ),
+    (PAPER_WORLD, "environment.optimize-explosions", "false"),
+    (PAPER_WORLD, "misc.redstone-implementation", "VANILLA"),
+    (SPIGOT, "world-settings.default.nerf-spawner-mobs", "false"),
+    (SPIGOT, "world-settings.default.hopper-amount", "1"),
+    (
+        SPIGOT,
+        "world-settings.default.ticks-per.hopper-transfer",
+        "8",
+    ),
+    (SPIGOT, "world-settings.default.ticks-per.hopper-check", "1"),
+    (BUKKIT, "spawn-limits.monsters", "70"),
+    (BUKKIT, "spawn-limits.animals", "10"),
+    (BUKKIT, "spawn-limits.water-animals", "5"),
+    (BUKKIT, "spawn-limits.water-ambient", "20"),
+    (BUKKIT, "spawn-limits.water-underground-creature", "5"),
+    (BUKKIT, "spawn-limits.axolotls", "5"),
+    (BUKKIT, "spawn-limits.ambient", "15"),
+    (BUKKIT, "ticks-per.monster-spawns", "1"),
+    (BUKKIT, "ticks-per.animal-spawns", "400"),
+];
+
+#[derive(Debug, Args)]
+pub struct Tune {
+    /// Set of values to apply
+    #[arg(long, value_parser = ["performance", "vanilla-parity"])]
+    preset: String,
+
+    /// Apply without asking after showing the changes
+    #[arg(short, long, action)]
+    yes: bool,
+}
+
+pub fn action(tune: &Tune) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    if !lockfile.loader.is_paper_family() {
+        return Err(anyhow!(
+            "presets only apply to Paper and Spigot servers, not {}",
+            lockfile.loader.name
+        ));
+    }
+
+    let preset = match tune.preset.as_str() {
+        "performance" => PERFORMANCE,
+        _ => VANILLA_PARITY,
+    };
+
+    // Spigot has no Paper configs to write to
+    let spigot = lockfile.loader.name == "spigot";
+
+    let mut files: Vec<(&str, Document, Document)> = vec![];
+    for (file, key, value) in preset {
+        if spigot && file.starts_with("config/paper-") {
+            continue;
+        }
+
+        let index = if let Some(i) = files.iter().position(|(f, _, _)| f == file) {
+            i
+        } else {
+            let document = Document::load(Path::new(file))?;
+            files.push((file, document.clone(), document));
+            files.len() - 1
+        };
+
+        files[index].2.set(key, value);
+    }
+
+    let mut changed = vec![];
+    for (file, before, after) in &files {
+        let lines = diff(before, after, preset, file);
+        if lines.is_empty() {
+            continue;
+        }
+
+        println!("{file}");
+        for line in lines {
+            println!("  {line}");
+        }
+
+        changed.push(*file);
+    }
+
+    if changed.is_empty() {
+        println!("the {} preset is already applied", tune.preset);
+        return Ok(());
+    }
+
+    if !tune.yes && !mup::confirm("Apply these changes?")? {
+        return Ok(());
+    }
+
+    // All files go into one snapshot so `mup server config undo` reverts the whole preset
+    config::snapshot_all(&changed.iter().map(Path::new).collect::<Vec<_>>())?;
+
+    for (file, _, after) in files.iter().filter(|(f, _, _)| changed.contains(f)) {
+        after.save(Path::new(file))?;
+    }
+
+    println!(
+        "applied the {} preset, restart the server for it to take effect",
+        tune.preset
+    );
+
+    Ok(())
+}
+
+// Changes the preset makes to one file, as removed and added lines
+fn diff(before: &Document, after: &Document, preset: Preset, file: &str) -> Vec<String> {
+    preset
+        .iter()
+        .filter(|(f, _, _)| *f == file)
+        .flat_map(|(_, key, _)| {
+            let old = before.get(key);
+            let new = after.get(key);
+
+            match (old, new) {
+                (_, None) => vec![],
+                (Some(old), Some(new)) if old == new => vec![],
+                (Some(old), Some(new)) => {
+                    vec![format!("- {key}: {old}"), format!("+ {key}: {new}")]
+                }
+                (None, Some(new)) => vec![format!("+ {key}: {new}")],
+            }
+        })
+        .collect()
+}
+
+// Block-style YAML as the server writes it, edited line by line so comments and the order of
+// keys are kept. Flow collections and multi-line values are left alone.
+#[derive(Clone, Default)]
+struct Document {
+    lines: Vec<String>,
+}
+
+impl Document {
+    // The server writes any settings missing from a file on its next start
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    fn parse(contents: &str) -> Self {
+        Self {
+            lines: contents.lines().map(String::from).collect(),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(prefix) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(prefix)?;
+        }
+
+        let mut contents = self.lines.join("\n");
+        contents.push('\n');
+
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    fn get(&self, path: &str) -> Option<&str> {
+        let mut range = (None, 0, self.lines.len());
+
+        let mut found = None;
+        for key in path.split('.') {
+            let i = self.find(range, key)?;
+            range = (Some(indent(&self.lines[i])), i + 1, self.block_end(i));
+            found = Some(i);
+        }
+
+        let (_, value) = self.lines[found?].split_once(':')?;
+        Some(value.trim())
+    }
+
+    // Replaces the value of `path`, adding the key and any missing parents if needed
+    fn set(&mut self, path: &str, value: &str) {
+        let keys: Vec<&str> = path.split('.').collect();
+        let mut range = (None, 0, self.lines.len());
+
+        for (depth, key) in keys.iter().enumerate() {
+            let Some(i) = self.find(range, key) else {
+                let (parent, start, end) = range;
+                let base = self.child_indent(parent, start, end);
+
+                let missing = keys[depth..].iter().enumerate().map(|(level, key)| {
+                    let padding = " ".repeat(base + 2 * level);
+                    if depth + level == keys.len() - 1 {
+                        format!("{padding}{key}: {value}")
+                    } else {
+                        format!("{padding}{key}:")
+                    }
+                });
+
+                self.lines.splice(end..end, missing);
+                return;
+            };
+
+            let padding = " ".repeat(indent(&self.lines[i]));
+            if depth == keys.len() - 1 {
+                self.lines[i] = format!("{padding}{key}: {value}");
+                return;
+            }
+
+            // Empty sections are written as `{}`, which has to go before children are added
+            if self.lines[i].trim_end().ends_with("{}") {
+                self.lines[i] = format!("{padding}{key}:");
+            }
+
+            range = (Some(indent(&self.lines[i])), i + 1, self.block_end(i));
+        }
+    }
+
+    // Line of `key` among the direct children of a section
+    fn find(
+        &self,
+        (parent, start, end): (Option<usize>, usize, usize),
+        key: &str,
+    ) -> Option<usize> {
+        let child = self.child_indent(parent, start, end);
+
+        (start..end).find(|&i| {
+            let line = &self.lines[i];
+            is_content(line)
+                && indent(line) == child
+                && line
+                    .split_once(':')
+                    .is_some_and(|(k, _)| k.trim().trim_matches(|c| c == '\'' || c == '"') == key)
+        })
+    }
+
+    // Indentation of the children of a section, going by the first one
+    fn child_indent(&self, parent: Option<usize>, start: usize, end: usize) -> usize {
+        let Some(parent) = parent else {
+            return 0;
+        };
+
+        self.lines[start..end]
+            .iter()
+            .filter(|l| is_content(l))
+            .map(|l| indent(l))
+            .find(|&i| i > parent)
+            .unwrap_or(parent + 2)
+    }
+
+    // One past the last line nested under line `i`
+    fn block_end(&self, i: usize) -> usize {
+        let own = indent(&self.lines[i]);
+        let mut end = i + 1;
+
+        for (j, line) in self.lines.iter().enumerate().skip(i + 1) {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            // List items may sit at the same indentation as their key
+            if indent(line) < own || (indent(line) == own && !trimmed.starts_with('-')) {
+                break;
+            }
+            end = j + 1;
+        }
+
+        end
+    }
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#') && !trimmed.starts_with('-')
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        let mut document = Document::parse(
+            "# header\n_version: 28\nchunks:\n  auto-save-interval: default\n  delay-chunk-unloads-by: 10s\nmisc: {}\nspawn:\n  allowed:\n  - a\n",
+        );
+        document.set("spawn.limit", "3");
+
+        assert_eq!(document.get("chunks.delay-chunk-unloads-by"), Some("10s"));
+        assert_eq!(document.get("chunks.missing"), None);
+
+        document.set("chunks.delay-chunk-unloads-by", "5s");
+        document.set("chunks.max-auto-save-chunks-per-tick", "8");
+        document.set("misc.redstone-implementation", "VANILLA");
+        document.set("tick-rates.behavior.villager", "2");
+
+        assert_eq!(
+            document.lines.join("\n"),
+            "# header\n_version: 28\nchunks:\n  auto-save-interval: default\n  delay-chunk-unloads-by: 5s\n  max-auto-save-chunks-per-tick: 8\nmisc:\n  redstone-implementation: VANILLA\nspawn:\n  allowed:\n  - a\n  limit: 3\ntick-rates:\n  behavior:\n    villager: 2"
+        );
+    }
+}
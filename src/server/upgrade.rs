@@ -0,0 +1,115 @@
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use super::lockfile::Lockfile;
+use crate::plugin::{self, Info, ResolveOptions};
+
+#[derive(Debug, Args)]
+pub struct Upgrade {
+    /// Minecraft version to upgrade to
+    #[arg(short, long)]
+    pub minecraft_version: String,
+
+    /// Loader version to upgrade to
+    #[arg(long, default_value = "latest")]
+    pub loader_version: String,
+
+    /// Upgrade even if some projects have no release for the new version, they are kept as is
+    #[arg(long, action)]
+    pub force: bool,
+
+    /// Do not ask for confirmation
+    #[arg(short, long, action)]
+    pub yes: bool,
+}
+
+// What happens to an installed project when the server is upgraded
+enum Plan {
+    /// Version the project resolves to, which may be the installed one
+    Update(String),
+    Keep(&'static str),
+    Incompatible(String),
+}
+
+pub fn action(upgrade: &Upgrade) -> Result<()> {
+    let current = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+
+    // Projects are resolved against a copy that only lives in memory until everything checks out
+    let mut target = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    target
+        .loader
+        .minecraft_version
+        .clone_from(&upgrade.minecraft_version);
+    target.loader.version.clone_from(&upgrade.loader_version);
+
+    if !target.loader.has_valid_minecraft_version() {
+        return Err(anyhow!(
+            "minecraft version {} is invalid",
+            upgrade.minecraft_version
+        ));
+    }
+
+    let plans: Vec<(&Info, Plan)> = current.mods.iter().map(|p| (p, plan(&target, p))).collect();
+
+    println!(
+        "{} {} -> {}",
+        current.loader.name, current.loader.minecraft_version, upgrade.minecraft_version
+    );
+    for (project, plan) in &plans {
+        match plan {
+            Plan::Update(version) if *version == project.version => {
+                println!("  {}: {} (unchanged)", project.name, project.version);
+            }
+            Plan::Update(version) => {
+                println!("  {}: {} -> {version}", project.name, project.version);
+            }
+            Plan::Keep(reason) => {
+                println!("  {}: {} (kept, {reason})", project.name, project.version);
+            }
+            Plan::Incompatible(e) => {
+                println!("  {}: no compatible release ({e})", project.name);
+            }
+        }
+    }
+
+    let incompatible = plans
+        .iter()
+        .filter(|(_, p)| matches!(p, Plan::Incompatible(_)))
+        .count();
+    if incompatible > 0 && !upgrade.force {
+        return Err(anyhow!(
+            "{incompatible} projects have no release for Minecraft {}, nothing was changed. \
+             Remove them or pass --force to keep them as they are",
+            upgrade.minecraft_version
+        ));
+    }
+
+    if !upgrade.yes && !mup::confirm("Proceed?")? {
+        return Ok(());
+    }
+
+    // The lockfile only changes once the new server jar is in place
+    target.loader.pin()?;
+    target.fetch_loader()?;
+
+    for (project, plan) in &plans {
+        if let Plan::Update(version) = plan {
+            if *version != project.version {
+                plugin::apply_update(&project.name, "latest", &ResolveOptions::default())?;
+            }
+        }
+    }
+
+    println!("upgraded to Minecraft {}", upgrade.minecraft_version);
+
+    Ok(())
+}
+
+fn plan(target: &Lockfile, project: &Info) -> Plan {
+    match plugin::latest_for(target, project) {
+        Ok(Some(info)) => Plan::Update(info.version),
+        Ok(None) if project.pinned => Plan::Keep("pinned"),
+        Ok(None) => Plan::Keep("not versioned by its provider"),
+        Err(e) => Plan::Incompatible(e.to_string()),
+    }
+}
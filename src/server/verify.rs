@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use super::lockfile::Lockfile;
+use crate::plugin::{self, verify};
+
+#[derive(Debug, Args)]
+pub struct Verify {
+    /// Download missing and modified jars again from the lockfile
+    #[arg(long, action)]
+    repair: bool,
+
+    /// Number of files to hash in parallel
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+// Checks every jar the server loads, e.g. after restoring a backup: projects in the lockfile
+// must be on disk with the recorded checksum, and nothing else may be in the mod directories
+pub fn action(args: &Verify) -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let report = verify::compare(&lockfile, args.jobs.unwrap_or_else(mup::jobs))?;
+
+    for info in &report.unchecked {
+        println!("unchecked  {} (no checksum recorded)", info.name);
+    }
+    for info in &report.missing {
+        println!("missing    {}", info.name);
+    }
+    for info in &report.modified {
+        println!("modified   {}", info.name);
+    }
+    for path in &report.unknown {
+        println!("unknown    {}", path.display());
+    }
+
+    // Downloads are checked against the lockfile, so repaired jars need no second look
+    let broken = if args.repair {
+        for info in report.missing.iter().chain(&report.modified) {
+            println!("downloading {} {}", info.name, info.version);
+            plugin::download_plugin(&lockfile, info)?;
+        }
+
+        0
+    } else {
+        report.missing.len() + report.modified.len()
+    };
+
+    if !report.unknown.is_empty() {
+        println!("track unknown jars with `mup plugin add --provider local` or delete them with `mup plugin gc`");
+    }
+
+    let failures = broken + report.unknown.len();
+    if failures > 0 {
+        return Err(anyhow!("{failures} files failed verification"));
+    }
+
+    println!("all {} projects verified", lockfile.mods.len());
+
+    Ok(())
+}
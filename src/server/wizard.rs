@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Result};
+use dialoguer::{Input, MultiSelect, Select};
+use log::warn;
+use regex::Regex;
+
+use super::{eula, lockfile::Lockfile};
+use crate::{
+    loader::{vanilla, Loader},
+    plugin::{self, ResolveOptions},
+};
+
+// How many of the newest releases are offered, older ones can still be typed in
+const SHOWN_RELEASES: usize = 15;
+
+// Walks through the choices `mup server init` otherwise takes as flags
+pub fn wizard() -> Result<()> {
+    if Lockfile::existing()?.is_some() {
+        return Err(anyhow!("a server is already initialized in this directory"));
+    }
+
+    let loaders = Loader::VALID_LOADERS;
+    let choice = Select::new()
+        .with_prompt("Loader")
+        .items(&loaders)
+        .default(loaders.iter().position(|l| *l == "paper").unwrap_or(0))
+        .interact()?;
+    let loader = loaders[choice];
+
+    let candidate = Loader::new(loader, "latest", "latest", false);
+    let minecraft_version = if candidate.is_bedrock() {
+        String::from("latest")
+    } else {
+        minecraft_version()?
+    };
+
+    let eula = !candidate.is_bedrock() && eula::prompt()?;
+
+    let memory = if candidate.is_bedrock() {
+        None
+    } else {
+        Some(memory()?)
+    };
+
+    let starters = starter_plugins(&candidate);
+    let chosen = if starters.is_empty() {
+        vec![]
+    } else {
+        MultiSelect::new()
+            .with_prompt("Starter plugins (space to select, enter to confirm)")
+            .items(starters)
+            .interact()?
+    };
+
+    // The eula was already asked about, so init is kept from asking again
+    super::init(&minecraft_version, loader, true, false, None, None)?;
+
+    if eula {
+        eula::sign()?;
+    } else if !candidate.is_bedrock() {
+        println!("the eula was not accepted, run `mup server sign` once you do");
+    }
+
+    if let Some(memory) = memory {
+        let mut lockfile =
+            Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+        lockfile.jvm_args = vec![format!("-Xms{memory}"), format!("-Xmx{memory}")];
+        lockfile.save()?;
+    }
+
+    if !chosen.is_empty() {
+        let ids: Vec<&str> = chosen.iter().map(|&i| starters[i]).collect();
+        plugin::add(
+            "modrinth",
+            &ids,
+            "latest",
+            false,
+            plugin::Reason::Requested,
+            &ResolveOptions::default(),
+        )?;
+    }
+
+    println!("server initialized, start it with `mup server run`");
+
+    Ok(())
+}
+
+// Recent releases are fetched live, falling back to typing one in when that fails
+fn minecraft_version() -> Result<String> {
+    let releases = match vanilla::releases() {
+        Ok(releases) => releases,
+        Err(e) => {
+            warn!("failed to fetch Minecraft versions: {e}");
+            vec![]
+        }
+    };
+
+    let shown = &releases[..releases.len().min(SHOWN_RELEASES)];
+    if !shown.is_empty() {
+        let mut items: Vec<&str> = shown.iter().map(String::as_str).collect();
+        items.push("other");
+
+        let choice = Select::new()
+            .with_prompt("Minecraft version")
+            .items(&items)
+            .default(0)
+            .interact()?;
+
+        if choice < shown.len() {
+            return Ok(shown[choice].clone());
+        }
+    }
+
+    Ok(Input::<String>::new()
+        .with_prompt("Minecraft version")
+        .validate_with(|v: &String| {
+            Loader::new("vanilla", v, "latest", false)
+                .has_valid_minecraft_version()
+                .then_some(())
+                .ok_or("not a Minecraft version")
+        })
+        .interact_text()?)
+}
+
+// Used for both the initial and maximum heap, like most hosts do
+fn memory() -> Result<String> {
+    let pattern = Regex::new(r"^\d+[MG]$")?;
+
+    Ok(Input::<String>::new()
+        .with_prompt("Memory for the server, e.g. 4G")
+        .default(String::from("2G"))
+        .validate_with(|m: &String| {
+            pattern
+                .is_match(m)
+                .then_some(())
+                .ok_or("use a number followed by M or G")
+        })
+        .interact_text()?)
+}
+
+// A few widely used Modrinth projects that run on the loader
+fn starter_plugins(loader: &Loader) -> &'static [&'static str] {
+    match loader.name.as_str() {
+        "fabric" => &[
+            "fabric-api",
+            "lithium",
+            "ferrite-core",
+            "spark",
+            "chunky",
+            "luckperms",
+        ],
+        "forge" | "neoforge" => &["ferrite-core", "spark", "chunky", "luckperms"],
+        "velocity" | "waterfall" | "bungeecord" | "spongevanilla" | "spongeforge" => {
+            &["luckperms", "spark"]
+        }
+        "vanilla" | "bedrock" => &[],
+        _ => &["luckperms", "spark", "chunky", "worldedit", "essentialsx"],
+    }
+}
@@ -0,0 +1,190 @@
+use std::{fs, fs::File, path::Path};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use flate2::read::GzDecoder;
+use log::info;
+
+use mup::nbt::{self, Tag};
+
+use super::properties::Properties;
+
+#[derive(Debug, Subcommand)]
+pub enum World {
+    /// List the worlds in the server directory
+    List,
+
+    /// Rename a world, including its dimension folders
+    Rename {
+        /// Current name of the world
+        from: String,
+
+        /// New name of the world
+        to: String,
+    },
+
+    /// Delete a world so it is regenerated on the next start
+    Reset {
+        /// Name of the world, defaults to the active level-name
+        name: Option<String>,
+
+        /// Do not ask for confirmation
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+
+    /// Print the seed of a world
+    Seed {
+        /// Name of the world, defaults to the active level-name
+        name: Option<String>,
+    },
+}
+
+const DIMENSION_SUFFIXES: [&str; 2] = ["_nether", "_the_end"];
+
+pub fn action(world: &World) -> Result<()> {
+    match world {
+        World::List => list(),
+        World::Rename { from, to } => rename(from, to),
+        World::Reset { name, yes } => reset(name.as_deref(), *yes),
+        World::Seed { name } => seed(name.as_deref()),
+    }
+}
+
+// Vanilla keeps every dimension inside the world folder, Bukkit-style servers split them out
+// into `<level>_nether` and `<level>_the_end`
+pub fn dimension_dirs(level: &str) -> Vec<String> {
+    let mut dirs = vec![level.to_string()];
+    dirs.extend(DIMENSION_SUFFIXES.iter().map(|s| format!("{level}{s}")));
+
+    dirs.into_iter().filter(|d| Path::new(d).is_dir()).collect()
+}
+
+pub fn active_dirs() -> Result<Vec<String>> {
+    let properties = Properties::load()?;
+
+    Ok(dimension_dirs(properties.level_name()))
+}
+
+fn list() -> Result<()> {
+    let properties = Properties::load()?;
+    let active = properties.level_name();
+
+    let mut worlds: Vec<String> = fs::read_dir(".")?
+        .filter_map(Result::ok)
+        .filter(|e| e.path().join("level.dat").exists())
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|name| {
+            !DIMENSION_SUFFIXES.iter().any(|s| {
+                name.strip_suffix(s)
+                    .is_some_and(|base| Path::new(base).join("level.dat").exists())
+            })
+        })
+        .collect();
+    worlds.sort();
+
+    if worlds.is_empty() {
+        println!("no worlds found");
+        return Ok(());
+    }
+
+    for world in worlds {
+        let mut dimensions = vec!["overworld"];
+
+        if Path::new(&world).join("DIM-1").is_dir()
+            || Path::new(&format!("{world}_nether")).is_dir()
+        {
+            dimensions.push("nether");
+        }
+
+        if Path::new(&world).join("DIM1").is_dir()
+            || Path::new(&format!("{world}_the_end")).is_dir()
+        {
+            dimensions.push("the_end");
+        }
+
+        let marker = if world == active { " (active)" } else { "" };
+        println!("{world}{marker}: {}", dimensions.join(", "));
+    }
+
+    Ok(())
+}
+
+fn rename(from: &str, to: &str) -> Result<()> {
+    let dirs = dimension_dirs(from);
+    if dirs.is_empty() {
+        return Err(anyhow!("world {from} does not exist"));
+    }
+
+    if !dimension_dirs(to).is_empty() {
+        return Err(anyhow!("world {to} already exists"));
+    }
+
+    for dir in dirs {
+        let renamed = format!("{to}{}", &dir[from.len()..]);
+        info!("renaming {dir} to {renamed}");
+
+        fs::rename(&dir, &renamed)?;
+    }
+
+    let mut properties = Properties::load()?;
+    if properties.level_name() == from {
+        info!("updating level-name to {to}");
+
+        properties.set("level-name", to);
+        properties.save()?;
+    }
+
+    Ok(())
+}
+
+fn reset(name: Option<&str>, yes: bool) -> Result<()> {
+    let dirs = match name {
+        Some(name) => dimension_dirs(name),
+        None => active_dirs()?,
+    };
+
+    if dirs.is_empty() {
+        println!("no worlds to reset");
+        return Ok(());
+    }
+
+    println!("this will delete {}", dirs.join(", "));
+
+    if !yes && !mup::confirm("Delete worlds?")? {
+        return Ok(());
+    }
+
+    for dir in dirs {
+        info!("deleting {dir}");
+        fs::remove_dir_all(dir)?;
+    }
+
+    println!("worlds will be regenerated on the next server start");
+
+    Ok(())
+}
+
+fn seed(name: Option<&str>) -> Result<()> {
+    let properties = Properties::load()?;
+    let level = name.unwrap_or_else(|| properties.level_name());
+
+    let path = Path::new(level).join("level.dat");
+    if !path.exists() {
+        return Err(anyhow!("world {level} has no level.dat"));
+    }
+
+    let root = nbt::read(GzDecoder::new(File::open(path)?))?;
+
+    // 1.16 moved the seed into WorldGenSettings
+    let seed = root
+        .path(&["Data", "WorldGenSettings", "seed"])
+        .or_else(|| root.path(&["Data", "RandomSeed"]));
+
+    match seed {
+        Some(Tag::Long(seed)) => println!("{seed}"),
+        _ => return Err(anyhow!("could not find a seed in {level}/level.dat")),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::io::{self, BufRead, IsTerminal};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use dialoguer::Password;
+use mup::credentials;
+
+#[derive(Debug, Subcommand)]
+pub enum Settings {
+    /// Manage API keys used by providers
+    #[command(subcommand)]
+    Credentials(Credentials),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Credentials {
+    /// Store an API key, read from a prompt or from stdin
+    Set {
+        #[arg(value_parser = ["github", "modrinth", "s3"])]
+        provider: String,
+    },
+
+    /// List stored API keys
+    List,
+
+    /// Delete a stored API key
+    Remove {
+        #[arg(value_parser = ["github", "modrinth", "s3"])]
+        provider: String,
+    },
+}
+
+pub fn action(settings: &Settings) -> Result<()> {
+    match settings {
+        Settings::Credentials(Credentials::Set { provider }) => set(provider),
+        Settings::Credentials(Credentials::List) => list(),
+        Settings::Credentials(Credentials::Remove { provider }) => remove(provider),
+    }
+}
+
+// Secrets are never taken as arguments, so they don't end up in shell history
+fn set(provider: &str) -> Result<()> {
+    let secret = if io::stdin().is_terminal() {
+        Password::new()
+            .with_prompt(format!("API key for {provider}"))
+            .interact()?
+    } else {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        line.trim().to_string()
+    };
+
+    if secret.is_empty() {
+        return Err(anyhow!("no API key given"));
+    }
+
+    let mut stored = credentials::load()?;
+    stored.insert(provider.to_string(), secret);
+    credentials::save(&stored)?;
+
+    println!("stored API key for {provider}");
+
+    Ok(())
+}
+
+fn list() -> Result<()> {
+    let stored = credentials::load()?;
+
+    for (provider, var) in credentials::PROVIDERS {
+        match (
+            std::env::var(var).ok().filter(|v| !v.is_empty()),
+            stored.get(provider),
+        ) {
+            (Some(secret), _) => {
+                println!("{provider}: {} (from {var})", credentials::mask(&secret));
+            }
+            (None, Some(secret)) => println!("{provider}: {}", credentials::mask(secret)),
+            (None, None) => println!("{provider}: not set"),
+        }
+    }
+
+    Ok(())
+}
+
+fn remove(provider: &str) -> Result<()> {
+    let mut stored = credentials::load()?;
+
+    if stored.remove(provider).is_none() {
+        return Err(anyhow!("no API key stored for {provider}"));
+    }
+
+    credentials::save(&stored)?;
+
+    println!("removed API key for {provider}");
+
+    Ok(())
+}
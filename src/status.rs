@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    plugin::{verify, Info},
+    server::lockfile::Lockfile,
+};
+
+// Summarizes how the jars on disk differ from the lockfile, like `git status`, without changing
+// anything
+pub fn status() -> Result<()> {
+    let lockfile = Lockfile::existing()?.ok_or_else(|| anyhow!("failed to read lockfile"))?;
+    let report = verify::compare(&lockfile, mup::jobs())?;
+
+    println!(
+        "{} {} with {} projects",
+        lockfile.loader.name,
+        lockfile.loader.minecraft_version,
+        lockfile.mods.len()
+    );
+
+    let path = |info: &Info| info.get_file_path(&lockfile.loader).display().to_string();
+    let sections = [
+        (
+            "In the lockfile but missing on disk",
+            "run `mup server install` to download them",
+            report.missing.iter().map(|i| format!("{} ({})", i.name, path(i))).collect(),
+        ),
+        (
+            "Modified since they were installed",
+            "run `mup server verify --repair` to download them again",
+            report.modified.iter().map(|i| format!("{} ({})", i.name, path(i))).collect(),
+        ),
+        (
+            "Not tracked by the lockfile",
+            "run `mup plugin add --provider local <path>` to track them or `mup plugin gc` to delete them",
+            report.unknown.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+        ),
+    ];
+
+    let mut clean = true;
+    for (title, hint, entries) in sections {
+        if entries.is_empty() {
+            continue;
+        }
+
+        clean = false;
+        println!("\n{title}:\n  ({hint})");
+        for entry in entries {
+            println!("        {entry}");
+        }
+    }
+
+    if !report.unchecked.is_empty() {
+        let names: Vec<&str> = report.unchecked.iter().map(|i| i.name.as_str()).collect();
+        println!("\nno checksum recorded for {}", names.join(", "));
+    }
+
+    if clean {
+        println!("every jar matches the lockfile");
+    }
+
+    Ok(())
+}
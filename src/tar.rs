@@ -0,0 +1,387 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+const BLOCK: usize = 512;
+// GNU extension carrying names that do not fit into the 100 bytes of a header
+const LONG_NAME: &str = "././@LongLink";
+
+/// Writes a tar archive of `paths`, files or directories relative to `base`, and returns the
+/// writer. Symlinks are skipped rather than followed.
+pub fn write<W: Write>(mut out: W, base: &Path, paths: &[PathBuf]) -> Result<W> {
+    for path in paths {
+        append(&mut out, base, path)?;
+    }
+
+    out.write_all(&[0; BLOCK * 2])?;
+
+    Ok(out)
+}
+
+fn append<W: Write>(out: &mut W, base: &Path, path: &Path) -> Result<()> {
+    let full = base.join(path);
+    let metadata = fs::symlink_metadata(&full)?;
+    let name = path.to_string_lossy().replace('\\', "/");
+
+    if metadata.is_dir() {
+        write_header(out, &format!("{name}/"), b'5', 0, mode(&metadata))?;
+
+        let mut entries: Vec<PathBuf> = fs::read_dir(&full)?
+            .map(|e| e.map(|e| path.join(e.file_name())))
+            .collect::<io::Result<_>>()?;
+        entries.sort();
+
+        for entry in entries {
+            append(out, base, &entry)?;
+        }
+    } else if metadata.is_file() {
+        write_header(out, &name, b'0', metadata.len(), mode(&metadata))?;
+
+        let copied = io::copy(&mut File::open(&full)?, out)?;
+        if copied != metadata.len() {
+            return Err(anyhow!("{name} changed while it was being archived"));
+        }
+        pad(out, copied)?;
+    } else {
+        info!("skipping {name}, it is not a regular file");
+    }
+
+    Ok(())
+}
+
+fn write_header<W: Write>(out: &mut W, name: &str, kind: u8, size: u64, mode: u32) -> Result<()> {
+    if name.len() >= 100 {
+        let long = [name.as_bytes(), &[0]].concat();
+        write_header(out, LONG_NAME, b'L', long.len() as u64, 0)?;
+        out.write_all(&long)?;
+        pad(out, long.len() as u64)?;
+    }
+
+    let mut header = [0u8; BLOCK];
+    let short = &name.as_bytes()[..name.len().min(99)];
+    header[..short.len()].copy_from_slice(short);
+    octal(&mut header[100..108], u64::from(mode));
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], size);
+    octal(&mut header[136..148], 0);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field set to spaces
+    header[148..156].fill(b' ');
+    let sum: u64 = header.iter().map(|b| u64::from(*b)).sum();
+    octal(&mut header[148..155], sum);
+
+    out.write_all(&header)?;
+
+    Ok(())
+}
+
+// Zero-padded octal digits followed by a NUL, filling the field
+fn octal(field: &mut [u8], value: u64) {
+    let width = field.len() - 1;
+    let digits = format!("{value:0width$o}");
+    field[..width].copy_from_slice(&digits.as_bytes()[digits.len() - width..]);
+    field[width] = 0;
+}
+
+fn pad<W: Write>(out: &mut W, size: u64) -> Result<()> {
+    let rest = (BLOCK - (size as usize % BLOCK)) % BLOCK;
+    out.write_all(&vec![0; rest])?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o777
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.is_dir() {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+/// Extracts a tar archive into `dest`, returning how many files were written. Regular files,
+/// directories and, on unix, symlinks that stay inside `dest` are supported, along with GNU and
+/// pax long names.
+pub fn extract<R: Read>(mut input: R, dest: &Path) -> Result<usize> {
+    let mut files = 0;
+    let mut long_name = None;
+    let mut long_link = None;
+    let mut header = [0u8; BLOCK];
+
+    loop {
+        input.read_exact(&mut header)?;
+
+        if header.iter().all(|b| *b == 0) {
+            return Ok(files);
+        }
+
+        let expected = parse_octal(&header[148..156])?;
+        let mut blank = header;
+        blank[148..156].fill(b' ');
+        if blank.iter().map(|b| u64::from(*b)).sum::<u64>() != expected {
+            return Err(anyhow!("corrupted tar header"));
+        }
+
+        let size = parse_octal(&header[124..136])?;
+        let mode = u32::try_from(parse_octal(&header[100..108])?)?;
+        let name = match long_name.take() {
+            Some(name) => name,
+            None => header_name(&header),
+        };
+        let link = long_link
+            .take()
+            .unwrap_or_else(|| field_str(&header[157..257]));
+
+        match header[156] {
+            b'L' | b'K' | b'x' => {
+                let mut data = vec![0; usize::try_from(size)?];
+                input.read_exact(&mut data)?;
+                skip_padding(&mut input, size)?;
+
+                match header[156] {
+                    b'L' => long_name = Some(field_str(&data)),
+                    b'K' => long_link = Some(field_str(&data)),
+                    _ => {
+                        let (path, linkpath) = pax_paths(&data);
+                        long_name = path.or(long_name);
+                        long_link = linkpath.or(long_link);
+                    }
+                }
+            }
+            b'2' => {
+                if !link_stays_inside(&name, &link) {
+                    return Err(anyhow!(
+                        "refusing to extract link {name} pointing outside of the destination"
+                    ));
+                }
+
+                let path = dest.join(safe_path(&name)?);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                symlink(&link, &path)?;
+            }
+            b'5' => {
+                fs::create_dir_all(dest.join(safe_path(&name)?))?;
+            }
+            b'0' | 0 => {
+                let path = dest.join(safe_path(&name)?);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                let mut file = File::create(&path)?;
+                let copied = io::copy(&mut (&mut input).take(size), &mut file)?;
+                if copied != size {
+                    return Err(anyhow!("truncated tar archive"));
+                }
+                skip_padding(&mut input, size)?;
+                set_mode(&path, mode)?;
+
+                files += 1;
+            }
+            kind => {
+                info!(
+                    "skipping {name}, unsupported tar entry type {}",
+                    kind as char
+                );
+                io::copy(&mut (&mut input).take(size), &mut io::sink())?;
+                skip_padding(&mut input, size)?;
+            }
+        }
+    }
+}
+
+// Ustar splits long names into a prefix and a name
+fn header_name(header: &[u8; BLOCK]) -> String {
+    let name = field_str(&header[0..100]);
+    let prefix = field_str(&header[345..500]);
+
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        format!("{prefix}/{name}")
+    } else {
+        name
+    }
+}
+
+// Pax extended headers are records of the form `<length> <key>=<value>\n`
+fn pax_paths(data: &[u8]) -> (Option<String>, Option<String>) {
+    let (mut path, mut linkpath) = (None, None);
+    let text = String::from_utf8_lossy(data);
+
+    for record in text.split_terminator('\n') {
+        let Some((_, pair)) = record.split_once(' ') else {
+            continue;
+        };
+
+        match pair.split_once('=') {
+            Some(("path", value)) => path = Some(value.to_string()),
+            Some(("linkpath", value)) => linkpath = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    (path, linkpath)
+}
+
+// Resolves a relative link target against the directory of the link
+fn link_stays_inside(name: &str, target: &str) -> bool {
+    if target.starts_with('/') {
+        return false;
+    }
+
+    let mut depth: usize = 0;
+    let parents = name.trim_end_matches('/').split('/').count() - 1;
+
+    for part in name.split('/').take(parents).chain(target.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => depth += 1,
+        }
+    }
+
+    true
+}
+
+#[cfg(unix)]
+fn symlink(target: &str, path: &Path) -> Result<()> {
+    if path.symlink_metadata().is_ok() {
+        fs::remove_file(path)?;
+    }
+
+    std::os::unix::fs::symlink(target, path)?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn symlink(target: &str, path: &Path) -> Result<()> {
+    info!("skipping link {} to {target}", path.display());
+
+    Ok(())
+}
+
+fn field_str(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    let text = field_str(field);
+    let text = text.trim();
+
+    if text.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(text, 8).map_err(|_| anyhow!("invalid number {text} in tar header"))
+}
+
+fn skip_padding<R: Read>(input: &mut R, size: u64) -> Result<()> {
+    let rest = (BLOCK - (size as usize % BLOCK)) % BLOCK;
+    io::copy(&mut input.take(rest as u64), &mut io::sink())?;
+
+    Ok(())
+}
+
+// Entries must stay inside the destination directory
+fn safe_path(name: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(name);
+
+    if path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(path)
+    } else {
+        Err(anyhow!(
+            "refusing to extract {name} outside of the destination"
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if mode & 0o777 != 0 {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let src = tempfile::tempdir().unwrap();
+        let world = src.path().join("world");
+        let long = "a".repeat(120);
+
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::write(world.join("level.dat"), b"level").unwrap();
+        fs::write(world.join("region").join(&long), vec![7; 1000]).unwrap();
+
+        let archive = write(vec![], src.path(), &[PathBuf::from("world")]).unwrap();
+        assert_eq!(archive.len() % BLOCK, 0);
+
+        let dest = tempfile::tempdir().unwrap();
+        let files = extract(archive.as_slice(), dest.path()).unwrap();
+
+        assert_eq!(files, 2);
+        assert_eq!(
+            fs::read(dest.path().join("world/level.dat")).unwrap(),
+            b"level"
+        );
+        assert_eq!(
+            fs::read(dest.path().join("world/region").join(&long)).unwrap(),
+            vec![7; 1000]
+        );
+    }
+
+    #[test]
+    fn test_rejects_corruption() {
+        let mut header = [0u8; BLOCK * 3];
+        write_header(&mut &mut header[..], "../evil", b'0', 0, 0o644).unwrap();
+        assert!(extract(&header[..], Path::new("/tmp")).is_err());
+
+        let mut header = [0u8; BLOCK * 3];
+        write_header(&mut &mut header[..], "file", b'0', 0, 0o644).unwrap();
+        header[0] = b'x';
+        assert!(extract(&header[..], Path::new("/tmp")).is_err());
+
+        assert!(link_stays_inside("jre/legal/a/LICENSE", "../b/LICENSE"));
+        assert!(!link_stays_inside("jre/lib", "../../etc"));
+        assert!(!link_stays_inside("jre/lib", "/etc/passwd"));
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    env,
+    sync::{Mutex, PoisonError},
+};
+
+use anyhow::Result;
+
+use crate::{
+    loader::Loader,
+    plugin::{Dependency, Info},
+    server::lockfile::Lockfile,
+};
+
+// The working directory is shared by the whole process, so tests that need a server directory
+// of their own take turns
+static WORKING_DIR: Mutex<()> = Mutex::new(());
+
+// Runs `f` inside an empty temporary directory, going back to the previous one afterwards
+pub fn in_temp_dir<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let _turn = WORKING_DIR.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let dir = tempfile::tempdir()?;
+    let original = env::current_dir()?;
+    env::set_current_dir(&dir)?;
+
+    let result = f();
+    env::set_current_dir(original)?;
+
+    result
+}
+
+// A Modrinth project requiring every project in `dependencies`, installed as a dependency of
+// the projects in `required_by` if there are any
+pub fn project(name: &str, dependencies: &[&str], required_by: &[&str]) -> Info {
+    let dependencies = dependencies
+        .iter()
+        .map(|d| Dependency {
+            id: String::new(),
+            source: String::new(),
+            name: d.to_string(),
+            required: true,
+            incompatible: false,
+            embedded: false,
+        })
+        .collect();
+
+    Info {
+        name: name.to_string(),
+        id: name.to_string(),
+        version: String::from("1.0.0"),
+        source: String::from("modrinth"),
+        download_url: format!("https://example.com/{name}.jar"),
+        dependencies: Some(dependencies),
+        dependency: !required_by.is_empty(),
+        required_by: required_by.iter().map(ToString::to_string).collect(),
+        ..Default::default()
+    }
+}
+
+pub fn lockfile_with(mods: Vec<Info>) -> Lockfile {
+    Lockfile {
+        loader: Loader::new("paper", "1.21.4", "latest", false),
+        mods,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECORDS: Mutex<Vec<Record>> = Mutex::new(Vec::new());
+
+struct Record {
+    phase: &'static str,
+    label: String,
+    duration: Duration,
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn record(phase: &'static str, label: &str, duration: Duration) {
+    if !is_enabled() {
+        return;
+    }
+
+    if let Ok(mut records) = RECORDS.lock() {
+        records.push(Record {
+            phase,
+            label: label.to_string(),
+            duration,
+        });
+    }
+}
+
+pub fn time<T>(phase: &'static str, label: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+
+    record(phase, label, start.elapsed());
+
+    result
+}
+
+pub fn report() {
+    if !is_enabled() {
+        return;
+    }
+
+    let Ok(records) = RECORDS.lock() else {
+        return;
+    };
+
+    eprintln!("timings:");
+
+    for r in records.iter() {
+        eprintln!("  {:<10} {:>10.3?}  {}", r.phase, r.duration, r.label);
+    }
+
+    let mut phases: Vec<&str> = records.iter().map(|r| r.phase).collect();
+    phases.sort_unstable();
+    phases.dedup();
+
+    eprintln!("totals:");
+
+    for phase in phases {
+        let matching = records.iter().filter(|r| r.phase == phase);
+        let count = matching.clone().count();
+        let total: Duration = matching.map(|r| r.duration).sum();
+
+        eprintln!("  {phase:<10} {total:>10.3?}  ({count} entries)");
+    }
+}
@@ -0,0 +1,431 @@
+use std::{cmp::Ordering, fmt};
+
+use versions::Versioning;
+
+/// A Minecraft version string, such as `1.20.4`, `1.20.5-pre1`, `1.7.10_pre4` or `24w14a`.
+///
+/// Releases and pre-releases are ordered against each other, with pre-releases sorting before
+/// release candidates, which sort before the release itself. Snapshots are only ordered against
+/// other snapshots, since relating them to releases requires the version manifest.
+#[derive(Clone, Debug)]
+pub enum MinecraftVersion {
+    Release {
+        major: u32,
+        minor: u32,
+        patch: Option<u32>,
+    },
+    PreRelease {
+        major: u32,
+        minor: u32,
+        patch: Option<u32>,
+        stage: Stage,
+        number: u32,
+        raw: String,
+    },
+    Snapshot {
+        year: u32,
+        week: u32,
+        revision: char,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    PreRelease,
+    ReleaseCandidate,
+}
+
+impl MinecraftVersion {
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+
+        if let Some(snapshot) = parse_snapshot(input) {
+            return Some(snapshot);
+        }
+
+        let split = input
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(input.len());
+        let (base, suffix) = input.split_at(split);
+        let (major, minor, patch) = parse_release(base)?;
+
+        if suffix.is_empty() {
+            return Some(Self::Release {
+                major,
+                minor,
+                patch,
+            });
+        }
+
+        let suffix = suffix.trim_start_matches(['-', '_', ' ']).to_lowercase();
+
+        let (stage, number) = if let Some(n) = suffix
+            .strip_prefix("pre-release")
+            .or_else(|| suffix.strip_prefix("pre"))
+        {
+            (Stage::PreRelease, n)
+        } else if let Some(n) = suffix
+            .strip_prefix("release candidate")
+            .or_else(|| suffix.strip_prefix("rc"))
+        {
+            (Stage::ReleaseCandidate, n)
+        } else {
+            return None;
+        };
+
+        let number = number.trim().parse().ok()?;
+
+        Some(Self::PreRelease {
+            major,
+            minor,
+            patch,
+            stage,
+            number,
+            raw: input.to_string(),
+        })
+    }
+
+    pub const fn is_release(&self) -> bool {
+        matches!(self, Self::Release { .. })
+    }
+
+    pub const fn is_snapshot(&self) -> bool {
+        matches!(self, Self::Snapshot { .. })
+    }
+
+    // Sort key for releases and pre-releases, a release sorts after all of its pre-releases
+    fn release_key(&self) -> Option<(u32, u32, u32, u8, u32)> {
+        match self {
+            Self::Release {
+                major,
+                minor,
+                patch,
+            } => Some((*major, *minor, patch.unwrap_or(0), 2, 0)),
+            Self::PreRelease {
+                major,
+                minor,
+                patch,
+                stage,
+                number,
+                ..
+            } => Some((*major, *minor, patch.unwrap_or(0), *stage as u8, *number)),
+            Self::Snapshot { .. } => None,
+        }
+    }
+}
+
+/// Checks whether `version` satisfies `spec`, which is either a single version (`1.21`) or an
+/// inclusive range (`1.19-1.20.4`) as used by Hangar's platform dependencies.
+pub fn spec_matches(spec: &str, version: &MinecraftVersion) -> bool {
+    if let Some((start, end)) = spec.split_once('-') {
+        if let (Some(start), Some(end)) =
+            (MinecraftVersion::parse(start), MinecraftVersion::parse(end))
+        {
+            return &start <= version && version <= &end;
+        }
+    }
+
+    MinecraftVersion::parse(spec).is_some_and(|v| &v == version)
+}
+
+/// Like [`spec_matches`], but also accepts versions on the same `major.minor` line as `version`,
+/// or up to `window` minor versions older.
+pub fn spec_within_window(spec: &str, version: &MinecraftVersion, window: u32) -> bool {
+    if spec_matches(spec, version) {
+        return true;
+    }
+
+    let upper = spec.split_once('-').map_or(spec, |(_, end)| end);
+
+    let (
+        Some(MinecraftVersion::Release { major, minor, .. }),
+        MinecraftVersion::Release {
+            major: target_major,
+            minor: target_minor,
+            ..
+        },
+    ) = (MinecraftVersion::parse(upper), version)
+    else {
+        return false;
+    };
+
+    major == *target_major && minor <= *target_minor && minor + window >= *target_minor
+}
+
+/// Compares free-form project version strings such as `2.19.1` or `5.4.0-beta.2`.
+/// Strings that cannot be parsed sort before those that can.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    Versioning::new(a).cmp(&Versioning::new(b))
+}
+
+/// Checks a project version against a constraint such as `~2.19`, `^5.4`, `<3.0` or `>=2.1, <3`.
+/// Only the leading numeric parts of the version are compared, so `5.4.0-beta.2` counts as `5.4.0`.
+/// Returns `None` if the constraint can't be parsed.
+pub fn satisfies_constraint(version: &str, constraint: &str) -> Option<bool> {
+    let version = numeric_parts(version)?;
+
+    let mut satisfied = true;
+    for part in constraint.split(',') {
+        let part = part.trim();
+        let op_len = part
+            .find(|c: char| c.is_ascii_digit() || c == 'v')
+            .unwrap_or(part.len());
+        let (op, bound) = part.split_at(op_len);
+        let bound = numeric_parts(bound)?;
+
+        let cmp = compare_parts(&version, &bound);
+        satisfied &= match op.trim() {
+            "" | "=" => cmp == Ordering::Equal,
+            ">" => cmp == Ordering::Greater,
+            ">=" => cmp != Ordering::Less,
+            "<" => cmp == Ordering::Less,
+            "<=" => cmp != Ordering::Greater,
+            // ~2.19 allows 2.19.x, ~2 allows 2.x
+            "~" => {
+                let mut upper = bound[..bound.len().min(2)].to_vec();
+                *upper.last_mut()? += 1;
+
+                cmp != Ordering::Less && compare_parts(&version, &upper) == Ordering::Less
+            }
+            // ^2.19 allows 2.x from 2.19 on, ^0.3 allows 0.3.x
+            "^" => {
+                let significant = bound
+                    .iter()
+                    .position(|p| *p != 0)
+                    .unwrap_or(bound.len() - 1);
+                let mut upper = bound[..=significant].to_vec();
+                upper[significant] += 1;
+
+                cmp != Ordering::Less && compare_parts(&version, &upper) == Ordering::Less
+            }
+            _ => return None,
+        };
+    }
+
+    Some(satisfied)
+}
+
+fn numeric_parts(input: &str) -> Option<Vec<u64>> {
+    let input = input.trim().trim_start_matches('v');
+    let end = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+
+    input[..end]
+        .trim_end_matches('.')
+        .split('.')
+        .map(|p| p.parse().ok())
+        .collect()
+}
+
+// Missing parts count as zero, so 2.19 and 2.19.0 are equal
+fn compare_parts(a: &[u64], b: &[u64]) -> Ordering {
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+fn parse_release(input: &str) -> Option<(u32, u32, Option<u32>)> {
+    let mut parts = input.split('.');
+
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = match parts.next() {
+        Some(p) => Some(p.parse().ok()?),
+        None => None,
+    };
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((major, minor, patch))
+}
+
+fn parse_snapshot(input: &str) -> Option<MinecraftVersion> {
+    let (year, rest) = input.split_once('w')?;
+
+    if year.len() != 2 || rest.len() != 3 {
+        return None;
+    }
+
+    let (week, revision) = rest.split_at(2);
+    let revision = revision.chars().next()?;
+
+    if !revision.is_ascii_lowercase() {
+        return None;
+    }
+
+    Some(MinecraftVersion::Snapshot {
+        year: year.parse().ok()?,
+        week: week.parse().ok()?,
+        revision,
+    })
+}
+
+impl fmt::Display for MinecraftVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Release {
+                major,
+                minor,
+                patch: Some(patch),
+            } => write!(f, "{major}.{minor}.{patch}"),
+            Self::Release {
+                major,
+                minor,
+                patch: None,
+            } => write!(f, "{major}.{minor}"),
+            Self::PreRelease { raw, .. } => write!(f, "{raw}"),
+            Self::Snapshot {
+                year,
+                week,
+                revision,
+            } => write!(f, "{year:02}w{week:02}{revision}"),
+        }
+    }
+}
+
+impl PartialEq for MinecraftVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for MinecraftVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (
+                Self::Snapshot {
+                    year,
+                    week,
+                    revision,
+                },
+                Self::Snapshot {
+                    year: other_year,
+                    week: other_week,
+                    revision: other_revision,
+                },
+            ) => Some((year, week, revision).cmp(&(other_year, other_week, other_revision))),
+            _ => Some(self.release_key()?.cmp(&other.release_key()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(input: &str) -> MinecraftVersion {
+        MinecraftVersion::parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_parse_releases() {
+        assert!(v("1.21").is_release());
+        assert!(v("1.20.4").is_release());
+        assert_eq!(v("1.21").to_string(), "1.21");
+        assert_eq!(v("1.20.4").to_string(), "1.20.4");
+        assert!(MinecraftVersion::parse("latest").is_none());
+        assert!(MinecraftVersion::parse("1").is_none());
+        assert!(MinecraftVersion::parse("1.2.3.4").is_none());
+    }
+
+    #[test]
+    fn test_parse_prereleases() {
+        assert!(matches!(
+            v("1.20.5-pre1"),
+            MinecraftVersion::PreRelease {
+                stage: Stage::PreRelease,
+                number: 1,
+                ..
+            }
+        ));
+        assert!(matches!(
+            v("1.16-rc1"),
+            MinecraftVersion::PreRelease {
+                stage: Stage::ReleaseCandidate,
+                ..
+            }
+        ));
+        assert!(matches!(
+            v("1.14 Pre-Release 2"),
+            MinecraftVersion::PreRelease { number: 2, .. }
+        ));
+        assert_eq!(v("1.7.10_pre4").to_string(), "1.7.10_pre4");
+    }
+
+    #[test]
+    fn test_parse_snapshots() {
+        assert!(v("24w14a").is_snapshot());
+        assert_eq!(v("24w14a").to_string(), "24w14a");
+        assert!(MinecraftVersion::parse("24w14").is_none());
+        assert!(MinecraftVersion::parse("20w14infinite").is_none());
+    }
+
+    #[test]
+    fn test_spec_matches() {
+        assert!(spec_matches("1.21", &v("1.21")));
+        assert!(spec_matches("1.21", &v("1.21.0")));
+        assert!(!spec_matches("1.21", &v("1.21.1")));
+        assert!(spec_matches("1.19-1.20.4", &v("1.19")));
+        assert!(spec_matches("1.19-1.20.4", &v("1.20.2")));
+        assert!(spec_matches("1.19-1.20.4", &v("1.20.4")));
+        assert!(!spec_matches("1.19-1.20.4", &v("1.20.5")));
+        assert!(spec_matches("1.20.5-pre1", &v("1.20.5-pre1")));
+        assert!(!spec_matches("garbage", &v("1.21")));
+    }
+
+    #[test]
+    fn test_spec_within_window() {
+        assert!(spec_within_window("1.21", &v("1.21.1"), 0));
+        assert!(spec_within_window("1.21.4", &v("1.21.1"), 0));
+        assert!(!spec_within_window("1.20.6", &v("1.21.1"), 0));
+        assert!(spec_within_window("1.20.6", &v("1.21.1"), 1));
+        assert!(spec_within_window("1.19-1.20.4", &v("1.21"), 1));
+        assert!(!spec_within_window("1.19-1.20.4", &v("1.22"), 1));
+        assert!(!spec_within_window("1.22", &v("1.21"), 5));
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("2.10.0", "2.9.1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0-beta.2", "1.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("3.0", "3.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_satisfies_constraint() {
+        let ok = |v, c| satisfies_constraint(v, c).unwrap();
+
+        assert!(ok("2.19.1", "~2.19"));
+        assert!(ok("2.19", "~2.19"));
+        assert!(!ok("2.20.0", "~2.19"));
+        assert!(ok("2.20.0", "^2.19"));
+        assert!(!ok("3.0.0", "^2.19"));
+        assert!(!ok("0.4.0", "^0.3"));
+        assert!(ok("2.9.9", "<3.0"));
+        assert!(!ok("3.0", "<3.0"));
+        assert!(ok("2.5.0", ">=2.1, <3"));
+        assert!(!ok("2.0.9", ">=2.1, <3"));
+        assert!(ok("5.4.0-beta.2", "=5.4"));
+        assert!(ok("v1.2.3", "1.2.3"));
+        assert!(satisfies_constraint("1.0", "!1.0").is_none());
+        assert!(satisfies_constraint("dev-build", "<3").is_none());
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(v("1.20.1") > v("1.20"));
+        assert!(v("1.20.10") > v("1.20.9"));
+        assert_eq!(v("1.20"), v("1.20.0"));
+        assert!(v("1.20.5-pre1") < v("1.20.5-rc1"));
+        assert!(v("1.20.5-rc1") < v("1.20.5"));
+        assert!(v("1.20.5-pre1") > v("1.20.4"));
+        assert!(v("1.7.10_pre4") < v("1.7.10"));
+        assert!(v("24w14a") > v("24w13b"));
+        assert!(v("24w14b") > v("24w14a"));
+        assert_eq!(v("24w14a").partial_cmp(&v("1.20.5")), None);
+    }
+}
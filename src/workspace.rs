@@ -0,0 +1,240 @@
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use serde::Deserialize;
+
+use crate::{
+    network, plugin,
+    server::{
+        eula,
+        lockfile::{self, Lockfile},
+        properties::{self, Properties},
+    },
+};
+
+pub const WORKSPACE_PATH: &str = "workspace.toml";
+
+#[derive(Debug, Subcommand)]
+pub enum Workspace {
+    /// Set up every server in workspace.toml, installing their projects
+    Install {
+        /// Do not ask for confirmation before downloading
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+
+    /// List the servers in workspace.toml, which other commands can work on with --server
+    List,
+}
+
+#[derive(Deserialize)]
+struct Config {
+    /// Settings every server starts from
+    #[serde(default)]
+    defaults: Settings,
+    servers: BTreeMap<String, Settings>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Settings {
+    minecraft_version: Option<String>,
+    loader: Option<String>,
+    /// Loader version, latest unless given
+    version: Option<String>,
+    /// Defaults to the name of the server
+    directory: Option<String>,
+    /// Projects to install, Modrinth IDs or slugs unless prefixed with `hangar:`. Those of a
+    /// server are installed along with the default ones
+    plugins: Vec<String>,
+    /// Values written to server.properties, a server's own override the default ones
+    properties: BTreeMap<String, String>,
+}
+
+// A server of the workspace with its defaults filled in
+struct Server<'a> {
+    name: &'a str,
+    directory: PathBuf,
+    minecraft_version: &'a str,
+    loader: &'a str,
+    version: &'a str,
+    plugins: Vec<String>,
+    properties: BTreeMap<&'a str, &'a str>,
+}
+
+impl Config {
+    fn load() -> Result<Self> {
+        let text = fs::read_to_string(WORKSPACE_PATH)
+            .map_err(|e| anyhow!("failed to read {WORKSPACE_PATH}: {e}"))?;
+        let config: Self = toml::from_str(&text)?;
+
+        for server in config.servers.values().chain([&config.defaults]) {
+            for (key, value) in &server.properties {
+                properties::validate(key, value)?;
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn server<'a>(&'a self, name: &'a str, settings: &'a Settings) -> Result<Server<'a>> {
+        let defaults = &self.defaults;
+        let pick = |own: &'a Option<String>, default: &'a Option<String>| {
+            own.as_deref().or(default.as_deref())
+        };
+
+        let mut plugins = defaults.plugins.clone();
+        for plugin in &settings.plugins {
+            if !plugins.contains(plugin) {
+                plugins.push(plugin.clone());
+            }
+        }
+
+        Ok(Server {
+            name,
+            directory: PathBuf::from(settings.directory.as_deref().unwrap_or(name)),
+            minecraft_version: pick(&settings.minecraft_version, &defaults.minecraft_version)
+                .ok_or_else(|| {
+                    anyhow!("{name} has no minecraft_version and there is no default")
+                })?,
+            loader: pick(&settings.loader, &defaults.loader)
+                .ok_or_else(|| anyhow!("{name} has no loader and there is no default"))?,
+            version: pick(&settings.version, &defaults.version).unwrap_or("latest"),
+            plugins,
+            properties: defaults
+                .properties
+                .iter()
+                .chain(&settings.properties)
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+        })
+    }
+
+    fn servers(&self) -> Result<Vec<Server<'_>>> {
+        self.servers
+            .iter()
+            .map(|(name, settings)| self.server(name, settings))
+            .collect()
+    }
+}
+
+pub fn action(workspace: &Workspace) -> Result<()> {
+    let config = Config::load()?;
+
+    match workspace {
+        Workspace::Install { yes } => install_all(&config, *yes),
+        Workspace::List => list(&config),
+    }
+}
+
+// Where a server of the workspace in the current directory lives, for --server
+pub fn directory(name: &str) -> Result<PathBuf> {
+    let config = Config::load()?;
+
+    let settings = config.servers.get(name).ok_or_else(|| {
+        let names: Vec<&str> = config.servers.keys().map(String::as_str).collect();
+        anyhow!(
+            "no server named {name} in {WORKSPACE_PATH}, expected one of {}",
+            names.join(", ")
+        )
+    })?;
+
+    Ok(config.server(name, settings)?.directory)
+}
+
+fn install_all(config: &Config, yes: bool) -> Result<()> {
+    let root = env::current_dir()?;
+
+    // Servers of a workspace usually share most of their projects
+    plugin::share_downloads(root.join(".mup").join("cache"));
+
+    for server in config.servers()? {
+        println!(
+            "{}: {} {} in {}",
+            server.name,
+            server.loader,
+            server.minecraft_version,
+            server.directory.display()
+        );
+
+        network::in_directory(&server.directory, || install(&server, yes))
+            .map_err(|e| anyhow!("{}: {e}", server.name))?;
+    }
+
+    Ok(())
+}
+
+fn install(server: &Server, yes: bool) -> Result<()> {
+    let mut lockfile = if let Some(lockfile) = Lockfile::existing()? {
+        if lockfile.loader.name != server.loader
+            || lockfile.loader.minecraft_version != server.minecraft_version
+        {
+            println!(
+                "{} runs {} {}, which differs from {WORKSPACE_PATH}",
+                server.name, lockfile.loader.name, lockfile.loader.minecraft_version
+            );
+        }
+
+        lockfile
+    } else {
+        let mut lockfile = Lockfile::with_params(server.minecraft_version, server.loader, false)?;
+        lockfile.loader.version = server.version.to_string();
+        lockfile.loader.pin()?;
+        lockfile.save()?;
+
+        lockfile
+    };
+
+    if !plugin::confirm_downloads(&lockfile.mods, yes)? {
+        return Ok(());
+    }
+
+    lockfile.fetch_loader()?;
+
+    for entry in &lockfile.mods {
+        plugin::download_plugin(&lockfile, entry)?;
+    }
+
+    network::add_missing(&server.plugins)?;
+
+    if !server.properties.is_empty() {
+        let mut properties = Properties::load()?;
+        for (key, value) in &server.properties {
+            properties.set(key, value);
+        }
+        properties.save()?;
+    }
+
+    if lockfile.loader.is_bedrock() || lockfile.loader.is_proxy() {
+        return Ok(());
+    }
+
+    eula::accept()
+}
+
+fn list(config: &Config) -> Result<()> {
+    let rows: Vec<Vec<String>> = config
+        .servers()?
+        .iter()
+        .map(|server| {
+            vec![
+                server.name.to_string(),
+                format!("{} {}", server.loader, server.minecraft_version),
+                server.directory.display().to_string(),
+                String::from(if lockfile::exists_in(&server.directory) {
+                    "installed"
+                } else {
+                    "not installed"
+                }),
+            ]
+        })
+        .collect();
+
+    println!(
+        "{}",
+        mup::format_table(&["NAME", "SERVER", "DIRECTORY", "STATUS"], &rows)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,344 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression, Crc};
+use log::info;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+
+/// Extracts a zip archive into `dest`, skipping every entry for which `skip` returns true.
+/// Only stored and deflated entries are supported, which covers what the official
+/// distributions use.
+pub fn extract(archive: &[u8], dest: &Path, skip: impl Fn(&Path) -> bool) -> Result<()> {
+    extract_mapped(archive, dest, |path| {
+        (!skip(path)).then(|| path.to_path_buf())
+    })
+}
+
+/// Extracts only the entries below `dir`, relative to it, such as the overrides of a modpack.
+pub fn extract_dir(archive: &[u8], dir: &str, dest: &Path) -> Result<()> {
+    extract_mapped(archive, dest, |path| {
+        path.strip_prefix(dir)
+            .ok()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+    })
+}
+
+/// Reads a single file from the archive, or `None` if there is no entry with that name.
+pub fn read(archive: &[u8], name: &str) -> Result<Option<Vec<u8>>> {
+    let Some(entry) = entries(archive)?.into_iter().find(|e| e.name == name) else {
+        return Ok(None);
+    };
+
+    let mut data = vec![];
+    entry.reader()?.read_to_end(&mut data)?;
+
+    Ok(Some(data))
+}
+
+/// Builds a deflated zip archive holding `files`, given as paths and their contents.
+pub fn write(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut archive = vec![];
+    let mut central = vec![];
+
+    for (name, data) in files {
+        let offset = u32::try_from(archive.len())?;
+
+        let mut crc = Crc::new();
+        crc.update(data);
+
+        let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+
+        // Version, flags, method, time and date (1980-01-01), then crc and sizes
+        let mut header = vec![];
+        header.extend(20u16.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(8u16.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(33u16.to_le_bytes());
+        header.extend(crc.sum().to_le_bytes());
+        header.extend(u32::try_from(compressed.len())?.to_le_bytes());
+        header.extend(u32::try_from(data.len())?.to_le_bytes());
+        header.extend(u16::try_from(name.len())?.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+
+        archive.extend(LOCAL_FILE_HEADER.to_le_bytes());
+        archive.extend(&header);
+        archive.extend(name.as_bytes());
+        archive.extend(&compressed);
+
+        central.extend(CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+        central.extend(20u16.to_le_bytes());
+        central.extend(&header);
+        // Comment length, disk number and internal attributes
+        central.extend([0; 6]);
+        central.extend((0o644u32 << 16).to_le_bytes());
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+
+    let count = u16::try_from(files.len())?;
+    let central_offset = u32::try_from(archive.len())?;
+    archive.extend(&central);
+    archive.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    archive.extend([0; 4]);
+    archive.extend(count.to_le_bytes());
+    archive.extend(count.to_le_bytes());
+    archive.extend(u32::try_from(central.len())?.to_le_bytes());
+    archive.extend(central_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+
+    Ok(archive)
+}
+
+struct Entry<'a> {
+    name: String,
+    method: u16,
+    attributes: u32,
+    data: &'a [u8],
+}
+
+impl Entry<'_> {
+    fn reader(&self) -> Result<Box<dyn Read + '_>> {
+        match self.method {
+            0 => Ok(Box::new(self.data)),
+            8 => Ok(Box::new(DeflateDecoder::new(self.data))),
+            method => Err(anyhow!(
+                "unsupported compression method {method} for {}",
+                self.name
+            )),
+        }
+    }
+}
+
+// Walks the central directory, which is authoritative over the local headers
+fn entries(archive: &[u8]) -> Result<Vec<Entry<'_>>> {
+    let eocd = (0..archive.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_at(archive, i) == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(|| anyhow!("not a zip archive"))?;
+
+    let count = u16_at(archive, eocd + 10).ok_or_else(truncated)?;
+    let mut offset = u32_at(archive, eocd + 16).ok_or_else(truncated)? as usize;
+    let mut entries = vec![];
+
+    for _ in 0..count {
+        if u32_at(archive, offset) != Some(CENTRAL_DIRECTORY_HEADER) {
+            return Err(anyhow!("corrupt zip central directory"));
+        }
+
+        let method = u16_at(archive, offset + 10).ok_or_else(truncated)?;
+        let compressed = u32_at(archive, offset + 20).ok_or_else(truncated)? as usize;
+        let name_len = u16_at(archive, offset + 28).ok_or_else(truncated)? as usize;
+        let extra_len = u16_at(archive, offset + 30).ok_or_else(truncated)? as usize;
+        let comment_len = u16_at(archive, offset + 32).ok_or_else(truncated)? as usize;
+        let attributes = u32_at(archive, offset + 38).ok_or_else(truncated)?;
+        let header = u32_at(archive, offset + 42).ok_or_else(truncated)? as usize;
+
+        let name = archive
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        offset += 46 + name_len + extra_len + comment_len;
+
+        if u32_at(archive, header) != Some(LOCAL_FILE_HEADER) {
+            return Err(anyhow!("corrupt zip entry {name}"));
+        }
+
+        let local_name_len = u16_at(archive, header + 26).ok_or_else(truncated)? as usize;
+        let local_extra_len = u16_at(archive, header + 28).ok_or_else(truncated)? as usize;
+        let start = header + 30 + local_name_len + local_extra_len;
+        let data = archive
+            .get(start..start + compressed)
+            .ok_or_else(truncated)?;
+
+        entries.push(Entry {
+            name,
+            method,
+            attributes,
+            data,
+        });
+    }
+
+    Ok(entries)
+}
+
+// `map` gives the path an entry is extracted to relative to `dest`, or None to skip it
+fn extract_mapped(
+    archive: &[u8],
+    dest: &Path,
+    map: impl Fn(&Path) -> Option<PathBuf>,
+) -> Result<()> {
+    for entry in entries(archive)? {
+        let Some(relative) = map(&safe_path(&entry.name)?) else {
+            info!("skipping {}", entry.name);
+            continue;
+        };
+
+        let path = dest.join(&relative);
+
+        if entry.name.ends_with('/') {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+
+        if let Some(prefix) = path.parent() {
+            fs::create_dir_all(prefix)?;
+        }
+
+        let mut output = fs::File::create(&path)?;
+        io::copy(&mut entry.reader()?, &mut output)?;
+
+        set_mode(&path, entry.attributes >> 16)?;
+    }
+
+    Ok(())
+}
+
+fn truncated() -> anyhow::Error {
+    anyhow!("truncated zip archive")
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+// Entries must stay inside the destination directory
+fn safe_path(name: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(name);
+
+    if path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(path)
+    } else {
+        Err(anyhow!(
+            "refusing to extract {name} outside of the destination"
+        ))
+    }
+}
+
+// Keeps executables such as the server binary executable
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if mode & 0o777 != 0 {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds an uncompressed zip holding `dir/` and `dir/hello.txt`
+    fn archive() -> Vec<u8> {
+        let mut zip = vec![];
+        let mut central = vec![];
+
+        for (name, data) in [("dir/", &b""[..]), ("dir/hello.txt", &b"hello"[..])] {
+            let offset = zip.len() as u32;
+
+            zip.extend(LOCAL_FILE_HEADER.to_le_bytes());
+            zip.extend([0; 22]);
+            zip[offset as usize + 18..offset as usize + 22]
+                .copy_from_slice(&(data.len() as u32).to_le_bytes());
+            zip.extend((name.len() as u16).to_le_bytes());
+            zip.extend(0u16.to_le_bytes());
+            zip.extend(name.as_bytes());
+            zip.extend(data);
+
+            central.extend(CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+            central.extend([0; 16]);
+            central.extend((data.len() as u32).to_le_bytes());
+            central.extend((data.len() as u32).to_le_bytes());
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend([0; 8]);
+            central.extend((0o644u32 << 16).to_le_bytes());
+            central.extend(offset.to_le_bytes());
+            central.extend(name.as_bytes());
+        }
+
+        let central_offset = zip.len() as u32;
+        zip.extend(&central);
+        zip.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        zip.extend([0; 6]);
+        zip.extend(2u16.to_le_bytes());
+        zip.extend((central.len() as u32).to_le_bytes());
+        zip.extend(central_offset.to_le_bytes());
+        zip.extend(0u16.to_le_bytes());
+
+        zip
+    }
+
+    #[test]
+    fn test_extract() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        extract(&archive(), dir.path(), |_| false)?;
+        assert_eq!(
+            fs::read_to_string(dir.path().join("dir/hello.txt"))?,
+            "hello"
+        );
+
+        let skipped = tempfile::tempdir()?;
+        extract(&archive(), skipped.path(), |p| p.ends_with("hello.txt"))?;
+        assert!(!skipped.path().join("dir/hello.txt").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_and_extract_dir() -> Result<()> {
+        assert_eq!(read(&archive(), "dir/hello.txt")?, Some(b"hello".to_vec()));
+        assert_eq!(read(&archive(), "missing.txt")?, None);
+
+        let dir = tempfile::tempdir()?;
+        extract_dir(&archive(), "dir", dir.path())?;
+        assert_eq!(fs::read_to_string(dir.path().join("hello.txt"))?, "hello");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write() -> Result<()> {
+        let files = [(String::from("dir/hello.txt"), b"hello".to_vec())];
+
+        assert_eq!(
+            read(&write(&files)?, "dir/hello.txt")?,
+            Some(b"hello".to_vec())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_path() {
+        assert!(safe_path("worlds/level.dat").is_ok());
+        assert!(safe_path("../escape").is_err());
+        assert!(safe_path("/etc/passwd").is_err());
+    }
+}